@@ -0,0 +1,765 @@
+// Address book: saved places with nicknames, tags, notes and cached station IDs
+//
+// Stored as pretty-printed JSON (`addresses.json`) in the data directory. The
+// legacy `addresses.txt` (`ADDRESS;LAT;LON` per line) is read once for migration.
+
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::io_error_with_path;
+use crate::geocode::{geocode_address, geocode_batch, BatchMatch, GeocodedAddress, GeocoderKind, MatchStatus};
+use crate::AppError;
+
+pub const ADDRESS_STORE_FILE: &str = "addresses.json";
+pub const LEGACY_ADDRESS_FILE: &str = "addresses.txt";
+
+// Bumped if the file layout changes incompatibly
+const STORE_VERSION: u32 = 1;
+
+/// One saved place.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredAddress {
+    /// Stable identifier, never reused after a delete
+    pub id: u32,
+    /// Short name such as "Home" or "Cabin"; unique ignoring case
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nickname: Option<String>,
+    /// Normalized (matched) street address
+    pub address: String,
+    pub lat: f64,
+    pub lon: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// The geocoder that resolved this address; unknown for migrated entries
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub provider: Option<GeocoderKind>,
+    /// How the Census batch geocoder last matched this address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_status: Option<MatchStatus>,
+    /// NWS observation station last used for this address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nws_station_id: Option<String>,
+    /// NOAA tide station last used for this address
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tide_station_id: Option<String>,
+}
+
+impl StoredAddress {
+    /// "Home (233 E MAIN ST, BOZEMAN, MT, 59715)", or just the address without a nickname.
+    pub fn label(&self) -> String {
+        match &self.nickname {
+            Some(nickname) => format!("{} ({})", nickname, self.address),
+            None => self.address.clone(),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    /// Marks the entry as modified now.
+    pub fn touch(&mut self) {
+        self.updated_at = Utc::now();
+    }
+}
+
+/// The saved addresses, in the order the user arranged them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddressBook {
+    version: u32,
+    next_id: u32,
+    addresses: Vec<StoredAddress>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl AddressBook {
+    /// The store in the configured data directory.
+    pub fn default_path() -> PathBuf {
+        crate::config::data_dir().join(ADDRESS_STORE_FILE)
+    }
+
+    /// An empty book that will be saved to `path`.
+    pub fn new(path: &Path) -> AddressBook {
+        AddressBook { version: STORE_VERSION, next_id: 1, addresses: Vec::new(), path: path.to_path_buf() }
+    }
+
+    /// Reads the store; a missing file yields an empty book.
+    pub fn load(path: &Path) -> Result<AddressBook, AppError> {
+        if !path.exists() {
+            return Ok(AddressBook::new(path));
+        }
+        let text = fs::read_to_string(path).map_err(|e| io_error_with_path(e, path))?;
+        let mut book: AddressBook = serde_json::from_str(&text)
+            .map_err(|e| AppError::Config(format!("{}: {}", path.display(), e)))?;
+        if book.version > STORE_VERSION {
+            return Err(AppError::Config(format!(
+                "{} was written by a newer reather (store version {}, this build reads {})",
+                path.display(), book.version, STORE_VERSION
+            )));
+        }
+        book.path = path.to_path_buf();
+        // Guard against hand-edited files
+        let max_id = book.addresses.iter().map(|a| a.id).max().unwrap_or(0);
+        book.next_id = book.next_id.max(max_id + 1);
+        Ok(book)
+    }
+
+    /// Writes the store, replacing the previous file only once the new one is complete.
+    pub fn save(&self) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, json).map_err(|e| io_error_with_path(e, &tmp_path))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| io_error_with_path(e, &self.path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn addresses(&self) -> &[StoredAddress] {
+        &self.addresses
+    }
+
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Appends a new entry and returns it for further edits.
+    pub fn add(&mut self, address: &str, lat: f64, lon: f64) -> &mut StoredAddress {
+        let now = Utc::now();
+        self.addresses.push(StoredAddress {
+            id: self.next_id,
+            nickname: None,
+            address: address.to_string(),
+            lat,
+            lon,
+            tags: Vec::new(),
+            notes: None,
+            created_at: now,
+            updated_at: now,
+            provider: None,
+            match_status: None,
+            nws_station_id: None,
+            tide_station_id: None,
+        });
+        self.next_id += 1;
+        self.addresses.last_mut().expect("entry was just pushed")
+    }
+
+    /// Appends a geocoder match, recording which provider resolved it.
+    pub fn add_geocoded(&mut self, geocoded: &GeocodedAddress) -> &mut StoredAddress {
+        let entry = self.add(&geocoded.matched_address, geocoded.lat, geocoded.lon);
+        entry.provider = Some(geocoded.provider);
+        entry
+    }
+
+    pub fn get(&self, id: u32) -> Option<&StoredAddress> {
+        self.addresses.iter().find(|a| a.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: u32) -> Option<&mut StoredAddress> {
+        self.addresses.iter_mut().find(|a| a.id == id)
+    }
+
+    /// Looks an entry up by id ("3") or nickname ("home").
+    pub fn find(&self, key: &str) -> Option<&StoredAddress> {
+        let key = key.trim();
+        if let Ok(id) = key.parse::<u32>() {
+            return self.get(id);
+        }
+        self.find_by_nickname(key)
+    }
+
+    pub fn find_by_nickname(&self, nickname: &str) -> Option<&StoredAddress> {
+        let nickname = nickname.trim();
+        self.addresses.iter().find(|a| a.nickname.as_deref().is_some_and(|n| n.eq_ignore_ascii_case(nickname)))
+    }
+
+    /// An existing entry with the same address (ignoring case) or practically the same coordinates.
+    pub fn find_duplicate(&self, address: &str, lat: f64, lon: f64) -> Option<&StoredAddress> {
+        self.addresses
+            .iter()
+            .find(|a| a.address.eq_ignore_ascii_case(address.trim()))
+            .or_else(|| self.find_near(lat, lon))
+    }
+
+    /// An existing entry at practically the same coordinates.
+    pub fn find_near(&self, lat: f64, lon: f64) -> Option<&StoredAddress> {
+        // ~10 m; geocoders return slightly different points for the same address
+        const SAME_PLACE_DEGREES: f64 = 0.0001;
+        self.addresses.iter().find(|a| (a.lat - lat).abs() < SAME_PLACE_DEGREES && (a.lon - lon).abs() < SAME_PLACE_DEGREES)
+    }
+
+    /// Checks that `nickname` is not used by an entry other than `id`.
+    pub fn check_nickname(&self, nickname: &str, id: Option<u32>) -> Result<(), AppError> {
+        if nickname.trim().is_empty() {
+            return Err(AppError::UserInput("Nickname cannot be blank.".to_string()));
+        }
+        if nickname.trim().parse::<u32>().is_ok() {
+            return Err(AppError::UserInput("Nickname cannot be a number; numbers refer to address ids.".to_string()));
+        }
+        match self.find_by_nickname(nickname) {
+            Some(existing) if Some(existing.id) != id => {
+                Err(AppError::UserInput(format!("Nickname '{}' is already used by address {}.", nickname.trim(), existing.id)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<StoredAddress> {
+        let index = self.addresses.iter().position(|a| a.id == id)?;
+        Some(self.addresses.remove(index))
+    }
+
+    /// Moves an entry to `position` (0-based, clamped to the end of the list).
+    pub fn move_to(&mut self, id: u32, position: usize) -> Result<(), AppError> {
+        let entry = self.remove(id).ok_or_else(|| AppError::UserInput(format!("No address with id {}.", id)))?;
+        let position = position.min(self.addresses.len());
+        self.addresses.insert(position, entry);
+        Ok(())
+    }
+}
+
+/// A line of the legacy `addresses.txt`; `coords` is `None` for address-only lines.
+#[derive(Debug, Clone)]
+pub struct LegacyAddress {
+    pub address: String,
+    pub coords: Option<(f64, f64)>,
+}
+
+/// Reads the legacy semicolon file, skipping comments and blank lines.
+/// Returns the usable entries and the lines that could not be parsed.
+pub fn read_legacy_file(path: &Path) -> Result<(Vec<LegacyAddress>, Vec<String>), AppError> {
+    let file = fs::File::open(path).map_err(|e| io_error_with_path(e, path))?;
+    let mut entries = Vec::new();
+    let mut malformed = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| io_error_with_path(e, path))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let parts: Vec<&str> = line.split(';').map(|p| p.trim()).collect();
+        match parts.as_slice() {
+            [address] => entries.push(LegacyAddress { address: address.to_string(), coords: None }),
+            [address, lat, lon] => match (lat.parse::<f64>(), lon.parse::<f64>()) {
+                (Ok(lat), Ok(lon)) => entries.push(LegacyAddress { address: address.to_string(), coords: Some((lat, lon)) }),
+                _ => malformed.push(line.to_string()),
+            },
+            _ => malformed.push(line.to_string()),
+        }
+    }
+    Ok((entries, malformed))
+}
+
+/// Splits "ski, family" into trimmed, non-empty tags.
+pub fn split_tags(value: &str) -> Vec<String> {
+    value.split(',').map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).collect()
+}
+
+/// A row of an import spreadsheet, before geocoding.
+#[derive(Debug, Clone)]
+pub struct ImportRow {
+    /// Line number in the file, counting the header as line 1
+    pub line: usize,
+    /// Address text to geocode
+    pub query: String,
+    pub nickname: Option<String>,
+    pub tags: Vec<String>,
+    pub notes: Option<String>,
+}
+
+/// What happened to one import row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportStatus {
+    Added,
+    /// Already in the book; `id` is the existing entry
+    Duplicate,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportOutcome {
+    pub line: usize,
+    pub query: String,
+    pub status: ImportStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u32>,
+    /// Census batch match status for the row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_status: Option<MatchStatus>,
+    /// The geocoder that resolved the row
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<GeocoderKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+// Header names accepted for each import column, compared ignoring case
+const ADDRESS_COLUMNS: [&str; 3] = ["address", "full address", "full_address"];
+const STREET_COLUMNS: [&str; 3] = ["street", "street address", "address1"];
+const CITY_COLUMNS: [&str; 2] = ["city", "municipality"];
+const STATE_COLUMNS: [&str; 2] = ["state", "region"];
+const ZIP_COLUMNS: [&str; 4] = ["zip", "zip code", "zipcode", "postal code"];
+const NICKNAME_COLUMNS: [&str; 2] = ["nickname", "name"];
+const TAGS_COLUMNS: [&str; 2] = ["tags", "tag"];
+const NOTES_COLUMNS: [&str; 2] = ["notes", "note"];
+
+/// Reads an import CSV. The header must name an `address` column, or
+/// `street`/`city`/`state`/`zip` columns that are joined into one address;
+/// `nickname`, `tags` (comma-separated) and `notes` are optional.
+/// Rows without any address text are returned as failed outcomes.
+pub fn read_import_csv(path: &Path) -> Result<(Vec<ImportRow>, Vec<ImportOutcome>), AppError> {
+    let bytes = fs::read(path).map_err(|e| io_error_with_path(e, path))?;
+    let mut rdr = csv::ReaderBuilder::new().flexible(true).trim(csv::Trim::All).from_reader(bytes.as_slice());
+    // The reader skips empty lines but reports a record as starting before them
+    let line_of = |position: &csv::Position| {
+        let skipped = bytes[position.byte() as usize..].iter().take_while(|b| matches!(b, b'\r' | b'\n')).filter(|b| **b == b'\n').count();
+        position.line() as usize + skipped
+    };
+    let csv_error = |e: csv::Error| AppError::UserInput(format!("{}: {}", path.display(), e));
+    let headers = rdr.headers().map_err(csv_error)?.clone();
+    let column = |names: &[&str]| headers.iter().position(|h| names.iter().any(|n| h.eq_ignore_ascii_case(n)));
+
+    let address_col = column(&ADDRESS_COLUMNS);
+    let part_cols: Vec<usize> = [&STREET_COLUMNS[..], &CITY_COLUMNS, &STATE_COLUMNS, &ZIP_COLUMNS]
+        .iter()
+        .filter_map(|names| column(names))
+        .collect();
+    if address_col.is_none() && part_cols.is_empty() {
+        return Err(AppError::UserInput(format!(
+            "{}: the header needs an 'address' column, or 'street', 'city', 'state' and 'zip' columns.",
+            path.display()
+        )));
+    }
+    let (nickname_col, tags_col, notes_col) = (column(&NICKNAME_COLUMNS), column(&TAGS_COLUMNS), column(&NOTES_COLUMNS));
+
+    let mut rows = Vec::new();
+    let mut failed = Vec::new();
+    for (index, record) in rdr.records().enumerate() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(index + 2, line_of);
+                failed.push(ImportOutcome { line, query: String::new(), status: ImportStatus::Failed, id: None, match_status: None, provider: None, message: Some(e.to_string()) });
+                continue;
+            }
+        };
+        let line = record.position().map_or(index + 2, line_of);
+        let field = |col: Option<usize>| col.and_then(|c| record.get(c)).filter(|v| !v.is_empty()).map(str::to_string);
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        let query = match address_col.and_then(|c| record.get(c)).filter(|v| !v.is_empty()) {
+            Some(address) => address.to_string(),
+            None => part_cols.iter().filter_map(|c| record.get(*c)).filter(|v| !v.is_empty()).collect::<Vec<_>>().join(", "),
+        };
+        if query.is_empty() {
+            failed.push(ImportOutcome { line, query, status: ImportStatus::Failed, id: None, match_status: None, provider: None, message: Some("No address in this row".to_string()) });
+            continue;
+        }
+        rows.push(ImportRow {
+            line,
+            query,
+            nickname: field(nickname_col),
+            tags: field(tags_col)
+                .map(|t| split_tags(&t))
+                .unwrap_or_default(),
+            notes: field(notes_col),
+        });
+    }
+    Ok((rows, failed))
+}
+
+impl AddressBook {
+    /// Geocodes the rows with one Census batch request and adds the matches,
+    /// skipping rows whose matched address or coordinates are already in the book.
+    /// Rows the batch could not match (or matched several ways) go through the
+    /// configured geocoder chain instead. One outcome per row, in file order; the
+    /// caller saves the book.
+    pub async fn import_rows(&mut self, rows: Vec<ImportRow>) -> Result<Vec<ImportOutcome>, AppError> {
+        let queries: Vec<String> = rows.iter().map(|r| r.query.clone()).collect();
+        let matches = geocode_batch(&queries).await?;
+        let mut outcomes = Vec::with_capacity(rows.len());
+        for (row, batch_match) in rows.into_iter().zip(matches) {
+            let match_status = Some(batch_match.status);
+            let resolved = match batch_match.geocoded {
+                Some(geocoded) => Ok(Some(geocoded)),
+                None => geocode_address(&row.query).await,
+            };
+            let provider = resolved.as_ref().ok().and_then(Option::as_ref).map(|g| g.provider);
+            let outcome = |status, id, message: String| ImportOutcome {
+                line: row.line,
+                query: row.query.clone(),
+                status,
+                id,
+                match_status,
+                provider,
+                message: Some(message),
+            };
+            let geocoded = match resolved {
+                Ok(Some(geocoded)) => geocoded,
+                Ok(None) => {
+                    outcomes.push(outcome(ImportStatus::Failed, None, format!("No geocoding match ({})", batch_match.status.as_str())));
+                    continue;
+                }
+                Err(e) => {
+                    outcomes.push(outcome(ImportStatus::Failed, None, format!("No batch geocoding match ({}): {}", batch_match.status.as_str(), e)));
+                    continue;
+                }
+            };
+            if let Some(existing) = self.find_duplicate(&geocoded.matched_address, geocoded.lat, geocoded.lon) {
+                outcomes.push(outcome(ImportStatus::Duplicate, Some(existing.id), format!("Already saved as {}", existing.label())));
+                continue;
+            }
+            if let Some(nickname) = &row.nickname {
+                if let Err(e) = self.check_nickname(nickname, None) {
+                    outcomes.push(outcome(ImportStatus::Failed, None, e.to_string()));
+                    continue;
+                }
+            }
+            let entry = self.add_geocoded(&geocoded);
+            entry.nickname = row.nickname.clone();
+            entry.tags = row.tags.clone();
+            entry.notes = row.notes.clone();
+            entry.match_status = match_status;
+            let id = entry.id;
+            outcomes.push(outcome(ImportStatus::Added, Some(id), geocoded.matched_address));
+        }
+        Ok(outcomes)
+    }
+
+    /// Re-geocodes the Census (or migrated) entries with one Census batch request
+    /// and records each match status. Entries the batch could not match (or matched
+    /// several ways) go through the configured geocoder chain instead. Matched
+    /// entries take the new address, coordinates and provider; entries without any
+    /// match keep theirs. Entries resolved by Nominatim or typed as coordinates are
+    /// left alone.
+    /// Returns (id, previous address, match) per re-geocoded entry.
+    pub async fn normalize(&mut self) -> Result<Vec<(u32, String, BatchMatch)>, AppError> {
+        let is_census = |a: &StoredAddress| a.provider.is_none_or(|p| p == GeocoderKind::Census);
+        let queries: Vec<String> = self.addresses.iter().filter(|a| is_census(a)).map(|a| a.address.clone()).collect();
+        let matches = geocode_batch(&queries).await?;
+        let mut results = Vec::with_capacity(matches.len());
+        for (entry, mut batch_match) in self.addresses.iter_mut().filter(|a| is_census(a)).zip(matches) {
+            let previous = entry.address.clone();
+            entry.match_status = Some(batch_match.status);
+            if batch_match.geocoded.is_none() {
+                // A lookup error leaves the entry as it was, like a miss
+                batch_match.geocoded = geocode_address(&entry.address).await.ok().flatten();
+            }
+            if let Some(geocoded) = &batch_match.geocoded {
+                entry.address = geocoded.matched_address.clone();
+                entry.lat = geocoded.lat;
+                entry.lon = geocoded.lon;
+                entry.provider = Some(geocoded.provider);
+            }
+            entry.touch();
+            results.push((entry.id, previous, batch_match));
+        }
+        Ok(results)
+    }
+}
+
+/// File formats for `addresses export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    /// A GeoJSON FeatureCollection of points
+    GeoJson,
+    /// vCard 4.0, one card per address
+    VCard,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "geojson" | "json" => Ok(ExportFormat::GeoJson),
+            "vcard" | "vcf" => Ok(ExportFormat::VCard),
+            other => Err(AppError::UserInput(format!("Unknown export format '{}'. Use csv, geojson or vcard.", other))),
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::GeoJson => "geojson",
+            ExportFormat::VCard => "vcard",
+        })
+    }
+}
+
+impl ExportFormat {
+    /// Guesses the format from a file extension (.csv, .geojson/.json, .vcf/.vcard).
+    pub fn from_path(path: &Path) -> Option<ExportFormat> {
+        path.extension().and_then(|e| e.to_str()).and_then(|e| e.parse().ok())
+    }
+}
+
+/// Renders entries in `format`.
+pub fn export(entries: &[&StoredAddress], format: ExportFormat) -> Result<String, AppError> {
+    match format {
+        ExportFormat::Csv => export_csv(entries),
+        ExportFormat::GeoJson => Ok(serde_json::to_string_pretty(&export_geojson(entries))? + "\n"),
+        ExportFormat::VCard => Ok(entries.iter().map(|e| export_vcard(e)).collect()),
+    }
+}
+
+// Same columns read_import_csv accepts, so an export can be imported elsewhere
+fn export_csv(entries: &[&StoredAddress]) -> Result<String, AppError> {
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    let csv_error = |e: csv::Error| AppError::Api(format!("Could not write CSV: {}", e));
+    wtr.write_record(["id", "nickname", "address", "lat", "lon", "tags", "notes", "created_at", "updated_at"]).map_err(csv_error)?;
+    for entry in entries {
+        wtr.write_record([
+            entry.id.to_string(),
+            entry.nickname.clone().unwrap_or_default(),
+            entry.address.clone(),
+            entry.lat.to_string(),
+            entry.lon.to_string(),
+            entry.tags.join(","),
+            entry.notes.clone().unwrap_or_default(),
+            entry.created_at.to_rfc3339(),
+            entry.updated_at.to_rfc3339(),
+        ])
+        .map_err(csv_error)?;
+    }
+    let bytes = wtr.into_inner().map_err(|e| AppError::Api(format!("Could not write CSV: {}", e)))?;
+    Ok(String::from_utf8(bytes).expect("CSV built from UTF-8 strings"))
+}
+
+fn export_geojson(entries: &[&StoredAddress]) -> serde_json::Value {
+    let features: Vec<serde_json::Value> = entries
+        .iter()
+        .map(|entry| {
+            serde_json::json!({
+                "type": "Feature",
+                // GeoJSON positions are [longitude, latitude]
+                "geometry": { "type": "Point", "coordinates": [entry.lon, entry.lat] },
+                "properties": {
+                    "id": entry.id,
+                    "nickname": entry.nickname,
+                    "address": entry.address,
+                    "tags": entry.tags,
+                    "notes": entry.notes,
+                },
+            })
+        })
+        .collect();
+    serde_json::json!({ "type": "FeatureCollection", "features": features })
+}
+
+fn export_vcard(entry: &StoredAddress) -> String {
+    // Census matches look like "233 E MAIN ST, BOZEMAN, MT, 59715"
+    let parts: Vec<&str> = entry.address.split(',').map(str::trim).collect();
+    let adr = match parts.as_slice() {
+        [street, city, state, zip] => format!(";;{};{};{};{};", vcard_escape(street), vcard_escape(city), vcard_escape(state), vcard_escape(zip)),
+        _ => format!(";;{};;;;", vcard_escape(&entry.address)),
+    };
+    let mut lines = vec![
+        "BEGIN:VCARD".to_string(),
+        "VERSION:4.0".to_string(),
+        format!("FN:{}", vcard_escape(entry.nickname.as_deref().unwrap_or(&entry.address))),
+        format!("ADR;LABEL=\"{}\":{}", entry.address.replace('"', "'"), adr),
+        format!("GEO:geo:{},{}", entry.lat, entry.lon),
+    ];
+    if !entry.tags.is_empty() {
+        let tags: Vec<String> = entry.tags.iter().map(|t| vcard_escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    if let Some(notes) = &entry.notes {
+        lines.push(format!("NOTE:{}", vcard_escape(notes)));
+    }
+    lines.push("END:VCARD".to_string());
+    // vCard lines end in CRLF (RFC 6350)
+    lines.iter().map(|l| format!("{}\r\n", l)).collect()
+}
+
+fn vcard_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace(',', "\\,").replace(';', "\\;").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Writes `contents` to a file of its own under the system temp directory
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("reather-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn book_with(entries: &[(&str, f64, f64)]) -> AddressBook {
+        let mut book = AddressBook::new(Path::new("addresses.json"));
+        for &(address, lat, lon) in entries {
+            book.add(address, lat, lon);
+        }
+        book
+    }
+
+    fn ids(book: &AddressBook) -> Vec<u32> {
+        book.addresses().iter().map(|a| a.id).collect()
+    }
+
+    #[test]
+    fn reads_legacy_lines() {
+        let path = temp_file(
+            "legacy.txt",
+            "# saved by reather 0.1\n\
+             233 E MAIN ST, BOZEMAN, MT, 59715;45.6793;-111.0373\n\
+             \n\
+             1600 PENNSYLVANIA AVE NW, WASHINGTON, DC, 20500\n\
+             BAD LINE;north;west\n\
+             A;1;2;3\n",
+        );
+        let (entries, malformed) = read_legacy_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].address, "233 E MAIN ST, BOZEMAN, MT, 59715");
+        assert_eq!(entries[0].coords, Some((45.6793, -111.0373)));
+        assert_eq!(entries[1].address, "1600 PENNSYLVANIA AVE NW, WASHINGTON, DC, 20500");
+        assert_eq!(entries[1].coords, None);
+        assert_eq!(malformed, ["BAD LINE;north;west", "A;1;2;3"]);
+    }
+
+    #[test]
+    fn finds_duplicates_by_address_or_coordinates() {
+        let book = book_with(&[("233 E MAIN ST, BOZEMAN, MT, 59715", 45.6793, -111.0373), ("PORTLAND HEAD LIGHT", 43.6231, -70.2079)]);
+        assert_eq!(book.find_duplicate(" 233 e main st, bozeman, mt, 59715 ", 0.0, 0.0).map(|a| a.id), Some(1));
+        assert_eq!(book.find_duplicate("1000 SHORE RD, CAPE ELIZABETH, ME", 43.62315, -70.20785).map(|a| a.id), Some(2));
+        assert!(book.find_duplicate("1000 SHORE RD, CAPE ELIZABETH, ME", 43.6240, -70.2079).is_none());
+    }
+
+    #[test]
+    fn move_to_clamps_to_the_end() {
+        let mut book = book_with(&[("A", 1.0, 1.0), ("B", 2.0, 2.0), ("C", 3.0, 3.0)]);
+        book.move_to(3, 0).unwrap();
+        assert_eq!(ids(&book), [3, 1, 2]);
+        book.move_to(3, 99).unwrap();
+        assert_eq!(ids(&book), [1, 2, 3]);
+        assert!(matches!(book.move_to(7, 0), Err(AppError::UserInput(_))));
+        assert_eq!(ids(&book), [1, 2, 3]);
+    }
+
+    #[test]
+    fn rejects_numeric_and_duplicate_nicknames() {
+        let mut book = book_with(&[("A", 1.0, 1.0), ("B", 2.0, 2.0)]);
+        book.get_mut(1).unwrap().nickname = Some("Home".to_string());
+        assert!(book.check_nickname("42", None).is_err());
+        assert!(book.check_nickname("  ", None).is_err());
+        assert!(book.check_nickname("home", Some(2)).is_err());
+        assert!(book.check_nickname("HOME", Some(1)).is_ok());
+        assert!(book.check_nickname("Cabin", None).is_ok());
+        assert_eq!(book.find("home").map(|a| a.id), Some(1));
+        assert_eq!(book.find("2").map(|a| a.id), Some(2));
+    }
+
+    fn entry(nickname: Option<&str>, address: &str, tags: &[&str], notes: Option<&str>) -> StoredAddress {
+        let mut book = book_with(&[(address, 45.6793, -111.0373)]);
+        let entry = book.get_mut(1).unwrap();
+        entry.nickname = nickname.map(str::to_string);
+        entry.tags = tags.iter().map(|t| t.to_string()).collect();
+        entry.notes = notes.map(str::to_string);
+        entry.clone()
+    }
+
+    #[test]
+    fn import_accepts_header_aliases_and_joins_address_parts() {
+        let path = temp_file(
+            "parts.csv",
+            "Name,Street Address,Municipality,Region,Zip Code,Tag,Note\n\
+             Home,233 E Main St,Bozeman,MT,59715,\"ski, family\",Back door code 1234\n\
+             ,100 Congress St,Portland,ME,,,\n",
+        );
+        let (rows, failed) = read_import_csv(&path).unwrap();
+        assert!(failed.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].query, "233 E Main St, Bozeman, MT, 59715");
+        assert_eq!(rows[0].nickname.as_deref(), Some("Home"));
+        assert_eq!(rows[0].tags, ["ski", "family"]);
+        assert_eq!(rows[0].notes.as_deref(), Some("Back door code 1234"));
+        assert_eq!(rows[1].query, "100 Congress St, Portland, ME");
+        assert_eq!(rows[1].nickname, None);
+
+        let path = temp_file("full.csv", "NICKNAME,Full Address\nCabin,\"1 Lake Rd, Big Sky, MT\"\n");
+        let (rows, _) = read_import_csv(&path).unwrap();
+        assert_eq!(rows[0].query, "1 Lake Rd, Big Sky, MT");
+
+        let path = temp_file("no-address.csv", "nickname,notes\nHome,x\n");
+        assert!(matches!(read_import_csv(&path), Err(AppError::UserInput(_))));
+    }
+
+    #[test]
+    fn import_skips_blank_rows_and_reports_line_numbers() {
+        let path = temp_file(
+            "blank.csv",
+            "address,nickname\n\
+             233 E Main St Bozeman MT,Home\n\
+             ,\n\
+             \n\
+             ,Cabin\n\
+             100 Congress St Portland ME,Office\n",
+        );
+        let (rows, failed) = read_import_csv(&path).unwrap();
+        assert_eq!(rows.iter().map(|r| r.line).collect::<Vec<_>>(), [2, 6]);
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].line, 5);
+        assert_eq!(failed[0].status, ImportStatus::Failed);
+        assert_eq!(failed[0].message.as_deref(), Some("No address in this row"));
+
+        let path = temp_file("blank-crlf.csv", "address\r\n\r\n\r\n100 Congress St Portland ME\r\n");
+        let (rows, _) = read_import_csv(&path).unwrap();
+        assert_eq!(rows[0].line, 4);
+    }
+
+    #[test]
+    fn vcard_escapes_text_and_ends_lines_in_crlf() {
+        let card = export_vcard(&entry(Some("Home; sweet home"), "233 E MAIN ST, BOZEMAN, MT, 59715", &["ski,lodge", "family"], Some("Gate code 12\nback door")));
+        assert!(card.starts_with("BEGIN:VCARD\r\nVERSION:4.0\r\n"));
+        assert!(card.ends_with("END:VCARD\r\n"));
+        assert_eq!(card.matches("\r\n").count(), card.matches('\n').count());
+        assert!(card.contains("FN:Home\\; sweet home\r\n"));
+        assert!(card.contains(":;;233 E MAIN ST;BOZEMAN;MT;59715;\r\n"));
+        assert!(card.contains("CATEGORIES:ski\\,lodge,family\r\n"));
+        assert!(card.contains("NOTE:Gate code 12\\nback door\r\n"));
+        assert_eq!(vcard_escape("a\\b"), "a\\\\b");
+    }
+
+    #[test]
+    fn csv_export_imports_back() {
+        let home = entry(Some("Home"), "233 E MAIN ST, BOZEMAN, MT, 59715", &["ski", "family"], Some("Says \"hi\", then leaves"));
+        let plain = entry(None, "100 CONGRESS ST, PORTLAND, ME, 04101", &[], None);
+        let path = temp_file("round-trip.csv", &export(&[&home, &plain], ExportFormat::Csv).unwrap());
+        let (rows, failed) = read_import_csv(&path).unwrap();
+        assert!(failed.is_empty());
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].query, home.address);
+        assert_eq!(rows[0].nickname.as_deref(), Some("Home"));
+        assert_eq!(rows[0].tags, home.tags);
+        assert_eq!(rows[0].notes, home.notes);
+        assert_eq!((rows[1].query.as_str(), rows[1].nickname.as_deref(), rows[1].notes.as_deref()), (plain.address.as_str(), None, None));
+        assert!(rows[1].tags.is_empty());
+    }
+}
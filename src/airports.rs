@@ -3,12 +3,20 @@
 
 use std::collections::HashMap;
 use std::sync::OnceLock;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-const AIRPORTS_CSV_URL: &str = "https://raw.githubusercontent.com/davidmegginson/ourairports-data/main/airports.csv";
+use crate::dataset_cache::{self, DatasetStatus, RefreshPolicy};
+use crate::ourairports::{self, Country, Frequency, Region};
+use crate::runways::{self, Runway};
+use crate::{config, haversine_distance, initial_bearing, AppError};
+
+/// Two-letter postal codes of the 50 US states
+pub const US_STATES: [&str; 50] = [
+    "AL","AK","AZ","AR","CA","CO","CT","DE","FL","GA","HI","ID","IL","IN","IA","KS","KY","LA","ME","MD","MA","MI","MN","MS","MO","MT","NE","NV","NH","NJ","NM","NY","NC","ND","OH","OK","OR","PA","RI","SC","SD","TN","TX","UT","VT","VA","WA","WV","WI","WY"
+];
 
 #[allow(dead_code)]
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct Airport {
     pub id: String,
     pub ident: String,
@@ -31,36 +39,80 @@ pub struct Airport {
     pub keywords: String,
 }
 
+impl Airport {
+    /// The four-character ICAO location indicator used by METAR and TAF reports:
+    /// the GPS code when it has that form, else the ident (e.g. "KBZN").
+    pub fn icao_code(&self) -> Option<&str> {
+        let is_icao = |code: &str| code.len() == 4 && code.chars().all(|c| c.is_ascii_alphanumeric());
+        [self.gps_code.trim(), self.ident.trim()].into_iter().find(|code| is_icao(code))
+    }
+
+    /// Whether the airport is the kind that publishes METARs: an airport (not a heliport,
+    /// seaplane base or closed field) with an all-letter ICAO indicator. Private strips
+    /// with numbered codes such as "MT00" seldom have a weather station.
+    pub fn may_report_metar(&self) -> bool {
+        matches!(self.type_.as_str(), "large_airport" | "medium_airport" | "small_airport")
+            && self.icao_code().is_some_and(|code| code.chars().all(|c| c.is_ascii_alphabetic()))
+    }
+
+    /// Runways from runways.csv (loaded by `runways::init_runways`).
+    pub fn runways(&self) -> &'static [Runway] {
+        runways::runways_for(&self.ident)
+    }
+
+    /// Radio frequencies from airport-frequencies.csv (loaded by `ourairports::init_datasets`).
+    pub fn frequencies(&self) -> &'static [Frequency] {
+        ourairports::frequencies_for(&self.ident)
+    }
+
+    pub fn country(&self) -> Option<&'static Country> {
+        ourairports::country(&self.iso_country)
+    }
+
+    pub fn region(&self) -> Option<&'static Region> {
+        ourairports::region(&self.iso_region)
+    }
+}
+
 static AIRPORTS: OnceLock<HashMap<String, Airport>> = OnceLock::new();
 
-pub fn init_airports() -> Result<(), Box<dyn std::error::Error>> {
-    let map = load_airports()?;
-    AIRPORTS.set(map).map_err(|_| "AIRPORTS already initialized".into())
+/// Loads the airport database, from the on-disk cache when it is fresh enough.
+/// Blocking; call before starting the async runtime.
+pub fn init_airports() -> Result<DatasetStatus, Box<dyn std::error::Error>> {
+    let (map, status) = load_airports(RefreshPolicy::IfStale)?;
+    AIRPORTS.set(map).map_err(|_| "AIRPORTS already initialized")?;
+    Ok(status)
 }
 
-fn load_airports() -> Result<HashMap<String, Airport>, Box<dyn std::error::Error>> {
+/// Revalidates the cached airports.csv with the server regardless of its age.
+/// Returns the number of airports in the refreshed database.
+pub fn refresh_airports() -> Result<(usize, DatasetStatus), AppError> {
+    let (map, status) = load_airports(RefreshPolicy::Force)?;
+    if let dataset_cache::DatasetSource::StaleCache { error } = status.source {
+        return Err(AppError::Api(format!("Could not refresh airports.csv: {}", error)));
+    }
+    let count = map.values().map(|a| a.ident.as_str()).collect::<HashSet<_>>().len();
+    Ok((count, status))
+}
+
+fn load_airports(policy: RefreshPolicy) -> Result<(HashMap<String, Airport>, DatasetStatus), AppError> {
     let mut map = HashMap::new();
-    let resp = reqwest::blocking::get(AIRPORTS_CSV_URL)?;
-    let bytes = resp.bytes()?;
-    let mut rdr = csv::Reader::from_reader(bytes.as_ref());
-    for result in rdr.deserialize() {
-        if let Ok(airport) = result {
-            let airport: Airport = airport;
-            // Insert by IATA code if present
-            if !airport.iata_code.is_empty() {
-                let iata = airport.iata_code.trim().to_uppercase();
-                map.insert(iata, airport.clone());
-            }
-            // Insert by ICAO (ident) if present and not already in map
-            if !airport.ident.is_empty() {
-                let icao = airport.ident.trim().to_uppercase();
-                if !map.contains_key(&icao) {
-                    map.insert(icao, airport.clone());
-                }
-            }
+    let url = format!("{}/airports.csv", config::endpoints().ourairports);
+    let (bytes, status) = dataset_cache::fetch_dataset("airports.csv", &url, policy)?;
+    let mut rdr = csv::Reader::from_reader(bytes.as_slice());
+    for airport in rdr.deserialize::<Airport>().flatten() {
+        // Insert by IATA code if present
+        if !airport.iata_code.is_empty() {
+            let iata = airport.iata_code.trim().to_uppercase();
+            map.insert(iata, airport.clone());
+        }
+        // Insert by ICAO (ident) if present and not already in map
+        if !airport.ident.is_empty() {
+            let icao = airport.ident.trim().to_uppercase();
+            map.entry(icao).or_insert_with(|| airport.clone());
         }
     }
-    Ok(map)
+    Ok((map, status))
 }
 
 fn get_airports() -> &'static HashMap<String, Airport> {
@@ -126,7 +178,7 @@ pub fn extract_zip_code(address_str: &str) -> Option<String> {
         let words: Vec<&str> = trimmed.split_whitespace().collect();
         if let Some(last_word) = words.last() {
             // Check if it's a 5-digit number (US ZIP code)
-            if last_word.len() == 5 && last_word.chars().all(|c| c.is_digit(10)) {
+            if last_word.len() == 5 && last_word.chars().all(|c| c.is_ascii_digit()) {
                 return Some(last_word.to_string());
             }
         }
@@ -184,3 +236,213 @@ pub fn search_airports(pattern: &str) -> Vec<&'static Airport> {
     });
     results
 }
+
+/// Filters shared by text search and nearest-airport lookup.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AirportFilter {
+    /// Only airports in one of the 50 US states
+    pub us_only: bool,
+    /// Only airports with scheduled (passenger) service
+    pub scheduled_only: bool,
+}
+
+impl AirportFilter {
+    pub fn matches(&self, airport: &Airport) -> bool {
+        if self.us_only {
+            let in_us_state = airport.iso_region.trim().strip_prefix("US-")
+                .is_some_and(|state| US_STATES.contains(&state.to_uppercase().as_str()));
+            if !in_us_state {
+                return false;
+            }
+        }
+        !self.scheduled_only || airport.scheduled_service == "yes"
+    }
+}
+
+/// An airport found by `nearest_airports`, with distance and bearing from the query point.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyAirport {
+    pub airport: &'static Airport,
+    pub distance_km: f64,
+    /// Initial bearing from the query point to the airport, degrees true
+    pub bearing_deg: f64,
+}
+
+// Grid cell size of the spatial index, in degrees of latitude and longitude
+const GRID_DEGREES: f64 = 1.0;
+
+// Buckets every airport with valid coordinates into 1° x 1° cells so a radius
+// query only has to measure the airports in the cells the circle overlaps.
+struct SpatialIndex {
+    cells: HashMap<(i32, i32), Vec<IndexedAirport>>,
+}
+
+// Airport with its coordinates already parsed
+type IndexedAirport = (f64, f64, &'static Airport);
+
+static SPATIAL_INDEX: OnceLock<SpatialIndex> = OnceLock::new();
+
+const LON_CELLS: i32 = (360.0 / GRID_DEGREES) as i32;
+
+fn grid_cell(lat: f64, lon: f64) -> (i32, i32) {
+    ((lat / GRID_DEGREES).floor() as i32, (lon / GRID_DEGREES).floor() as i32)
+}
+
+// Maps a longitude cell into [-180°, 180°) so queries can wrap across the antimeridian
+fn wrap_lon_cell(cell: i32) -> i32 {
+    (cell + LON_CELLS / 2).rem_euclid(LON_CELLS) - LON_CELLS / 2
+}
+
+impl SpatialIndex {
+    fn build(airports: impl IntoIterator<Item = &'static Airport>) -> SpatialIndex {
+        let mut cells: HashMap<(i32, i32), Vec<IndexedAirport>> = HashMap::new();
+        let mut seen = HashSet::new();
+        for airport in airports {
+            // The map holds most airports twice (IATA and ICAO keys)
+            if !seen.insert(airport.ident.as_str()) {
+                continue;
+            }
+            if let (Ok(lat), Ok(lon)) = (airport.latitude_deg.parse::<f64>(), airport.longitude_deg.parse::<f64>()) {
+                let (cell_lat, cell_lon) = grid_cell(lat, lon);
+                cells.entry((cell_lat, wrap_lon_cell(cell_lon))).or_default().push((lat, lon, airport));
+            }
+        }
+        SpatialIndex { cells }
+    }
+
+    fn nearest(&self, lat: f64, lon: f64, radius_km: f64, filter: &AirportFilter) -> Vec<NearbyAirport> {
+        // One degree of latitude is ~111 km; longitude degrees shrink with cos(lat)
+        let lat_span = radius_km / 111.0;
+        let min_cos = (lat.abs() + lat_span).min(90.0).to_radians().cos();
+        let lon_span = if min_cos < 1e-6 { 360.0 } else { (radius_km / (111.0 * min_cos)).min(360.0) };
+        let (lat_lo, lon_lo) = grid_cell((lat - lat_span).max(-90.0), lon - lon_span);
+        let (lat_hi, lon_hi) = grid_cell((lat + lat_span).min(90.0), lon + lon_span);
+        let lon_cells: Vec<i32> = if lon_hi - lon_lo >= LON_CELLS {
+            (-LON_CELLS / 2..LON_CELLS / 2).collect()
+        } else {
+            (lon_lo..=lon_hi).map(wrap_lon_cell).collect()
+        };
+
+        let mut results = Vec::new();
+        for cell_lat in lat_lo..=lat_hi {
+            for &cell_lon in &lon_cells {
+                let Some(candidates) = self.cells.get(&(cell_lat, cell_lon)) else {
+                    continue;
+                };
+                for &(a_lat, a_lon, airport) in candidates {
+                    let distance_km = haversine_distance(lat, lon, a_lat, a_lon);
+                    if distance_km <= radius_km && filter.matches(airport) {
+                        results.push(NearbyAirport {
+                            airport,
+                            distance_km,
+                            bearing_deg: initial_bearing(lat, lon, a_lat, a_lon),
+                        });
+                    }
+                }
+            }
+        }
+        results.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+        results
+    }
+}
+
+fn get_spatial_index() -> &'static SpatialIndex {
+    SPATIAL_INDEX.get_or_init(|| SpatialIndex::build(get_airports().values()))
+}
+
+/// Largest useful search radius: half the Earth's circumference, in km.
+pub const MAX_RADIUS_KM: f64 = 20_038.0;
+
+/// Airports within `radius_km` of a point that pass `filter`, nearest first.
+/// `radius_km` is capped at `MAX_RADIUS_KM`.
+pub fn nearest_airports(lat: f64, lon: f64, radius_km: f64, filter: &AirportFilter) -> Vec<NearbyAirport> {
+    get_spatial_index().nearest(lat, lon, radius_km.min(MAX_RADIUS_KM), filter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn airport(ident: &str, lat: f64, lon: f64) -> &'static Airport {
+        Box::leak(Box::new(Airport {
+            ident: ident.to_string(),
+            latitude_deg: lat.to_string(),
+            longitude_deg: lon.to_string(),
+            ..Airport::default()
+        }))
+    }
+
+    fn idents(results: &[NearbyAirport]) -> Vec<&'static str> {
+        results.iter().map(|nearby| nearby.airport.ident.as_str()).collect()
+    }
+
+    #[test]
+    fn wraps_across_the_antimeridian() {
+        let index = SpatialIndex::build([airport("EAST", -16.0, 179.9), airport("WEST", -16.0, -179.9), airport("FAR", -16.0, 170.0)]);
+        let results = index.nearest(-16.0, 179.9, 50.0, &AirportFilter::default());
+        assert_eq!(idents(&results), ["EAST", "WEST"]);
+        assert!((results[1].distance_km - 21.4).abs() < 0.5, "got {}", results[1].distance_km);
+        let results = index.nearest(-16.0, -179.9, 50.0, &AirportFilter::default());
+        assert_eq!(idents(&results), ["WEST", "EAST"]);
+    }
+
+    #[test]
+    fn polar_queries_cover_every_longitude() {
+        let index = SpatialIndex::build([airport("POLE", -90.0, 0.0), airport("A", -89.5, 100.0), airport("B", -89.5, -80.0), airport("C", -80.0, 0.0)]);
+        let results = index.nearest(-89.9, 45.0, 100.0, &AirportFilter::default());
+        assert_eq!(idents(&results), ["POLE", "A", "B"]);
+        let results = index.nearest(90.0, 0.0, 500.0, &AirportFilter::default());
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn huge_radius_is_clamped_and_finds_everything() {
+        let index = SpatialIndex::build([airport("KBZN", 45.78, -111.15), airport("YSSY", -33.95, 151.18)]);
+        let results = index.nearest(45.68, -111.04, 1e12, &AirportFilter::default());
+        assert_eq!(idents(&results), ["KBZN", "YSSY"]);
+    }
+
+    #[test]
+    fn matches_a_brute_force_scan() {
+        // A deterministic spread of points over the globe
+        let airports: Vec<&'static Airport> = (0..2000)
+            .map(|i| {
+                let lat = ((i * 7919) % 17_999) as f64 / 100.0 - 89.99;
+                let lon = ((i * 104_729) % 35_999) as f64 / 100.0 - 179.99;
+                airport(&format!("A{}", i), lat, lon)
+            })
+            .collect();
+        let index = SpatialIndex::build(airports.iter().copied());
+        for &(lat, lon, radius_km) in &[(45.68, -111.04, 800.0), (0.0, 179.5, 1500.0), (-85.0, 10.0, 2500.0), (60.0, -30.0, 10_000.0)] {
+            let mut expected: Vec<&str> = airports
+                .iter()
+                .filter(|a| {
+                    let (a_lat, a_lon) = (a.latitude_deg.parse().unwrap(), a.longitude_deg.parse().unwrap());
+                    haversine_distance(lat, lon, a_lat, a_lon) <= radius_km
+                })
+                .map(|a| a.ident.as_str())
+                .collect();
+            let mut found = idents(&index.nearest(lat, lon, radius_km, &AirportFilter::default()));
+            assert!(!expected.is_empty());
+            expected.sort_unstable();
+            found.sort_unstable();
+            assert_eq!(found, expected, "query ({}, {}) within {} km", lat, lon, radius_km);
+        }
+    }
+
+    #[test]
+    fn metar_candidates_are_lettered_airports() {
+        let with = |ident: &str, gps_code: &str, type_: &str| Airport {
+            ident: ident.to_string(),
+            gps_code: gps_code.to_string(),
+            type_: type_.to_string(),
+            ..Airport::default()
+        };
+        assert!(with("KBZN", "KBZN", "medium_airport").may_report_metar());
+        assert!(with("US-0123", "KRKD", "small_airport").may_report_metar());
+        assert!(!with("MT00", "MT00", "small_airport").may_report_metar());
+        assert!(!with("KXYZ", "", "heliport").may_report_metar());
+        assert!(!with("KOLD", "", "closed").may_report_metar());
+        assert!(!with("BZN", "", "medium_airport").may_report_metar());
+    }
+}
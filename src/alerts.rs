@@ -0,0 +1,70 @@
+// NWS active weather alerts (watches, warnings and advisories) for a point
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::{api_error, config, AppError, HTTP_CLIENT};
+
+/// An active NWS alert, e.g. a Tornado Warning or Winter Storm Watch.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Alert {
+    pub event: String,
+    /// "Extreme", "Severe", "Moderate", "Minor" or "Unknown"
+    pub severity: String,
+    /// "Immediate", "Expected", "Future", "Past" or "Unknown"
+    pub urgency: String,
+    #[serde(default)]
+    pub certainty: Option<String>,
+    #[serde(default)]
+    pub onset: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub expires: Option<DateTime<FixedOffset>>,
+    #[serde(default)]
+    pub headline: Option<String>,
+    #[serde(default)]
+    pub area_desc: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub instruction: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertCollection {
+    features: Vec<AlertFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertFeature {
+    properties: Alert,
+}
+
+/// Fetches the alerts currently in effect at a point, most severe first.
+pub async fn fetch_active_alerts(lat: f64, lon: f64) -> Result<Vec<Alert>, AppError> {
+    // NWS rejects points with more than four decimal places
+    let url = format!("{}/alerts/active?point={:.4},{:.4}", config::endpoints().nws, lat, lon);
+    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+
+    if !response.status().is_success() {
+        return Err(api_error("NWS Alerts API", &url, response).await);
+    }
+
+    let collection: AlertCollection = response.json().await.map_err(|e| {
+        AppError::Api(format!("Failed to parse JSON response from NWS Alerts API (URL: {}): {}", url, e))
+    })?;
+    let mut alerts: Vec<Alert> = collection.features.into_iter().map(|f| f.properties).collect();
+    alerts.sort_by_key(|a| severity_rank(&a.severity));
+    Ok(alerts)
+}
+
+// Lower ranks sort first
+fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "Extreme" => 0,
+        "Severe" => 1,
+        "Moderate" => 2,
+        "Minor" => 3,
+        _ => 4,
+    }
+}
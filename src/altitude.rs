@@ -0,0 +1,105 @@
+// Pressure altitude and density altitude for an airfield
+//
+// Pressure altitude corrects field elevation for non-standard pressure; density
+// altitude further corrects it for non-standard temperature, and is the altitude the
+// aircraft performs as if it were at. Hot days at high fields (Bozeman in July) push
+// it thousands of feet above the field, lengthening take-off rolls and cutting climb.
+
+use serde::Serialize;
+
+use crate::config;
+
+/// Standard sea-level altimeter setting, inHg.
+pub const STANDARD_ALTIMETER_INHG: f64 = 29.92;
+
+/// Pressure altitude in feet from field elevation and the altimeter setting.
+pub fn pressure_altitude_ft(elevation_ft: f64, altimeter_inhg: f64) -> f64 {
+    elevation_ft + (STANDARD_ALTIMETER_INHG - altimeter_inhg) * 1000.0
+}
+
+/// ISA temperature in °C at a pressure altitude (15 °C at sea level, falling 1.98 °C
+/// per 1,000 ft).
+pub fn isa_temperature_c(pressure_altitude_ft: f64) -> f64 {
+    15.0 - 1.98 * pressure_altitude_ft / 1000.0
+}
+
+/// Density altitude in feet for dry air (the NWS formula), from field elevation,
+/// altimeter setting and outside air temperature.
+pub fn density_altitude_ft(elevation_ft: f64, altimeter_inhg: f64, temperature_c: f64) -> f64 {
+    // Station pressure from the altimeter setting, then the density of the air
+    let elevation_m = elevation_ft * 0.3048;
+    let station_inhg = altimeter_inhg * ((288.0 - 0.0065 * elevation_m) / 288.0).powf(5.2561);
+    let temperature_rankine = temperature_c * 9.0 / 5.0 + 32.0 + 459.67;
+    145442.16 * (1.0 - (17.326 * station_inhg / temperature_rankine).powf(0.235))
+}
+
+/// Pressure and density altitude of a field for one observation.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FieldAltitudes {
+    pub elevation_ft: f64,
+    pub altimeter_inhg: f64,
+    pub temperature_c: f64,
+    pub pressure_altitude_ft: f64,
+    pub density_altitude_ft: f64,
+    /// Outside air temperature minus ISA temperature at the pressure altitude
+    pub isa_deviation_c: f64,
+    /// Density altitude at or above `[altitude] high_density_altitude_ft`
+    pub high_density_altitude: bool,
+}
+
+impl FieldAltitudes {
+    pub fn new(elevation_ft: f64, altimeter_inhg: f64, temperature_c: f64, warning_ft: f64) -> FieldAltitudes {
+        let pressure_altitude_ft = pressure_altitude_ft(elevation_ft, altimeter_inhg);
+        let density_altitude_ft = density_altitude_ft(elevation_ft, altimeter_inhg, temperature_c);
+        FieldAltitudes {
+            elevation_ft,
+            altimeter_inhg,
+            temperature_c,
+            pressure_altitude_ft,
+            density_altitude_ft,
+            isa_deviation_c: temperature_c - isa_temperature_c(pressure_altitude_ft),
+            high_density_altitude: density_altitude_ft >= warning_ft,
+        }
+    }
+}
+
+/// The configured high-density-altitude warning threshold in feet.
+pub fn high_density_altitude_ft() -> f64 {
+    config::get().altitude.high_density_altitude_ft
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pressure_altitude_rises_as_the_altimeter_falls() {
+        assert_eq!(pressure_altitude_ft(4473.0, 29.92), 4473.0);
+        assert!((pressure_altitude_ft(4473.0, 29.72) - 4673.0).abs() < 1e-6);
+        assert!((pressure_altitude_ft(0.0, 30.12) + 200.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn standard_day_density_altitude_is_the_elevation() {
+        assert!(density_altitude_ft(0.0, 29.92, 15.0).abs() < 50.0);
+        let isa = isa_temperature_c(5000.0);
+        assert!((density_altitude_ft(5000.0, 29.92, isa) - 5000.0).abs() < 100.0);
+    }
+
+    #[test]
+    fn hot_day_at_a_high_field() {
+        // Rule of thumb: 120 ft per °C above ISA; 5,000 ft at 30 °C is about 8,000 ft
+        let da = density_altitude_ft(5000.0, 29.92, 30.0);
+        assert!((da - 8000.0).abs() < 400.0, "got {}", da);
+    }
+
+    #[test]
+    fn flags_high_density_altitude() {
+        let summer = FieldAltitudes::new(4473.0, 30.00, 32.0, 6000.0);
+        assert!(summer.high_density_altitude);
+        assert!(summer.isa_deviation_c > 20.0);
+        let winter = FieldAltitudes::new(4473.0, 30.00, -2.0, 6000.0);
+        assert!(!winter.high_density_altitude);
+        assert!(winter.density_altitude_ft < winter.pressure_altitude_ft);
+    }
+}
@@ -0,0 +1,87 @@
+// Latest METAR and TAF for an airport from the aviationweather.gov data API
+//
+// Reports are fetched in raw text form and decoded with the `metar` module. The API
+// answers 204 No Content (or an empty body) for stations that issue no reports,
+// which comes back here as `Ok(None)`.
+
+use std::collections::HashMap;
+
+use crate::flight_rules::FlightCategory;
+use crate::metar::{self, Metar, Taf};
+use crate::{api_error, config, AppError, HTTP_CLIENT};
+
+// Station ids per request when fetching METARs in bulk
+const BULK_CHUNK: usize = 100;
+
+// Fetches the raw text of one product ("metar" or "taf") for comma-separated station
+// ids; `None` when there is none
+async fn fetch_raw(product: &str, ids: &str) -> Result<Option<String>, AppError> {
+    let url = format!(
+        "{}/api/data/{}?ids={}&format=raw",
+        config::endpoints().aviation_weather,
+        product,
+        urlencoding::encode(ids)
+    );
+    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(api_error("Aviation Weather API", &url, response).await);
+    }
+    let text = response.text().await.map_err(AppError::Network)?;
+    let text = text.trim();
+    Ok((!text.is_empty()).then(|| text.to_string()))
+}
+
+/// Fetches and decodes the latest METAR for an ICAO station id (e.g. "KBZN").
+pub async fn fetch_metar(icao: &str) -> Result<Option<Metar>, AppError> {
+    let Some(text) = fetch_raw("metar", icao).await? else {
+        return Ok(None);
+    };
+    // Only the most recent report when the API returns several lines
+    let latest = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or_default();
+    metar::parse_metar(latest).map(Some)
+}
+
+/// Fetches and decodes the current TAF for an ICAO station id.
+pub async fn fetch_taf(icao: &str) -> Result<Option<Taf>, AppError> {
+    let Some(text) = fetch_raw("taf", icao).await? else {
+        return Ok(None);
+    };
+    // A TAF spans several lines; a second "TAF" line would start an older report
+    let mut report = Vec::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if line.starts_with("TAF") && !report.is_empty() {
+            break;
+        }
+        report.push(line);
+    }
+    metar::parse_taf(&report.join(" ")).map(Some)
+}
+
+/// Fetches the latest METAR of every station in `icaos`, in requests of up to 100 ids.
+/// Stations without a report are left out, as are reports that fail to decode.
+pub async fn fetch_metars(icaos: &[&str]) -> Result<Vec<Metar>, AppError> {
+    let mut metars: Vec<Metar> = Vec::new();
+    for chunk in icaos.chunks(BULK_CHUNK) {
+        let Some(text) = fetch_raw("metar", &chunk.join(",")).await? else {
+            continue;
+        };
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Ok(metar) = metar::parse_metar(line) {
+                // Keep the first (most recent) report per station
+                if !metars.iter().any(|m| m.station == metar.station) {
+                    metars.push(metar);
+                }
+            }
+        }
+    }
+    Ok(metars)
+}
+
+/// Current flight category of each station in `icaos` that has a METAR, keyed by station id.
+pub async fn fetch_flight_categories(icaos: &[&str]) -> Result<HashMap<String, FlightCategory>, AppError> {
+    let metars = fetch_metars(icaos).await?;
+    Ok(metars
+        .into_iter()
+        .filter_map(|metar| Some((metar.station.clone(), metar.flight_category()?)))
+        .collect())
+}
@@ -0,0 +1,641 @@
+// Command-line parsing for non-interactive use of reather.
+// With no arguments the interactive menu starts; otherwise the first argument
+// selects a subcommand so reather can be scripted from cron or shell pipelines.
+
+use std::path::PathBuf;
+
+use reather::addresses::{split_tags, ExportFormat};
+use reather::airports::{AirportFilter, MAX_RADIUS_KM};
+use reather::flight_rules::FlightCategory;
+use reather::geocode::parse_coordinates;
+use reather::nws;
+use reather::{AppError, UnitSystem};
+
+pub const USAGE: &str = "Usage: reather [--format text|json] [--units imperial|metric|aviation] [--no-cache] [COMMAND]
+
+Runs the interactive menu when no command is given.
+
+Commands:
+  weather <address|airport>       Current conditions at the nearest NWS station (or the
+                                  station chosen for a saved address) with a recent
+                                  observation
+  stations <address|airport> [--use ID]
+                                  Closest NWS observation stations with distance and
+                                  age of the last observation; --use saves a station
+                                  for a saved address
+  history <address|airport> [--hours N]
+                                  Recent observations with min/max, the 3-hour pressure
+                                  tendency and charts (default: 24 hours, up to 72)
+  forecast <address|airport> [--periods N] [--detailed]
+                                  Local NWS forecast as a table, or period by period
+                                  with --detailed (default: all 14 periods, 7 days)
+  hourly <address|airport> [--hours N]
+                                  Hourly NWS forecast with a temperature sparkline
+                                  (default: 24 hours, up to 156)
+  alerts <address|airport>        Active NWS watches, warnings and advisories
+  tides <address|airport>         Nearest NOAA tide station and predictions
+  lookup <lat,lon|maps-url> [--save] [--nickname NAME]
+                                  City, county, state, ZIP and FIPS codes for a
+                                  coordinate or Google Maps link; --save adds it
+                                  to the address book
+  quakes [--min-mag M] [--hours H]
+                                  USGS earthquakes (default: all magnitudes, 24 hours)
+  airports search <pattern> [--us-only] [--passenger-only] [--category VFR|MVFR|IFR|LIFR]
+                                  Search airports; '*' is a wildcard (e.g. 'Rome*');
+                                  --category keeps airports whose METAR is in that
+                                  flight category
+  airports near <address|airport> [--radius KM] [--limit N] [--us-only] [--passenger-only]
+                                  Nearest airports with distance and bearing
+                                  (default: 50 km, 10 airports)
+  airports show <code>            Airport details with current weather
+  airports refresh                Re-download the cached OurAirports database
+  addresses list [--tag TAG]      Saved addresses with nicknames, tags and notes
+  addresses add <address> [--nickname NAME] [--tags a,b] [--notes TEXT]
+                                  Geocode and save an address
+  addresses edit <id|nickname> [--nickname NAME] [--tags a,b] [--notes TEXT]
+                                  Change an entry; pass \"\" to clear a field
+  addresses delete <id|nickname>  Remove an entry
+  addresses move <id|nickname> <position>
+                                  Reorder the list (positions start at 1)
+  addresses import <file.csv>     Geocode and save each row of a spreadsheet; the
+                                  header names an address column (or street, city,
+                                  state, zip) plus optional nickname, tags, notes
+  addresses export <file|-> [--as csv|geojson|vcard] [--tag TAG]
+                                  Write the book as CSV, a GeoJSON FeatureCollection
+                                  or vCards (format from the extension by default)
+  addresses normalize             Re-geocode every entry in one Census batch request
+                                  and record each match status
+  cache list                      Cached geocoder matches with their age
+  cache clear [--expired]         Empty the geocoding cache, or drop expired entries
+  help                            Show this message
+
+Options:
+  --format text|json              Output format for commands (default: text).
+                                  JSON reports carry a schema_version field.
+  --units imperial|metric|aviation
+                                  Units for text output (default: from reather.toml
+                                  or REATHER_UNITS, else imperial). JSON is always SI.
+  --no-cache                      Geocode without reading or writing the local cache
+
+An <address|airport> is a saved address nickname (e.g. Home), an IATA/ICAO
+airport code (e.g. BZN, KBZN), a street address
+(e.g. \"233 E Main St, Bozeman, MT 59715\") or coordinates (e.g. 45.68,-111.04).";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+/// Everything parsed from the command line.
+#[derive(Debug)]
+pub struct Invocation {
+    pub format: OutputFormat,
+    /// Overrides the configured unit system
+    pub units: Option<UnitSystem>,
+    /// Bypass the geocoding cache
+    pub no_cache: bool,
+    /// `None` when the interactive menu should run
+    pub command: Option<Command>,
+}
+
+/// How forecast periods are rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForecastView {
+    /// One line per period
+    Table,
+    /// Full NWS wording for each period
+    Detailed,
+}
+
+/// Periods in a full NWS forecast: day and night for 7 days.
+pub const FORECAST_PERIODS: usize = 14;
+
+/// Hours of observation history shown unless asked otherwise.
+pub const HISTORY_DEFAULT_HOURS: u32 = 24;
+
+/// Hours shown by the hourly forecast unless asked otherwise.
+pub const HOURLY_FORECAST_DEFAULT_HOURS: usize = 24;
+/// NWS hourly forecasts cover about 6.5 days.
+pub const HOURLY_FORECAST_MAX_HOURS: usize = 156;
+
+#[derive(Debug)]
+pub enum Command {
+    Weather { location: String },
+    /// `use_station` remembers that station for a saved address
+    Stations { location: String, use_station: Option<String> },
+    History { location: String, hours: u32 },
+    Forecast { location: String, periods: usize, view: ForecastView },
+    Hourly { location: String, hours: usize },
+    Alerts { location: String },
+    Tides { location: String },
+    Lookup { coordinates: (f64, f64), save: bool, nickname: Option<String> },
+    Quakes { min_mag: f64, hours: u64 },
+    AirportSearch { pattern: String, filter: AirportFilter, category: Option<FlightCategory> },
+    AirportsNear { location: String, radius_km: f64, limit: usize, filter: AirportFilter },
+    AirportDetails { code: String },
+    AirportsRefresh,
+    Addresses(AddressCommand),
+    Cache(CacheCommand),
+    Help,
+}
+
+#[derive(Debug)]
+pub enum CacheCommand {
+    List,
+    /// `expired_only` keeps entries still within the TTL
+    Clear { expired_only: bool },
+}
+
+#[derive(Debug)]
+pub enum AddressCommand {
+    List { tag: Option<String> },
+    Add { address: String, edits: AddressEdits },
+    Edit { key: String, edits: AddressEdits },
+    Delete { key: String },
+    /// `position` starts at 1
+    Move { key: String, position: usize },
+    Import { path: PathBuf },
+    /// Re-geocode every entry with the Census batch geocoder
+    Normalize,
+    /// `path` "-" writes to stdout
+    Export { path: PathBuf, format: ExportFormat, tag: Option<String> },
+}
+
+/// Field changes for an address book entry; `None` leaves a field alone and
+/// an empty nickname or notes string clears it.
+#[derive(Debug, Default)]
+pub struct AddressEdits {
+    pub nickname: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub notes: Option<String>,
+}
+
+impl Command {
+    /// Whether the command needs the airport database loaded first.
+    pub fn needs_airports(&self) -> bool {
+        !matches!(self, Command::Quakes { .. } | Command::Lookup { .. } | Command::AirportsRefresh | Command::Addresses(_) | Command::Cache(_) | Command::Help)
+    }
+}
+
+/// Parses the process arguments (without the program name).
+/// Global options such as `--format` and `--units` may appear anywhere on the line.
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Invocation, AppError> {
+    let mut format = OutputFormat::Text;
+    let mut units = None;
+    let mut no_cache = false;
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--format" {
+            let value = args.next().ok_or_else(|| AppError::UserInput("--format requires a value (text or json).".to_string()))?;
+            format = parse_format(&value)?;
+        } else if let Some(value) = arg.strip_prefix("--format=") {
+            format = parse_format(value)?;
+        } else if arg == "--units" {
+            let value = args.next().ok_or_else(|| AppError::UserInput("--units requires a value (imperial, metric or aviation).".to_string()))?;
+            units = Some(value.parse()?);
+        } else if let Some(value) = arg.strip_prefix("--units=") {
+            units = Some(value.parse()?);
+        } else if arg == "--no-cache" {
+            no_cache = true;
+        } else {
+            positional.push(arg);
+        }
+    }
+    let command = parse_command(positional)?;
+    if format == OutputFormat::Json && command.is_none() {
+        return Err(AppError::UserInput("--format json needs a command; the interactive menu is text only.".to_string()));
+    }
+    Ok(Invocation { format, units, no_cache, command })
+}
+
+fn parse_format(value: &str) -> Result<OutputFormat, AppError> {
+    match value.to_ascii_lowercase().as_str() {
+        "text" => Ok(OutputFormat::Text),
+        "json" => Ok(OutputFormat::Json),
+        other => Err(AppError::UserInput(format!("Unknown format '{}'. Use text or json.", other))),
+    }
+}
+
+fn parse_command(args: Vec<String>) -> Result<Option<Command>, AppError> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        return Ok(None);
+    };
+    let rest: Vec<String> = args.collect();
+
+    let command = match command.as_str() {
+        "weather" => Command::Weather { location: join_location(&rest, "weather")? },
+        "stations" => parse_stations(&rest)?,
+        "history" => parse_history(&rest)?,
+        "forecast" => parse_forecast(&rest)?,
+        "hourly" => parse_hourly(&rest)?,
+        "alerts" => Command::Alerts { location: join_location(&rest, "alerts")? },
+        "tides" => Command::Tides { location: join_location(&rest, "tides")? },
+        "lookup" => parse_lookup(&rest)?,
+        "quakes" | "earthquakes" => parse_quakes(&rest)?,
+        "airports" => parse_airports(&rest)?,
+        "addresses" => Command::Addresses(parse_addresses(&rest)?),
+        "cache" => Command::Cache(parse_cache(&rest)?),
+        "help" | "-h" | "--help" => Command::Help,
+        other => {
+            return Err(AppError::UserInput(format!("Unknown command '{}'. Run 'reather help' for usage.", other)));
+        }
+    };
+    Ok(Some(command))
+}
+
+// Locations may be passed quoted or as several words; join them back together.
+fn join_location(rest: &[String], command: &str) -> Result<String, AppError> {
+    let location = rest.join(" ").trim().to_string();
+    if location.is_empty() {
+        return Err(AppError::UserInput(format!("'{}' needs an address or airport code, e.g. 'reather {} KBZN'.", command, command)));
+    }
+    Ok(location)
+}
+
+fn parse_stations(rest: &[String]) -> Result<Command, AppError> {
+    let mut use_station = None;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--use" => use_station = Some(flag_value(&mut iter, "--use")?.trim().to_ascii_uppercase()),
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'stations'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let location = join_location(&words, "stations")?;
+    Ok(Command::Stations { location, use_station })
+}
+
+fn parse_forecast(rest: &[String]) -> Result<Command, AppError> {
+    let mut periods = FORECAST_PERIODS;
+    let mut view = ForecastView::Table;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--periods" => {
+                periods = flag_value(&mut iter, "--periods")?
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| (1..=FORECAST_PERIODS).contains(n))
+                    .ok_or_else(|| AppError::UserInput(format!("--periods expects a number from 1 to {}.", FORECAST_PERIODS)))?;
+            }
+            "--detailed" => view = ForecastView::Detailed,
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'forecast'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let location = join_location(&words, "forecast")?;
+    Ok(Command::Forecast { location, periods, view })
+}
+
+fn parse_hourly(rest: &[String]) -> Result<Command, AppError> {
+    let mut hours = HOURLY_FORECAST_DEFAULT_HOURS;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--hours" => {
+                hours = flag_value(&mut iter, "--hours")?
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| (1..=HOURLY_FORECAST_MAX_HOURS).contains(n))
+                    .ok_or_else(|| AppError::UserInput(format!("--hours expects a number from 1 to {}.", HOURLY_FORECAST_MAX_HOURS)))?;
+            }
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'hourly'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let location = join_location(&words, "hourly")?;
+    Ok(Command::Hourly { location, hours })
+}
+
+fn parse_history(rest: &[String]) -> Result<Command, AppError> {
+    let mut hours = HISTORY_DEFAULT_HOURS;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--hours" => {
+                hours = flag_value(&mut iter, "--hours")?
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|n| (1..=nws::MAX_HISTORY_HOURS).contains(n))
+                    .ok_or_else(|| AppError::UserInput(format!("--hours expects a number from 1 to {}.", nws::MAX_HISTORY_HOURS)))?;
+            }
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'history'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let location = join_location(&words, "history")?;
+    Ok(Command::History { location, hours })
+}
+
+fn parse_quakes(rest: &[String]) -> Result<Command, AppError> {
+    let mut min_mag = 0.0;
+    let mut hours = 24;
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--min-mag" => {
+                min_mag = flag_value(&mut iter, "--min-mag")?
+                    .parse::<f64>()
+                    .map_err(|_| AppError::UserInput("--min-mag expects a number, e.g. 5 or 6.5.".to_string()))?;
+            }
+            "--hours" => {
+                hours = flag_value(&mut iter, "--hours")?
+                    .parse::<u64>()
+                    .map_err(|_| AppError::UserInput("--hours expects a whole number of hours.".to_string()))?;
+            }
+            other => return Err(AppError::UserInput(format!("Unexpected argument '{}' for 'quakes'.", other))),
+        }
+    }
+    if hours == 0 || hours > 720 {
+        return Err(AppError::UserInput("--hours must be between 1 and 720 (30 days).".to_string()));
+    }
+    Ok(Command::Quakes { min_mag, hours })
+}
+
+fn parse_airports(rest: &[String]) -> Result<Command, AppError> {
+    match rest.first().map(|s| s.as_str()) {
+        Some("search") => {}
+        Some("near") => return parse_airports_near(&rest[1..]),
+        Some("refresh") if rest.len() == 1 => return Ok(Command::AirportsRefresh),
+        Some("show") => {
+            return match &rest[1..] {
+                [code] => Ok(Command::AirportDetails { code: code.clone() }),
+                _ => Err(AppError::UserInput("Usage: reather airports show <code>".to_string())),
+            };
+        }
+        _ => return Err(AppError::UserInput("Usage: reather airports search <pattern> [--us-only] [--passenger-only] [--category VFR|MVFR|IFR|LIFR] | airports near <address|airport> | airports show <code> | airports refresh".to_string())),
+    }
+    let mut filter = AirportFilter::default();
+    let mut category = None;
+    let mut words = Vec::new();
+    let mut iter = rest[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--us-only" => filter.us_only = true,
+            "--passenger-only" => filter.scheduled_only = true,
+            "--category" => {
+                category = Some(flag_value(&mut iter, "--category")?.parse::<FlightCategory>().map_err(AppError::UserInput)?);
+            }
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'airports search'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let pattern = words.join(" ");
+    if pattern.trim().is_empty() {
+        return Err(AppError::UserInput("'airports search' needs a search pattern, e.g. 'Rome*'.".to_string()));
+    }
+    Ok(Command::AirportSearch { pattern, filter, category })
+}
+
+fn parse_airports_near(rest: &[String]) -> Result<Command, AppError> {
+    let mut radius_km = 50.0;
+    let mut limit = 10;
+    let mut filter = AirportFilter::default();
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--radius" => {
+                radius_km = flag_value(&mut iter, "--radius")?
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|r| *r > 0.0 && *r <= MAX_RADIUS_KM)
+                    .ok_or_else(|| AppError::UserInput(format!("--radius expects a positive number of kilometres, at most {}.", MAX_RADIUS_KM)))?;
+            }
+            "--limit" => {
+                limit = flag_value(&mut iter, "--limit")?
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n >= 1)
+                    .ok_or_else(|| AppError::UserInput("--limit expects a whole number of at least 1.".to_string()))?;
+            }
+            "--us-only" => filter.us_only = true,
+            "--passenger-only" => filter.scheduled_only = true,
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'airports near'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let location = join_location(&words, "airports near")?;
+    Ok(Command::AirportsNear { location, radius_km, limit, filter })
+}
+
+fn parse_lookup(rest: &[String]) -> Result<Command, AppError> {
+    let mut save = false;
+    let mut nickname = None;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--save" => save = true,
+            "--nickname" => nickname = Some(flag_value(&mut iter, "--nickname")?.clone()),
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'lookup'.", other)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    // "45.68, -111.04" arrives as two words
+    let text = words.join(" ");
+    if text.trim().is_empty() {
+        return Err(AppError::UserInput("'lookup' needs coordinates or a Google Maps link, e.g. 'reather lookup 45.68,-111.04'.".to_string()));
+    }
+    let coordinates = parse_coordinates(&text).ok_or_else(|| {
+        AppError::UserInput(format!("'{}' is not a lat,lon pair or a Google Maps link with coordinates.", text))
+    })?;
+    if nickname.is_some() && !save {
+        return Err(AppError::UserInput("--nickname needs --save.".to_string()));
+    }
+    Ok(Command::Lookup { coordinates, save, nickname })
+}
+
+fn parse_cache(rest: &[String]) -> Result<CacheCommand, AppError> {
+    let args: Vec<&str> = rest.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["list"] => Ok(CacheCommand::List),
+        ["clear"] => Ok(CacheCommand::Clear { expired_only: false }),
+        ["clear", "--expired"] => Ok(CacheCommand::Clear { expired_only: true }),
+        _ => Err(AppError::UserInput("Usage: reather cache list | clear [--expired]".to_string())),
+    }
+}
+
+const ADDRESSES_USAGE: &str = "Usage: reather addresses list [--tag TAG] | add <address> [--nickname NAME] [--tags a,b] [--notes TEXT] | edit <id|nickname> [...] | delete <id|nickname> | move <id|nickname> <position> | import <file.csv> | normalize | export <file|-> [--as csv|geojson|vcard] [--tag TAG]";
+
+fn parse_addresses(rest: &[String]) -> Result<AddressCommand, AppError> {
+    let Some(action) = rest.first() else {
+        return Err(AppError::UserInput(ADDRESSES_USAGE.to_string()));
+    };
+    let mut edits = AddressEdits::default();
+    let mut tag = None;
+    let mut export_format = None;
+    let mut words: Vec<String> = Vec::new();
+    let mut iter = rest[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--nickname" => edits.nickname = Some(flag_value(&mut iter, "--nickname")?.clone()),
+            "--tags" => edits.tags = Some(split_tags(flag_value(&mut iter, "--tags")?)),
+            "--notes" => edits.notes = Some(flag_value(&mut iter, "--notes")?.clone()),
+            "--tag" if action == "list" || action == "export" => tag = Some(flag_value(&mut iter, "--tag")?.clone()),
+            "--as" if action == "export" => export_format = Some(flag_value(&mut iter, "--as")?.parse::<ExportFormat>()?),
+            other if other.starts_with("--") => {
+                return Err(AppError::UserInput(format!("Unknown option '{}' for 'addresses {}'.", other, action)));
+            }
+            other => words.push(other.to_string()),
+        }
+    }
+    let has_edits = edits.nickname.is_some() || edits.tags.is_some() || edits.notes.is_some();
+    match (action.as_str(), words.as_slice()) {
+        ("list", []) if !has_edits => Ok(AddressCommand::List { tag }),
+        ("add", [_, ..]) => Ok(AddressCommand::Add { address: words.join(" "), edits }),
+        ("edit", [key]) if has_edits => Ok(AddressCommand::Edit { key: key.clone(), edits }),
+        ("edit", [_]) => Err(AppError::UserInput("'addresses edit' needs at least one of --nickname, --tags or --notes.".to_string())),
+        ("delete", [key]) if !has_edits => Ok(AddressCommand::Delete { key: key.clone() }),
+        ("move", [key, position]) if !has_edits => {
+            let position = position.parse::<usize>()
+                .ok()
+                .filter(|p| *p >= 1)
+                .ok_or_else(|| AppError::UserInput("Position must be a number starting at 1.".to_string()))?;
+            Ok(AddressCommand::Move { key: key.clone(), position })
+        }
+        ("normalize", []) if !has_edits => Ok(AddressCommand::Normalize),
+        ("import", [path]) if !has_edits => Ok(AddressCommand::Import { path: PathBuf::from(path) }),
+        ("export", [path]) if !has_edits => {
+            let path = PathBuf::from(path);
+            let format = match export_format {
+                Some(format) => format,
+                None if path.as_os_str() == "-" => ExportFormat::Csv,
+                None => ExportFormat::from_path(&path).ok_or_else(|| {
+                    AppError::UserInput(format!("Cannot tell the export format from '{}'; add --as csv|geojson|vcard.", path.display()))
+                })?,
+            };
+            Ok(AddressCommand::Export { path, format, tag })
+        }
+        _ => Err(AppError::UserInput(ADDRESSES_USAGE.to_string())),
+    }
+}
+
+fn flag_value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> Result<&'a String, AppError> {
+    iter.next().ok_or_else(|| AppError::UserInput(format!("{} requires a value.", flag)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Result<Invocation, AppError> {
+        parse_args(line.split_whitespace().map(String::from))
+    }
+
+    fn command(line: &str) -> Command {
+        parse(line).unwrap().command.unwrap()
+    }
+
+    fn rejects(line: &str, message: &str) {
+        match parse(line) {
+            Err(AppError::UserInput(e)) => assert!(e.contains(message), "'{}' gave '{}'", line, e),
+            other => panic!("'{}' should be rejected, got {:?}", line, other),
+        }
+    }
+
+    #[test]
+    fn no_arguments_runs_the_menu() {
+        let invocation = parse("").unwrap();
+        assert!(invocation.command.is_none());
+        assert_eq!(invocation.format, OutputFormat::Text);
+        rejects("--format json", "interactive menu");
+    }
+
+    #[test]
+    fn global_options_anywhere_on_the_line() {
+        let invocation = parse("weather --format json KBZN --units metric --no-cache").unwrap();
+        assert_eq!(invocation.format, OutputFormat::Json);
+        assert_eq!(invocation.units, Some(UnitSystem::Metric));
+        assert!(invocation.no_cache);
+        assert!(matches!(invocation.command, Some(Command::Weather { location }) if location == "KBZN"));
+        assert_eq!(parse("--format=JSON alerts BZN").unwrap().format, OutputFormat::Json);
+        rejects("--format xml weather KBZN", "Unknown format 'xml'");
+        rejects("weather KBZN --format", "--format requires a value");
+    }
+
+    #[test]
+    fn locations_are_joined_from_several_words() {
+        let Command::Forecast { location, periods, view } = command("forecast 233 E Main St, Bozeman --periods 4 --detailed") else {
+            panic!("expected a forecast");
+        };
+        assert_eq!(location, "233 E Main St, Bozeman");
+        assert_eq!(periods, 4);
+        assert_eq!(view, ForecastView::Detailed);
+        rejects("weather", "needs an address or airport code");
+        rejects("forecast KBZN --periods 15", "--periods expects a number from 1 to 14");
+    }
+
+    #[test]
+    fn airport_search_flags() {
+        let Command::AirportSearch { pattern, filter, category } = command("airports search Rome* --us-only --passenger-only --category ifr") else {
+            panic!("expected an airport search");
+        };
+        assert_eq!(pattern, "Rome*");
+        assert!(filter.us_only && filter.scheduled_only);
+        assert_eq!(category, Some(FlightCategory::IFR));
+        rejects("airports search --us-only", "needs a search pattern");
+        rejects("airports search Rome --category XFR", "XFR");
+        rejects("airports search Rome --bogus", "Unknown option '--bogus'");
+    }
+
+    #[test]
+    fn airports_near_validates_radius_and_limit() {
+        let Command::AirportsNear { location, radius_km, limit, .. } = command("airports near KBZN") else {
+            panic!("expected airports near");
+        };
+        assert_eq!((location.as_str(), radius_km, limit), ("KBZN", 50.0, 10));
+        let Command::AirportsNear { radius_km, limit, .. } = command("airports near 45.68,-111.04 --radius 120.5 --limit 3") else {
+            panic!("expected airports near");
+        };
+        assert_eq!((radius_km, limit), (120.5, 3));
+        rejects("airports near KBZN --radius 0", "--radius expects a positive number");
+        rejects("airports near KBZN --radius -5", "--radius expects a positive number");
+        rejects("airports near KBZN --radius far", "--radius expects a positive number");
+        rejects("airports near KBZN --radius 1e12", "at most 20038");
+        assert!(matches!(command("airports near KBZN --radius 20038"), Command::AirportsNear { .. }));
+        rejects("airports near KBZN --limit 2.5", "--limit expects a whole number");
+        rejects("airports near KBZN --limit 0", "--limit expects a whole number of at least 1");
+        rejects("airports near KBZN --limit", "--limit requires a value");
+    }
+
+    #[test]
+    fn quakes_validates_hours() {
+        assert!(matches!(command("quakes --min-mag 4.5 --hours 48"), Command::Quakes { min_mag, hours: 48 } if min_mag == 4.5));
+        rejects("quakes --hours 0", "between 1 and 720");
+        rejects("quakes --hours 721", "between 1 and 720");
+        rejects("quakes --min-mag big", "--min-mag expects a number");
+    }
+
+    #[test]
+    fn unknown_commands_are_rejected() {
+        rejects("wether KBZN", "Unknown command 'wether'");
+        rejects("airports", "Usage: reather airports");
+        rejects("airports list", "Usage: reather airports");
+        rejects("cache purge", "Usage: reather cache");
+        rejects("addresses rename Home Work", "Usage: reather addresses");
+        assert!(matches!(command("--help"), Command::Help));
+    }
+}
@@ -0,0 +1,293 @@
+// Runtime configuration: upstream base URLs, display units and the data directory
+//
+// Settings come from `reather.toml` in the data directory (or the file named by
+// REATHER_CONFIG), and individual REATHER_* environment variables override the file.
+// This lets every upstream be pointed at an on-prem mirror or a local stand-in server.
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+use crate::error::io_error_with_path;
+use crate::geocode::GeocoderKind;
+use crate::units::UnitSystem;
+use crate::AppError;
+
+pub const DATA_DIR: &str = "data";
+pub const CONFIG_FILE: &str = "reather.toml";
+
+pub const DEFAULT_CENSUS_URL: &str = "https://geocoding.geo.census.gov";
+pub const DEFAULT_NWS_URL: &str = "https://api.weather.gov";
+pub const DEFAULT_USGS_URL: &str = "https://earthquake.usgs.gov";
+pub const DEFAULT_NOAA_TIDES_URL: &str = "https://api.tidesandcurrents.noaa.gov";
+pub const DEFAULT_FCC_URL: &str = "https://geo.fcc.gov";
+pub const DEFAULT_NOMINATIM_URL: &str = "https://nominatim.openstreetmap.org";
+pub const DEFAULT_OURAIRPORTS_URL: &str = "https://raw.githubusercontent.com/davidmegginson/ourairports-data/main";
+pub const DEFAULT_AVIATION_WEATHER_URL: &str = "https://aviationweather.gov";
+
+/// Base URLs (scheme, host and optional path prefix, no trailing slash) of every upstream service.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Endpoints {
+    /// US Census geocoder (REATHER_CENSUS_URL)
+    pub census: String,
+    /// api.weather.gov (REATHER_NWS_URL)
+    pub nws: String,
+    /// USGS earthquake feeds (REATHER_USGS_URL)
+    pub usgs: String,
+    /// NOAA CO-OPS tides API and metadata API (REATHER_NOAA_TIDES_URL)
+    pub noaa_tides: String,
+    /// FCC census block API (REATHER_FCC_URL)
+    pub fcc: String,
+    /// Nominatim reverse geocoder (REATHER_NOMINATIM_URL)
+    pub nominatim: String,
+    /// Directory holding the OurAirports CSV files (REATHER_OURAIRPORTS_URL)
+    pub ourairports: String,
+    /// aviationweather.gov data API for METAR and TAF reports (REATHER_AVIATION_WEATHER_URL)
+    pub aviation_weather: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Endpoints {
+            census: DEFAULT_CENSUS_URL.to_string(),
+            nws: DEFAULT_NWS_URL.to_string(),
+            usgs: DEFAULT_USGS_URL.to_string(),
+            noaa_tides: DEFAULT_NOAA_TIDES_URL.to_string(),
+            fcc: DEFAULT_FCC_URL.to_string(),
+            nominatim: DEFAULT_NOMINATIM_URL.to_string(),
+            ourairports: DEFAULT_OURAIRPORTS_URL.to_string(),
+            aviation_weather: DEFAULT_AVIATION_WEATHER_URL.to_string(),
+        }
+    }
+}
+
+/// Caching of downloaded datasets such as the OurAirports CSV files.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DatasetsConfig {
+    /// Revalidate cached copies older than this (default: one week)
+    pub refresh_after_hours: u64,
+}
+
+impl Default for DatasetsConfig {
+    fn default() -> Self {
+        DatasetsConfig { refresh_after_hours: 168 }
+    }
+}
+
+/// Forward geocoding providers and local caching of their results.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GeocodingConfig {
+    /// Providers tried in order until one matches (REATHER_GEOCODERS, comma-separated)
+    pub providers: Vec<GeocoderKind>,
+    /// Consult and fill the geocoding cache (false with --no-cache on the command line)
+    pub cache: bool,
+    /// Ask the geocoder again once an entry is older than this (default: 90 days)
+    pub cache_ttl_days: u64,
+}
+
+impl Default for GeocodingConfig {
+    fn default() -> Self {
+        GeocodingConfig {
+            providers: vec![GeocoderKind::Manual, GeocoderKind::Census, GeocoderKind::Nominatim],
+            cache: true,
+            cache_ttl_days: 90,
+        }
+    }
+}
+
+/// Choice of NWS observation station for current conditions.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct StationsConfig {
+    /// How many of the closest stations to list and to try in turn
+    pub candidates: usize,
+    /// Fall back to the next station when the latest observation is older than this
+    pub max_observation_age_minutes: u64,
+}
+
+impl Default for StationsConfig {
+    fn default() -> Self {
+        StationsConfig { candidates: 5, max_observation_age_minutes: 90 }
+    }
+}
+
+/// Runway wind checks in airport details.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RunwaysConfig {
+    /// Flag runway ends whose crosswind (or gust crosswind) exceeds this many knots
+    pub crosswind_limit_kt: f64,
+}
+
+impl Default for RunwaysConfig {
+    fn default() -> Self {
+        RunwaysConfig { crosswind_limit_kt: 15.0 }
+    }
+}
+
+/// Pressure and density altitude in airport details.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AltitudeConfig {
+    /// Warn when density altitude reaches this many feet
+    pub high_density_altitude_ft: f64,
+}
+
+impl Default for AltitudeConfig {
+    fn default() -> Self {
+        AltitudeConfig { high_density_altitude_ft: 6000.0 }
+    }
+}
+
+/// How results are presented.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// imperial (default), metric or aviation (REATHER_UNITS, or --units on the command line)
+    pub units: UnitSystem,
+}
+
+/// Contents of `reather.toml`. Every section and key is optional.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub endpoints: Endpoints,
+    pub datasets: DatasetsConfig,
+    pub geocoding: GeocodingConfig,
+    pub stations: StationsConfig,
+    pub runways: RunwaysConfig,
+    pub altitude: AltitudeConfig,
+    pub display: DisplayConfig,
+}
+
+impl Config {
+    /// Reads a config file; a missing file yields the defaults.
+    pub fn from_file(path: &Path) -> Result<Config, AppError> {
+        if !path.exists() {
+            return Ok(Config::default());
+        }
+        let text = fs::read_to_string(path).map_err(|e| io_error_with_path(e, path))?;
+        toml::from_str(&text).map_err(|e| AppError::Config(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Applies REATHER_* environment variable overrides on top of the current values.
+    pub fn apply_env(mut self) -> Config {
+        let overrides: [(&str, &mut String); 8] = [
+            ("REATHER_CENSUS_URL", &mut self.endpoints.census),
+            ("REATHER_NWS_URL", &mut self.endpoints.nws),
+            ("REATHER_USGS_URL", &mut self.endpoints.usgs),
+            ("REATHER_NOAA_TIDES_URL", &mut self.endpoints.noaa_tides),
+            ("REATHER_FCC_URL", &mut self.endpoints.fcc),
+            ("REATHER_NOMINATIM_URL", &mut self.endpoints.nominatim),
+            ("REATHER_OURAIRPORTS_URL", &mut self.endpoints.ourairports),
+            ("REATHER_AVIATION_WEATHER_URL", &mut self.endpoints.aviation_weather),
+        ];
+        for (var, field) in overrides {
+            if let Ok(value) = env::var(var) {
+                if !value.trim().is_empty() {
+                    *field = value;
+                }
+            }
+        }
+        self.normalize();
+        self
+    }
+
+    /// Loads the config file (REATHER_CONFIG or `reather.toml` in the data directory)
+    /// and applies environment overrides.
+    pub fn load() -> Result<Config, AppError> {
+        let path = env::var_os("REATHER_CONFIG")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| data_dir().join(CONFIG_FILE));
+        let mut config = Config::from_file(&path)?.apply_env();
+        if let Ok(units) = env::var("REATHER_UNITS") {
+            config.display.units = units.parse().map_err(|_| {
+                AppError::Config(format!("REATHER_UNITS: unknown unit system '{}' (use imperial, metric or aviation)", units))
+            })?;
+        }
+        if let Ok(providers) = env::var("REATHER_GEOCODERS") {
+            config.geocoding.providers = providers
+                .split(',')
+                .map(|p| p.parse())
+                .collect::<Result<_, _>>()
+                .map_err(|e| AppError::Config(format!("REATHER_GEOCODERS: {}", e)))?;
+        }
+        if config.geocoding.providers.is_empty() {
+            return Err(AppError::Config("[geocoding] providers must name at least one geocoder".to_string()));
+        }
+        Ok(config)
+    }
+
+    // Base URLs are joined with paths that start with '/'
+    fn normalize(&mut self) {
+        let e = &mut self.endpoints;
+        for url in [
+            &mut e.census,
+            &mut e.nws,
+            &mut e.usgs,
+            &mut e.noaa_tides,
+            &mut e.fcc,
+            &mut e.nominatim,
+            &mut e.ourairports,
+            &mut e.aviation_weather,
+        ] {
+            let trimmed = url.trim().trim_end_matches('/').to_string();
+            *url = trimmed;
+        }
+    }
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Installs the configuration used by every fetch function.
+/// Without a call to `init`, the first use loads it with `Config::load`, falling
+/// back to the defaults (plus environment overrides) if the file is unreadable.
+pub fn init(config: Config) -> Result<(), AppError> {
+    CONFIG.set(config).map_err(|_| AppError::Config("configuration already initialized".to_string()))
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(|| Config::load().unwrap_or_else(|_| Config::default().apply_env()))
+}
+
+pub fn endpoints() -> &'static Endpoints {
+    &get().endpoints
+}
+
+/// The unit system for text output.
+pub fn units() -> UnitSystem {
+    get().display.units
+}
+
+/// Points an absolute api.weather.gov URL (as returned inside NWS responses)
+/// at the configured NWS base URL.
+pub fn rebase_nws_url(url: &str) -> String {
+    let base = &endpoints().nws;
+    match url.strip_prefix(DEFAULT_NWS_URL) {
+        Some(path) if base != DEFAULT_NWS_URL => format!("{}{}", base, path),
+        _ => url.to_string(),
+    }
+}
+
+/// Directory for user data and caches: REATHER_DATA_DIR if set, else `./data` if it
+/// exists, else the directory containing the executable.
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("REATHER_DATA_DIR") {
+        return PathBuf::from(dir);
+    }
+    let data_dir_path = PathBuf::from(DATA_DIR);
+    if data_dir_path.exists() {
+        return data_dir_path;
+    }
+    // Do NOT create the data directory, just use the executable directory
+    env::current_exe()
+        .ok()
+        .and_then(|path| path.parent().map(|p| p.to_path_buf()))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
@@ -0,0 +1,275 @@
+// On-disk cache for downloaded datasets (the OurAirports CSV files)
+//
+// Each file is stored in the data directory next to a `<name>.meta.json` sidecar
+// holding the ETag, Last-Modified and download time. Stale copies are revalidated
+// with a conditional GET, and the cached copy is used whenever the network is down.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::error::io_error_with_path;
+use crate::{config, AppError, APP_USER_AGENT};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefreshPolicy {
+    /// Use the cached copy if it is younger than the configured maximum age
+    IfStale,
+    /// Always revalidate with the server
+    Force,
+}
+
+/// Where the returned bytes came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatasetSource {
+    /// Fresh enough on disk; no request was made
+    Cache,
+    /// Downloaded a new copy
+    Downloaded,
+    /// Server confirmed the cached copy is current (HTTP 304)
+    NotModified,
+    /// Download failed, so the cached copy was used anyway
+    StaleCache { error: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct DatasetStatus {
+    pub name: String,
+    pub source: DatasetSource,
+    /// When the data was last downloaded or revalidated
+    pub fetched_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    url: String,
+    etag: Option<String>,
+    last_modified: Option<String>,
+    fetched_at: Option<DateTime<Utc>>,
+}
+
+fn cache_path(name: &str) -> PathBuf {
+    config::data_dir().join(name)
+}
+
+fn meta_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".meta.json")
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn read_meta(path: &Path) -> Option<CacheMeta> {
+    let text = fs::read_to_string(meta_path(path)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn write_cache(path: &Path, bytes: &[u8], meta: &CacheMeta) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error_with_path(e, parent))?;
+    }
+    write_replacing(path, bytes)?;
+    write_meta(path, meta)
+}
+
+fn write_meta(path: &Path, meta: &CacheMeta) -> Result<(), AppError> {
+    write_replacing(&meta_path(path), serde_json::to_string_pretty(meta)?.as_bytes())
+}
+
+// Replaces `path` only once the new contents are completely written
+fn write_replacing(path: &Path, bytes: &[u8]) -> Result<(), AppError> {
+    let tmp_path = with_suffix(path, ".tmp");
+    fs::write(&tmp_path, bytes).map_err(|e| io_error_with_path(e, &tmp_path))?;
+    fs::rename(&tmp_path, path).map_err(|e| io_error_with_path(e, path))
+}
+
+/// Returns the contents of dataset `name` (e.g. "airports.csv") from `url`,
+/// going through the on-disk cache. Blocking; call it outside the async runtime.
+pub fn fetch_dataset(name: &str, url: &str, policy: RefreshPolicy) -> Result<(Vec<u8>, DatasetStatus), AppError> {
+    let max_age = chrono::Duration::hours(config::get().datasets.refresh_after_hours as i64);
+    fetch_cached(&cache_path(name), url, policy, max_age, |meta| download(url, meta))
+}
+
+// The cache policy behind `fetch_dataset`, for the file at `path`, with the request made by `download`
+fn fetch_cached(
+    path: &Path,
+    url: &str,
+    policy: RefreshPolicy,
+    max_age: chrono::Duration,
+    download: impl FnOnce(Option<&CacheMeta>) -> Result<Download, AppError>,
+) -> Result<(Vec<u8>, DatasetStatus), AppError> {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let cached = fs::read(path).ok();
+    // Metadata from a different URL (e.g. a mirror was configured) can't be used to revalidate
+    let meta = read_meta(path).filter(|m| m.url == url);
+
+    if let (Some(bytes), Some(fetched_at), RefreshPolicy::IfStale) = (&cached, meta.as_ref().and_then(|m| m.fetched_at), policy) {
+        if Utc::now().signed_duration_since(fetched_at) < max_age {
+            let status = DatasetStatus { name, source: DatasetSource::Cache, fetched_at };
+            return Ok((bytes.clone(), status));
+        }
+    }
+
+    match download(cached.as_ref().and(meta.as_ref())) {
+        Ok(Download::Modified { bytes, etag, last_modified }) => {
+            let now = Utc::now();
+            let meta = CacheMeta { url: url.to_string(), etag, last_modified, fetched_at: Some(now) };
+            write_cache(path, &bytes, &meta)?;
+            Ok((bytes, DatasetStatus { name, source: DatasetSource::Downloaded, fetched_at: now }))
+        }
+        Ok(Download::NotModified) => {
+            let now = Utc::now();
+            let meta = CacheMeta { fetched_at: Some(now), ..meta.unwrap_or_default() };
+            write_meta(path, &meta)?;
+            let bytes = cached.unwrap_or_default();
+            Ok((bytes, DatasetStatus { name, source: DatasetSource::NotModified, fetched_at: now }))
+        }
+        Err(e) => match cached {
+            Some(bytes) => {
+                let fetched_at = meta.and_then(|m| m.fetched_at)
+                    .or_else(|| fs::metadata(path).and_then(|m| m.modified()).ok().map(DateTime::<Utc>::from))
+                    .unwrap_or_else(Utc::now);
+                let source = DatasetSource::StaleCache { error: e.to_string() };
+                Ok((bytes, DatasetStatus { name, source, fetched_at }))
+            }
+            None => Err(e),
+        },
+    }
+}
+
+enum Download {
+    Modified { bytes: Vec<u8>, etag: Option<String>, last_modified: Option<String> },
+    NotModified,
+}
+
+fn download(url: &str, meta: Option<&CacheMeta>) -> Result<Download, AppError> {
+    let client = reqwest::blocking::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .timeout(Duration::from_secs(120))
+        .build()?;
+    let mut request = client.get(url);
+    if let Some(meta) = meta {
+        if let Some(etag) = &meta.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &meta.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send()?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Download::NotModified);
+    }
+    let response = response.error_for_status()?;
+    let header = |name| response.headers().get(name).and_then(|v: &reqwest::header::HeaderValue| v.to_str().ok()).map(str::to_string);
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let bytes = response.bytes()?.to_vec();
+    Ok(Download::Modified { bytes, etag, last_modified })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const URL: &str = "https://example.com/airports.csv";
+
+    fn temp_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("reather-test-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("airports.csv")
+    }
+
+    fn seed(path: &Path, bytes: &[u8], fetched_hours_ago: i64) {
+        let meta = CacheMeta {
+            url: URL.to_string(),
+            etag: Some("\"v1\"".to_string()),
+            last_modified: None,
+            fetched_at: Some(Utc::now() - chrono::Duration::hours(fetched_hours_ago)),
+        };
+        write_cache(path, bytes, &meta).unwrap();
+    }
+
+    fn fetch(path: &Path, policy: RefreshPolicy, result: Result<Download, AppError>) -> (Vec<u8>, DatasetSource, Option<String>) {
+        let mut sent_etag = None;
+        let (bytes, status) = fetch_cached(path, URL, policy, chrono::Duration::hours(24), |meta| {
+            sent_etag = meta.and_then(|m| m.etag.clone());
+            result
+        })
+        .unwrap();
+        (bytes, status.source, sent_etag)
+    }
+
+    fn modified(bytes: &[u8]) -> Result<Download, AppError> {
+        Ok(Download::Modified { bytes: bytes.to_vec(), etag: Some("\"v2\"".to_string()), last_modified: None })
+    }
+
+    #[test]
+    fn meta_sits_next_to_the_data_file() {
+        assert_eq!(meta_path(Path::new("data/airports.csv")), PathBuf::from("data/airports.csv.meta.json"));
+    }
+
+    #[test]
+    fn fresh_cache_is_used_without_a_request() {
+        let path = temp_path("fresh");
+        seed(&path, b"old", 1);
+        let (bytes, status) = fetch_cached(&path, URL, RefreshPolicy::IfStale, chrono::Duration::hours(24), |_| panic!("no request expected")).unwrap();
+        assert_eq!((bytes.as_slice(), status.source, status.name.as_str()), (&b"old"[..], DatasetSource::Cache, "airports.csv"));
+
+        // Forcing a refresh revalidates even a fresh copy
+        assert_eq!(fetch(&path, RefreshPolicy::Force, Ok(Download::NotModified)).1, DatasetSource::NotModified);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn stale_cache_is_revalidated() {
+        let path = temp_path("revalidate");
+        seed(&path, b"old", 48);
+        let (bytes, source, sent_etag) = fetch(&path, RefreshPolicy::IfStale, Ok(Download::NotModified));
+        assert_eq!((bytes.as_slice(), source, sent_etag.as_deref()), (&b"old"[..], DatasetSource::NotModified, Some("\"v1\"")));
+        // The revalidation counts as a fresh fetch
+        assert!(read_meta(&path).unwrap().fetched_at.unwrap() > Utc::now() - chrono::Duration::minutes(1));
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn new_copy_replaces_the_cache() {
+        let path = temp_path("download");
+        let (bytes, source, sent_etag) = fetch(&path, RefreshPolicy::IfStale, modified(b"first"));
+        assert_eq!((bytes.as_slice(), source, sent_etag), (&b"first"[..], DatasetSource::Downloaded, None));
+
+        seed(&path, b"old", 48);
+        let (bytes, source, _) = fetch(&path, RefreshPolicy::IfStale, modified(b"new"));
+        assert_eq!((bytes.as_slice(), source), (&b"new"[..], DatasetSource::Downloaded));
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(read_meta(&path).unwrap().etag.as_deref(), Some("\"v2\""));
+        assert!(!with_suffix(&path, ".tmp").exists());
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn failed_download_falls_back_to_the_cache() {
+        let path = temp_path("fallback");
+        seed(&path, b"old", 48);
+        let (bytes, source, _) = fetch(&path, RefreshPolicy::IfStale, Err(AppError::Api("offline".to_string())));
+        assert_eq!(bytes, b"old");
+        assert!(matches!(source, DatasetSource::StaleCache { error } if error.contains("offline")));
+
+        // Metadata recorded for another URL isn't used to revalidate
+        let meta = CacheMeta { url: "https://mirror.example.com/airports.csv".to_string(), ..read_meta(&path).unwrap() };
+        write_meta(&path, &meta).unwrap();
+        assert_eq!(fetch(&path, RefreshPolicy::IfStale, Ok(Download::NotModified)).2, None);
+        fs::remove_dir_all(path.parent().unwrap()).unwrap();
+
+        let missing = temp_path("missing");
+        assert!(fetch_cached(&missing, URL, RefreshPolicy::IfStale, chrono::Duration::hours(24), |_| Err(AppError::Api("offline".to_string()))).is_err());
+    }
+}
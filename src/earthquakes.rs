@@ -0,0 +1,88 @@
+// USGS earthquake GeoJSON feeds
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{config, AppError, HTTP_CLIENT};
+
+/// A single earthquake event from a USGS summary feed.
+#[derive(Debug, Clone, Serialize)]
+pub struct Earthquake {
+    pub magnitude: Option<f64>,
+    pub place: Option<String>,
+    pub time: DateTime<Utc>,
+    pub url: Option<String>,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub depth_km: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EarthquakeFeatureCollection {
+    features: Vec<EarthquakeFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EarthquakeFeature {
+    properties: EarthquakeProperties,
+    geometry: Option<EarthquakeGeometry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EarthquakeProperties {
+    mag: Option<f64>,
+    place: Option<String>,
+    time: Option<i64>,
+    url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EarthquakeGeometry {
+    coordinates: Vec<f64>, // [lon, lat, depth]
+}
+
+/// Picks the smallest USGS summary feed that covers the requested time window.
+pub fn earthquake_feed_url(max_age_hours: u64) -> String {
+    let feed = if max_age_hours <= 24 {
+        "all_day"
+    } else if max_age_hours <= 168 {
+        "all_week"
+    } else {
+        "all_month"
+    };
+    format!("{}/earthquakes/feed/v1.0/summary/{}.geojson", config::endpoints().usgs, feed)
+}
+
+/// Fetches earthquakes of at least `min_mag` from the last `max_age_hours`,
+/// in feed order (newest first).
+pub async fn fetch_earthquakes(min_mag: f64, max_age_hours: u64) -> Result<Vec<Earthquake>, AppError> {
+    let url = earthquake_feed_url(max_age_hours);
+    let resp = HTTP_CLIENT.get(&url).send().await?.error_for_status()?;
+    let data: EarthquakeFeatureCollection = resp.json().await?;
+    let now = Utc::now();
+    let max_age = chrono::Duration::hours(max_age_hours as i64);
+    let mut quakes = Vec::new();
+    for feature in data.features {
+        let mag = feature.properties.mag.unwrap_or(-999.0);
+        if mag < min_mag { continue; }
+        let Some(event_time) = feature.properties.time.and_then(|ms| Utc.timestamp_millis_opt(ms).single()) else {
+            continue;
+        };
+        if now.signed_duration_since(event_time) > max_age { continue; }
+        let (lat, lon, depth_km) = match feature.geometry.as_ref().map(|g| g.coordinates.as_slice()) {
+            Some([lon, lat, depth, ..]) => (Some(*lat), Some(*lon), Some(*depth)),
+            Some([lon, lat]) => (Some(*lat), Some(*lon), None),
+            _ => (None, None, None),
+        };
+        quakes.push(Earthquake {
+            magnitude: feature.properties.mag,
+            place: feature.properties.place,
+            time: event_time,
+            url: feature.properties.url,
+            lat,
+            lon,
+            depth_km,
+        });
+    }
+    Ok(quakes)
+}
@@ -0,0 +1,71 @@
+// Error type shared by the reather library and the CLI
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug)]
+pub enum AppError {
+    Io(io::Error, Option<String>),
+    Network(reqwest::Error),
+    Api(String), // For API-specific errors (e.g., bad status, missing data)
+    JsonParse(serde_json::Error),
+    UserInput(String),
+    Config(String), // Malformed reather.toml or settings
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::Io(err, path) => {
+                if let Some(p) = path {
+                    write!(f, "File I/O error for \'{}\': {}", p, err)
+                } else {
+                    write!(f, "File I/O error: {}", err)
+                }
+            }
+            AppError::Network(err) => write!(f, "Network error: {}. Please check your internet connection.", err),
+            AppError::Api(msg) => write!(f, "API error: {}", msg),
+            AppError::JsonParse(err) => write!(f, "JSON parsing error: {}", err),
+            AppError::UserInput(msg) => write!(f, "Invalid input: {}", msg),
+            AppError::Config(msg) => write!(f, "Configuration error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AppError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AppError::Io(err, _) => Some(err),
+            AppError::Network(err) => Some(err),
+            AppError::JsonParse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+// Convert io::Error to AppError
+impl From<io::Error> for AppError {
+    fn from(err: io::Error) -> AppError {
+        AppError::Io(err, None)
+    }
+}
+
+/// Helper to create `AppError::Io` with a path
+pub fn io_error_with_path(err: io::Error, path: &Path) -> AppError {
+    AppError::Io(err, Some(path.display().to_string()))
+}
+
+// Convert reqwest::Error to AppError
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> AppError {
+        AppError::Network(err)
+    }
+}
+
+// Convert serde_json::Error to AppError
+impl From<serde_json::Error> for AppError {
+    fn from(err: serde_json::Error) -> AppError {
+        AppError::JsonParse(err)
+    }
+}
@@ -0,0 +1,137 @@
+// FAA flight categories (VFR, MVFR, IFR, LIFR) from ceiling and visibility
+//
+// The ceiling is the base of the lowest broken (BKN), overcast (OVC) or obscured
+// (VV, vertical visibility) layer; FEW and SCT layers never form a ceiling. A
+// category is the worse of the ceiling band and the visibility band.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+/// FAA flight category, from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+pub enum FlightCategory {
+    /// Ceiling above 3,000 ft and visibility above 5 SM
+    VFR,
+    /// Marginal VFR: ceiling 1,000 to 3,000 ft or visibility 3 to 5 SM
+    MVFR,
+    /// Ceiling 500 to below 1,000 ft or visibility 1 to below 3 SM
+    IFR,
+    /// Low IFR: ceiling below 500 ft or visibility below 1 SM
+    LIFR,
+}
+
+impl FlightCategory {
+    pub const ALL: [FlightCategory; 4] = [FlightCategory::VFR, FlightCategory::MVFR, FlightCategory::IFR, FlightCategory::LIFR];
+
+    /// Category for a ceiling (ft AGL, `None` when there is no ceiling) and a visibility
+    /// (statute miles). `None` when the visibility is unknown and there is no ceiling to go on.
+    pub fn from_conditions(ceiling_ft: Option<f64>, visibility_sm: Option<f64>) -> Option<FlightCategory> {
+        let by_ceiling = ceiling_ft.map(|ft| {
+            if ft < 500.0 {
+                FlightCategory::LIFR
+            } else if ft < 1000.0 {
+                FlightCategory::IFR
+            } else if ft <= 3000.0 {
+                FlightCategory::MVFR
+            } else {
+                FlightCategory::VFR
+            }
+        });
+        let by_visibility = visibility_sm.map(|sm| {
+            if sm < 1.0 {
+                FlightCategory::LIFR
+            } else if sm < 3.0 {
+                FlightCategory::IFR
+            } else if sm <= 5.0 {
+                FlightCategory::MVFR
+            } else {
+                FlightCategory::VFR
+            }
+        });
+        match (by_ceiling, by_visibility) {
+            (Some(c), Some(v)) => Some(c.max(v)),
+            (Some(c), None) => Some(c),
+            // No ceiling is unlimited, so visibility decides
+            (None, v) => v,
+        }
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            FlightCategory::VFR => "visual flight rules",
+            FlightCategory::MVFR => "marginal visual flight rules",
+            FlightCategory::IFR => "instrument flight rules",
+            FlightCategory::LIFR => "low instrument flight rules",
+        }
+    }
+}
+
+impl fmt::Display for FlightCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlightCategory::VFR => "VFR",
+            FlightCategory::MVFR => "MVFR",
+            FlightCategory::IFR => "IFR",
+            FlightCategory::LIFR => "LIFR",
+        })
+    }
+}
+
+impl FromStr for FlightCategory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        FlightCategory::ALL
+            .into_iter()
+            .find(|category| category.to_string().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| format!("unknown flight category '{}' (use VFR, MVFR, IFR or LIFR)", s))
+    }
+}
+
+/// Whether a cloud cover code (BKN, OVC, VV, ...) counts as a ceiling.
+pub fn is_ceiling_cover(cover: &str) -> bool {
+    matches!(cover, "BKN" | "OVC" | "VV")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worse_of_ceiling_and_visibility_wins() {
+        assert_eq!(FlightCategory::from_conditions(Some(5000.0), Some(10.0)), Some(FlightCategory::VFR));
+        assert_eq!(FlightCategory::from_conditions(Some(5000.0), Some(2.0)), Some(FlightCategory::IFR));
+        assert_eq!(FlightCategory::from_conditions(Some(400.0), Some(10.0)), Some(FlightCategory::LIFR));
+    }
+
+    #[test]
+    fn band_edges_follow_faa_definitions() {
+        assert_eq!(FlightCategory::from_conditions(Some(3000.0), None), Some(FlightCategory::MVFR));
+        assert_eq!(FlightCategory::from_conditions(Some(1000.0), None), Some(FlightCategory::MVFR));
+        assert_eq!(FlightCategory::from_conditions(Some(500.0), None), Some(FlightCategory::IFR));
+        assert_eq!(FlightCategory::from_conditions(None, Some(5.0)), Some(FlightCategory::MVFR));
+        assert_eq!(FlightCategory::from_conditions(None, Some(3.0)), Some(FlightCategory::MVFR));
+        assert_eq!(FlightCategory::from_conditions(None, Some(1.0)), Some(FlightCategory::IFR));
+        assert_eq!(FlightCategory::from_conditions(None, Some(0.75)), Some(FlightCategory::LIFR));
+    }
+
+    #[test]
+    fn unknown_without_visibility_or_ceiling() {
+        assert_eq!(FlightCategory::from_conditions(None, None), None);
+    }
+
+    #[test]
+    fn parses_case_insensitively() {
+        assert_eq!("ifr".parse::<FlightCategory>(), Ok(FlightCategory::IFR));
+        assert_eq!(" LIFR ".parse::<FlightCategory>(), Ok(FlightCategory::LIFR));
+        assert!("IMC".parse::<FlightCategory>().is_err());
+    }
+
+    #[test]
+    fn only_broken_overcast_and_obscured_layers_are_ceilings() {
+        assert!(is_ceiling_cover("BKN") && is_ceiling_cover("OVC") && is_ceiling_cover("VV"));
+        assert!(!is_ceiling_cover("FEW") && !is_ceiling_cover("SCT") && !is_ceiling_cover("CLR"));
+    }
+}
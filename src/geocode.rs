@@ -0,0 +1,602 @@
+// Forward geocoding (US Census, Nominatim, typed coordinates) and reverse lookups (FCC, Nominatim)
+//
+// Each forward geocoder implements `Geocoder`. `geocode_address` tries the
+// providers configured under `[geocoding] providers` in order and returns the
+// first match, tagged with the provider that found it.
+
+use std::fmt;
+use std::future::Future;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{config, geocode_cache, AppError, APP_USER_AGENT, HTTP_CLIENT};
+
+/// An address as matched by the geocoder, with its coordinates.
+#[derive(Debug, Clone, Serialize)]
+pub struct GeocodedAddress {
+    pub matched_address: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// The geocoder that produced the match
+    pub provider: GeocoderKind,
+}
+
+/// A forward geocoder: turns free text into a matched address and coordinates.
+pub trait Geocoder {
+    fn kind(&self) -> GeocoderKind;
+
+    /// `Ok(None)` when the provider has no match for the query.
+    fn geocode(&self, query: &str) -> impl Future<Output = Result<Option<GeocodedAddress>, AppError>> + Send;
+}
+
+/// The available geocoders, as named in `reather.toml` and REATHER_GEOCODERS.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeocoderKind {
+    /// US Census one-line geocoder; US street addresses only
+    Census,
+    /// OpenStreetMap Nominatim; worldwide, including places and PO box towns
+    Nominatim,
+    /// "lat,lon" typed by the user
+    Manual,
+}
+
+impl FromStr for GeocoderKind {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "census" => Ok(GeocoderKind::Census),
+            "nominatim" => Ok(GeocoderKind::Nominatim),
+            "manual" => Ok(GeocoderKind::Manual),
+            other => Err(AppError::UserInput(format!("Unknown geocoder '{}'. Use census, nominatim or manual.", other))),
+        }
+    }
+}
+
+impl fmt::Display for GeocoderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GeocoderKind::Census => "census",
+            GeocoderKind::Nominatim => "nominatim",
+            GeocoderKind::Manual => "manual",
+        })
+    }
+}
+
+impl GeocoderKind {
+    async fn geocode(self, query: &str) -> Result<Option<GeocodedAddress>, AppError> {
+        match self {
+            GeocoderKind::Census => CensusGeocoder.geocode(query).await,
+            GeocoderKind::Nominatim => NominatimGeocoder.geocode(query).await,
+            GeocoderKind::Manual => ManualGeocoder.geocode(query).await,
+        }
+    }
+}
+
+// Structs for deserializing Census Geocoding API response
+#[derive(Deserialize, Debug)]
+struct GeocodeResponse {
+    result: GeocodeResult,
+}
+
+#[derive(Deserialize, Debug)]
+struct GeocodeResult {
+    #[serde(rename = "addressMatches")]
+    address_matches: Vec<AddressMatch>,
+}
+
+#[derive(Deserialize, Debug)]
+struct AddressMatch {
+    #[serde(rename = "matchedAddress")]
+    matched_address: String,
+    coordinates: Coordinates,
+}
+
+#[derive(Deserialize, Debug)]
+struct Coordinates {
+    x: f64, // longitude
+    y: f64, // latitude
+}
+
+/// Geocodes free text with the configured provider chain (default: manual
+/// coordinates, then Census, then Nominatim) and returns the first match.
+/// Returns `Ok(None)` when no provider finds a match.
+///
+/// Fresh matches come from the geocoding cache without a request; when no
+/// provider can be reached, an expired cache entry is used instead.
+pub async fn geocode_address(address_query: &str) -> Result<Option<GeocodedAddress>, AppError> {
+    let settings = &config::get().geocoding;
+    let cached = if settings.cache { geocode_cache::lookup(address_query) } else { None };
+    if let Some(entry) = cached.as_ref().filter(|e| e.is_fresh()) {
+        return Ok(Some(entry.to_geocoded()));
+    }
+    let mut first_error = None;
+    for provider in &settings.providers {
+        match provider.geocode(address_query).await {
+            Ok(Some(geocoded)) => {
+                // Typed coordinates are cheaper to parse than to look up
+                if settings.cache && geocoded.provider != GeocoderKind::Manual {
+                    geocode_cache::store(address_query, &geocoded);
+                }
+                return Ok(Some(geocoded));
+            }
+            Ok(None) => {}
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+    match (cached, first_error) {
+        (Some(entry), Some(_)) => Ok(Some(entry.to_geocoded())),
+        (_, Some(e)) => Err(e),
+        (_, None) => Ok(None),
+    }
+}
+
+/// The US Census one-line address geocoder.
+pub struct CensusGeocoder;
+
+impl Geocoder for CensusGeocoder {
+    fn kind(&self) -> GeocoderKind {
+        GeocoderKind::Census
+    }
+
+    async fn geocode(&self, address_query: &str) -> Result<Option<GeocodedAddress>, AppError> {
+        let benchmark = "Public_AR_Current";
+        let format = "json";
+        let url = format!(
+            "{}/geocoder/locations/onelineaddress?address={}&benchmark={}&format={}",
+            config::endpoints().census,
+            urlencoding::encode(address_query),
+            benchmark,
+            format
+        );
+
+        let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+
+        if response.status().is_success() {
+            let geocode_data: GeocodeResponse = response.json().await.map_err(|e| AppError::Api(format!("Failed to parse JSON response from geocoding service: {}", e)))?;
+            Ok(geocode_data.result.address_matches.into_iter().next().map(|first_match| GeocodedAddress {
+                matched_address: first_match.matched_address,
+                lat: first_match.coordinates.y,
+                lon: first_match.coordinates.x,
+                provider: self.kind(),
+            }))
+        } else {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
+            Err(AppError::Api(format!(
+                "Geocoding service returned an error (Status: {}). Details: {}",
+                status, error_text
+            )))
+        }
+    }
+}
+
+/// OpenStreetMap Nominatim search, for addresses and places outside the Census coverage.
+pub struct NominatimGeocoder;
+
+#[derive(Deserialize, Debug)]
+struct NominatimPlace {
+    lat: String,
+    lon: String,
+    display_name: String,
+}
+
+impl Geocoder for NominatimGeocoder {
+    fn kind(&self) -> GeocoderKind {
+        GeocoderKind::Nominatim
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Option<GeocodedAddress>, AppError> {
+        let url = format!("{}/search?format=jsonv2&limit=1&q={}", config::endpoints().nominatim, urlencoding::encode(query));
+        // Nominatim's usage policy requires an identifying User-Agent
+        let response = HTTP_CLIENT.get(&url).header("User-Agent", APP_USER_AGENT).send().await.map_err(AppError::Network)?;
+        if !response.status().is_success() {
+            return Err(crate::api_error("Nominatim search", &url, response).await);
+        }
+        let places: Vec<NominatimPlace> = response.json().await
+            .map_err(|e| AppError::Api(format!("Failed to parse JSON response from Nominatim: {}", e)))?;
+        Ok(places.into_iter().next().and_then(|place| {
+            Some(GeocodedAddress {
+                lat: place.lat.parse().ok()?,
+                lon: place.lon.parse().ok()?,
+                matched_address: place.display_name,
+                provider: self.kind(),
+            })
+        }))
+    }
+}
+
+/// Coordinates typed as "lat,lon" (e.g. "45.68, -111.04") or pasted as a
+/// Google Maps link; no request is made.
+pub struct ManualGeocoder;
+
+impl Geocoder for ManualGeocoder {
+    fn kind(&self) -> GeocoderKind {
+        GeocoderKind::Manual
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Option<GeocodedAddress>, AppError> {
+        Ok(parse_coordinates(query).map(|(lat, lon)| GeocodedAddress {
+            matched_address: format!("{:.5}, {:.5}", lat, lon),
+            lat,
+            lon,
+            provider: self.kind(),
+        }))
+    }
+}
+
+/// Parses "lat,lon" with both values in range; anything else is `None`.
+pub fn parse_lat_lon(text: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = text.split_once(',')?;
+    let lat: f64 = lat.trim().parse().ok()?;
+    let lon: f64 = lon.trim().parse().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Parses "lat,lon" or a Google Maps URL that carries coordinates, e.g.
+/// `https://www.google.com/maps?q=45.68,-111.04`, `.../maps/search/?api=1&query=45.68,-111.04`,
+/// `.../maps/@45.68,-111.04,15z` or a place link with `!3d45.68!4d-111.04`.
+pub fn parse_coordinates(text: &str) -> Option<(f64, f64)> {
+    let text = text.trim();
+    if let Some(coords) = parse_lat_lon(text) {
+        return Some(coords);
+    }
+    if !text.contains("google.") && !text.contains("goo.gl") {
+        return None;
+    }
+    let url = urlencoding::decode(text).map(|u| u.into_owned()).unwrap_or_else(|_| text.to_string());
+    // A place's own position, which is more precise than the map view
+    if let (Some(lat_at), Some(lon_at)) = (url.find("!3d"), url.find("!4d")) {
+        let number = |from: usize| {
+            let rest = &url[from + 3..];
+            let end = rest.find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-')).unwrap_or(rest.len());
+            rest[..end].parse::<f64>().ok()
+        };
+        if let (Some(lat), Some(lon)) = (number(lat_at), number(lon_at)) {
+            return parse_lat_lon(&format!("{},{}", lat, lon));
+        }
+    }
+    let lat_lon_prefix = |value: &str| {
+        let mut parts = value.split(',');
+        parse_lat_lon(&format!("{},{}", parts.next()?, parts.next()?))
+    };
+    // Query parameters, matched whole so "hq=" or "sq=" is not taken for "q="
+    let query = url.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    let from_query = ["q", "query", "ll", "destination"].iter().find_map(|key| {
+        query.split(['&', '#']).find_map(|param| param.strip_prefix(key)?.strip_prefix('=')).and_then(lat_lon_prefix)
+    });
+    // The map view in the path, e.g. "/maps/@45.68,-111.04,15z"
+    from_query.or_else(|| {
+        let start = url.find("/@")? + 2;
+        lat_lon_prefix(url[start..].split(['/', '?', '&']).next()?)
+    })
+}
+
+/// How the Census geocoder matched an address in a batch request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchStatus {
+    Exact,
+    #[serde(rename = "Non_Exact")]
+    NonExact,
+    /// Several equally good candidates; Census returns no coordinates
+    Tie,
+    #[serde(rename = "No_Match")]
+    NoMatch,
+}
+
+impl MatchStatus {
+    /// The Census spelling, e.g. "Non_Exact"
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchStatus::Exact => "Exact",
+            MatchStatus::NonExact => "Non_Exact",
+            MatchStatus::Tie => "Tie",
+            MatchStatus::NoMatch => "No_Match",
+        }
+    }
+}
+
+/// One record of a batch geocode, in the same order as the input.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchMatch {
+    pub status: MatchStatus,
+    /// Present for `Exact` and `Non_Exact` matches
+    pub geocoded: Option<GeocodedAddress>,
+}
+
+/// The Census batch endpoint accepts at most this many records per upload.
+pub const BATCH_GEOCODE_LIMIT: usize = 10_000;
+
+/// Geocodes many one-line addresses with the Census batch endpoint
+/// (`/geocoder/locations/addressbatch`), uploading up to `BATCH_GEOCODE_LIMIT`
+/// records per request. Returns one `BatchMatch` per input address, in order.
+/// Always asks the geocoder; matches are written to the geocoding cache.
+pub async fn geocode_batch(addresses: &[String]) -> Result<Vec<BatchMatch>, AppError> {
+    let mut matches = Vec::with_capacity(addresses.len());
+    for chunk in addresses.chunks(BATCH_GEOCODE_LIMIT) {
+        matches.extend(geocode_batch_chunk(chunk).await?);
+    }
+    // Batch results also answer later single lookups of the same query
+    if config::get().geocoding.cache {
+        geocode_cache::store_all(
+            addresses.iter().zip(&matches).filter_map(|(query, m)| m.geocoded.as_ref().map(|g| (query.as_str(), g))),
+        );
+    }
+    Ok(matches)
+}
+
+async fn geocode_batch_chunk(addresses: &[String]) -> Result<Vec<BatchMatch>, AppError> {
+    // Census expects "Unique ID, Street address, City, State, ZIP" with no header
+    let mut wtr = csv::Writer::from_writer(Vec::new());
+    for (id, address) in addresses.iter().enumerate() {
+        let (street, city, state, zip) = split_batch_address(address);
+        wtr.write_record([id.to_string().as_str(), street, city, state, zip])
+            .map_err(|e| AppError::Api(format!("Could not build batch geocoding upload: {}", e)))?;
+    }
+    let upload = wtr.into_inner().map_err(|e| AppError::Api(format!("Could not build batch geocoding upload: {}", e)))?;
+
+    let url = format!("{}/geocoder/locations/addressbatch", config::endpoints().census);
+    let part = reqwest::multipart::Part::bytes(upload)
+        .file_name("addresses.csv")
+        .mime_str("text/csv")
+        .map_err(AppError::Network)?;
+    let form = reqwest::multipart::Form::new()
+        .part("addressFile", part)
+        .text("benchmark", "Public_AR_Current");
+    let response = HTTP_CLIENT.post(&url).multipart(form).send().await.map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(crate::api_error("Batch geocoding service", &url, response).await);
+    }
+    let body = response.text().await.map_err(AppError::Network)?;
+    Ok(parse_batch_response(&body, addresses.len()))
+}
+
+// Splits a one-line address into the batch upload's street, city, state and ZIP
+// fields; anything that is not "street, city, state[,] zip" goes in the street field
+fn split_batch_address(address: &str) -> (&str, &str, &str, &str) {
+    let parts: Vec<&str> = address.split(',').map(str::trim).collect();
+    match parts.as_slice() {
+        [street, city, state, zip] => (street, city, state, zip),
+        [street, city, state_zip] => match state_zip.split_once(' ') {
+            Some((state, zip)) => (street, city, state, zip.trim()),
+            None => (street, city, state_zip, ""),
+        },
+        _ => (address, "", "", ""),
+    }
+}
+
+// Response rows: id, input, Match|No_Match|Tie, Exact|Non_Exact, matched address,
+// "lon,lat", TIGER line id, side. Rows come back in no particular order.
+fn parse_batch_response(body: &str, count: usize) -> Vec<BatchMatch> {
+    let mut matches = vec![BatchMatch { status: MatchStatus::NoMatch, geocoded: None }; count];
+    let mut rdr = csv::ReaderBuilder::new().has_headers(false).flexible(true).from_reader(body.as_bytes());
+    for record in rdr.records().flatten() {
+        let Some(index) = record.get(0).and_then(|id| id.trim().parse::<usize>().ok()).filter(|i| *i < count) else {
+            continue;
+        };
+        let status = match (record.get(2), record.get(3)) {
+            (Some("Match"), Some("Exact")) => MatchStatus::Exact,
+            (Some("Match"), _) => MatchStatus::NonExact,
+            (Some("Tie"), _) => MatchStatus::Tie,
+            _ => MatchStatus::NoMatch,
+        };
+        let coords = record.get(5).and_then(|c| c.split_once(',')).and_then(|(lon, lat)| {
+            Some((lat.trim().parse::<f64>().ok()?, lon.trim().parse::<f64>().ok()?))
+        });
+        let geocoded = match (status, record.get(4), coords) {
+            (MatchStatus::Exact | MatchStatus::NonExact, Some(matched), Some((lat, lon))) => {
+                Some(GeocodedAddress { matched_address: matched.to_string(), lat, lon, provider: GeocoderKind::Census })
+            }
+            _ => None,
+        };
+        // A match without usable coordinates is no use to us
+        let status = if geocoded.is_none() && matches!(status, MatchStatus::Exact | MatchStatus::NonExact) { MatchStatus::NoMatch } else { status };
+        matches[index] = BatchMatch { status, geocoded };
+    }
+    matches
+}
+
+/// What is at a coordinate, combining the FCC census block API (US counties,
+/// states and FIPS codes) with Nominatim reverse geocoding (city, ZIP, worldwide).
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PlaceDescription {
+    pub lat: f64,
+    pub lon: f64,
+    /// Nominatim's one-line description of the place
+    pub display_name: Option<String>,
+    pub city: Option<String>,
+    pub county: Option<String>,
+    pub state: Option<String>,
+    /// Two-letter postal code, e.g. "MT"
+    pub state_code: Option<String>,
+    pub zip: Option<String>,
+    /// ISO 3166-1 alpha-2, lowercase as Nominatim returns it
+    pub country_code: Option<String>,
+    pub state_fips: Option<String>,
+    pub county_fips: Option<String>,
+    /// 15-digit census block FIPS code
+    pub block_fips: Option<String>,
+}
+
+impl PlaceDescription {
+    /// "Bozeman, MT, 59715" (the Census layout), falling back to Nominatim's description or the coordinates.
+    pub fn label(&self) -> String {
+        let state = self.state_code.as_ref().or(self.state.as_ref());
+        match (&self.city, state) {
+            (Some(city), Some(state)) => {
+                let zip = self.zip.as_ref().map(|z| format!(", {}", z)).unwrap_or_default();
+                format!("{}, {}{}", city, state, zip)
+            }
+            _ => self.display_name.clone().unwrap_or_else(|| format!("{:.5}, {:.5}", self.lat, self.lon)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(rename_all = "PascalCase")]
+struct FccBlockResponse {
+    block: Option<FccArea>,
+    county: Option<FccArea>,
+    state: Option<FccArea>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct FccArea {
+    #[serde(rename = "FIPS")]
+    fips: Option<String>,
+    name: Option<String>,
+    code: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct NominatimReverse {
+    display_name: Option<String>,
+    #[serde(default)]
+    address: NominatimAddress,
+}
+
+#[derive(Deserialize, Debug, Default)]
+struct NominatimAddress {
+    city: Option<String>,
+    town: Option<String>,
+    village: Option<String>,
+    hamlet: Option<String>,
+    county: Option<String>,
+    state: Option<String>,
+    postcode: Option<String>,
+    country_code: Option<String>,
+}
+
+async fn fetch_fcc_block(lat: f64, lon: f64) -> Result<FccBlockResponse, AppError> {
+    let url = format!("{}/api/census/block/find?latitude={}&longitude={}&format=json", config::endpoints().fcc, lat, lon);
+    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(crate::api_error("FCC census block", &url, response).await);
+    }
+    response.json().await.map_err(|e| AppError::Api(format!("Failed to parse JSON response from the FCC census block API: {}", e)))
+}
+
+async fn fetch_nominatim_reverse(lat: f64, lon: f64) -> Result<NominatimReverse, AppError> {
+    let url = format!("{}/reverse?format=jsonv2&lat={}&lon={}", config::endpoints().nominatim, lat, lon);
+    let response = HTTP_CLIENT.get(&url).header("User-Agent", APP_USER_AGENT).send().await.map_err(AppError::Network)?;
+    if !response.status().is_success() {
+        return Err(crate::api_error("Nominatim reverse", &url, response).await);
+    }
+    response.json().await.map_err(|e| AppError::Api(format!("Failed to parse JSON response from Nominatim: {}", e)))
+}
+
+/// Describes a coordinate. Both services are asked at once; the lookup fails
+/// only when neither answers.
+pub async fn reverse_geocode(lat: f64, lon: f64) -> Result<PlaceDescription, AppError> {
+    let (fcc, nominatim) = tokio::join!(fetch_fcc_block(lat, lon), fetch_nominatim_reverse(lat, lon));
+    if let (Err(e), Err(_)) = (&fcc, &nominatim) {
+        return Err(AppError::Api(format!("Could not describe {:.5}, {:.5}: {}", lat, lon, e)));
+    }
+    let fcc = fcc.unwrap_or_default();
+    let nominatim = nominatim.unwrap_or_default();
+    let (state, county, block) = (fcc.state.unwrap_or_default(), fcc.county.unwrap_or_default(), fcc.block.unwrap_or_default());
+    let address = nominatim.address;
+    Ok(PlaceDescription {
+        lat,
+        lon,
+        display_name: nominatim.display_name,
+        city: address.city.or(address.town).or(address.village).or(address.hamlet),
+        county: county.name.or(address.county),
+        state: state.name.or(address.state),
+        state_code: state.code,
+        zip: address.postcode,
+        country_code: address.country_code,
+        state_fips: state.fips,
+        county_fips: county.fips,
+        block_fips: block.fips,
+    })
+}
+
+/// Looks up "County-State" for a coordinate via the FCC census block API.
+pub async fn get_county_state_from_latlon(lat: f64, lon: f64) -> Option<String> {
+    let block = fetch_fcc_block(lat, lon).await.ok()?;
+    Some(format!("{}-{}", block.county?.name?, block.state?.name?))
+}
+
+/// Looks up "City-State" for a coordinate via Nominatim reverse geocoding.
+pub async fn get_city_state_from_latlon(lat: f64, lon: f64) -> Option<String> {
+    let address = fetch_nominatim_reverse(lat, lon).await.ok()?.address;
+    let city = address.city.or(address.town).or(address.village)?;
+    Some(format!("{}-{}", city, address.state?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_batch_addresses() {
+        assert_eq!(split_batch_address("233 E MAIN ST, BOZEMAN, MT, 59715"), ("233 E MAIN ST", "BOZEMAN", "MT", "59715"));
+        assert_eq!(split_batch_address("233 E Main St, Bozeman, MT 59715"), ("233 E Main St", "Bozeman", "MT", "59715"));
+        assert_eq!(split_batch_address("233 E Main St, Bozeman, MT"), ("233 E Main St", "Bozeman", "MT", ""));
+        assert_eq!(split_batch_address("233 E Main St Bozeman MT 59715"), ("233 E Main St Bozeman MT 59715", "", "", ""));
+        assert_eq!(split_batch_address("Suite 4, 233 E Main St, Bozeman, MT, 59715"), ("Suite 4, 233 E Main St, Bozeman, MT, 59715", "", "", ""));
+    }
+
+    #[test]
+    fn parses_batch_responses_in_any_order() {
+        let body = "\
+            \"2\",\"1 NOWHERE RD, X, MT, 00000\",\"No_Match\"\n\
+            \"0\",\"233 E Main St, Bozeman, MT, 59715\",\"Match\",\"Exact\",\"233 E MAIN ST, BOZEMAN, MT, 59715\",\"-111.0373,45.6793\",\"123\",\"L\"\n\
+            \"3\",\"MAIN ST, SPRINGFIELD\",\"Tie\"\n\
+            \"1\",\"100 Congress St, Portland, ME\",\"Match\",\"Non_Exact\",\"100 CONGRESS ST, PORTLAND, ME, 04101\",\"-70.2553,43.6591\",\"456\",\"R\"\n\
+            \"4\",\"5 ELM ST, X, MT\",\"Match\",\"Exact\",\"5 ELM ST, X, MT, 59000\",\"\",\"789\",\"L\"\n\
+            \"9\",\"out of range\",\"Match\",\"Exact\",\"IGNORED\",\"-1,1\"\n";
+        let matches = parse_batch_response(body, 6);
+        let statuses: Vec<MatchStatus> = matches.iter().map(|m| m.status).collect();
+        assert_eq!(
+            statuses,
+            [MatchStatus::Exact, MatchStatus::NonExact, MatchStatus::NoMatch, MatchStatus::Tie, MatchStatus::NoMatch, MatchStatus::NoMatch]
+        );
+        let exact = matches[0].geocoded.as_ref().unwrap();
+        assert_eq!(exact.matched_address, "233 E MAIN ST, BOZEMAN, MT, 59715");
+        assert_eq!((exact.lat, exact.lon), (45.6793, -111.0373));
+        assert_eq!(exact.provider, GeocoderKind::Census);
+        assert_eq!(matches[1].geocoded.as_ref().unwrap().lat, 43.6591);
+        // Ties, matches without coordinates and missing rows carry no position
+        assert!(matches[2..].iter().all(|m| m.geocoded.is_none()));
+    }
+
+    #[test]
+    fn parses_coordinates_and_google_maps_links() {
+        assert_eq!(parse_coordinates(" 45.68, -111.04 "), Some((45.68, -111.04)));
+        assert_eq!(parse_coordinates("https://www.google.com/maps?q=45.68,-111.04"), Some((45.68, -111.04)));
+        assert_eq!(parse_coordinates("https://maps.google.com/?hl=en&q=45.68%2C-111.04&z=12"), Some((45.68, -111.04)));
+        assert_eq!(
+            parse_coordinates("https://www.google.com/maps/search/?api=1&query=43.6591,-70.2553"),
+            Some((43.6591, -70.2553))
+        );
+        assert_eq!(parse_coordinates("https://www.google.com/maps/@45.6793,-111.0373,15z"), Some((45.6793, -111.0373)));
+        assert_eq!(
+            parse_coordinates("https://www.google.com/maps/place/Bozeman/@45.67,-111.1,12z/data=!3m1!4b1!3d45.6769979!4d-111.0429339"),
+            Some((45.6769979, -111.0429339))
+        );
+    }
+
+    #[test]
+    fn matches_whole_query_parameters() {
+        // "hq=" and "sq=" end in "q=" but are not the query
+        assert_eq!(parse_coordinates("https://maps.google.com/maps?hq=1,2&q=45.68,-111.04"), Some((45.68, -111.04)));
+        assert_eq!(parse_coordinates("https://maps.google.com/maps?sq=10,20"), None);
+        assert_eq!(parse_coordinates("https://www.google.com/maps?layer=c&ll=45.68,-111.04"), Some((45.68, -111.04)));
+    }
+
+    #[test]
+    fn rejects_out_of_range_and_unrelated_text() {
+        assert_eq!(parse_coordinates("91,0"), None);
+        assert_eq!(parse_coordinates("45,181"), None);
+        assert_eq!(parse_coordinates("https://www.google.com/maps?q=95.0,-111.04"), None);
+        assert_eq!(parse_coordinates("https://www.google.com/maps/@45.68,-200,15z"), None);
+        assert_eq!(parse_coordinates("https://example.com/?q=45.68,-111.04"), None);
+        assert_eq!(parse_coordinates("233 E Main St, Bozeman"), None);
+    }
+}
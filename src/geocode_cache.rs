@@ -0,0 +1,226 @@
+// Persistent cache of geocoding results, keyed by the normalized query
+//
+// Stored as `geocode_cache.json` in the data directory. `geocode_address` answers
+// from a fresh entry without a request, and falls back to an expired entry when the
+// geocoder cannot be reached, so saved and repeated lookups keep working offline.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::io_error_with_path;
+use crate::geocode::{GeocodedAddress, GeocoderKind};
+use crate::{config, AppError};
+
+pub const GEOCODE_CACHE_FILE: &str = "geocode_cache.json";
+
+// Bumped if the file layout changes incompatibly; older files are discarded
+const CACHE_VERSION: u32 = 1;
+
+/// A cached geocoder match.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedGeocode {
+    pub matched_address: String,
+    pub lat: f64,
+    pub lon: f64,
+    /// Entries from before providers were recorded came from Census
+    #[serde(default = "census")]
+    pub provider: GeocoderKind,
+    pub cached_at: DateTime<Utc>,
+}
+
+fn census() -> GeocoderKind {
+    GeocoderKind::Census
+}
+
+impl CachedGeocode {
+    /// Whether the entry is younger than the configured TTL.
+    pub fn is_fresh(&self) -> bool {
+        Utc::now() - self.cached_at < ttl()
+    }
+
+    pub fn to_geocoded(&self) -> GeocodedAddress {
+        GeocodedAddress { matched_address: self.matched_address.clone(), lat: self.lat, lon: self.lon, provider: self.provider }
+    }
+}
+
+/// The cache file: normalized query → match.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeocodeCache {
+    version: u32,
+    entries: BTreeMap<String, CachedGeocode>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl GeocodeCache {
+    pub fn default_path() -> PathBuf {
+        config::data_dir().join(GEOCODE_CACHE_FILE)
+    }
+
+    /// Reads the cache; a missing file, or one from another cache version, yields an empty cache.
+    pub fn load(path: &Path) -> Result<GeocodeCache, AppError> {
+        let empty = GeocodeCache { version: CACHE_VERSION, entries: BTreeMap::new(), path: path.to_path_buf() };
+        if !path.exists() {
+            return Ok(empty);
+        }
+        let text = fs::read_to_string(path).map_err(|e| io_error_with_path(e, path))?;
+        let mut cache: GeocodeCache = serde_json::from_str(&text)
+            .map_err(|e| AppError::Config(format!("{}: {}", path.display(), e)))?;
+        if cache.version != CACHE_VERSION {
+            return Ok(empty);
+        }
+        cache.path = path.to_path_buf();
+        Ok(cache)
+    }
+
+    /// Writes the cache, replacing the previous file only once the new one is complete.
+    pub fn save(&self) -> Result<(), AppError> {
+        let json = serde_json::to_string_pretty(self)?;
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, json).map_err(|e| io_error_with_path(e, &tmp_path))?;
+        fs::rename(&tmp_path, &self.path).map_err(|e| io_error_with_path(e, &self.path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Entries by normalized query, in alphabetical order.
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &CachedGeocode)> {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The entry for `query`, fresh or not.
+    pub fn get(&self, query: &str) -> Option<&CachedGeocode> {
+        self.entries.get(&normalize_query(query))
+    }
+
+    pub fn insert(&mut self, query: &str, geocoded: &GeocodedAddress) {
+        self.entries.insert(
+            normalize_query(query),
+            CachedGeocode {
+                matched_address: geocoded.matched_address.clone(),
+                lat: geocoded.lat,
+                lon: geocoded.lon,
+                provider: geocoded.provider,
+                cached_at: Utc::now(),
+            },
+        );
+    }
+
+    /// Removes every entry, or only the expired ones; returns how many were removed.
+    pub fn clear(&mut self, expired_only: bool) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|_, entry| expired_only && entry.is_fresh());
+        before - self.entries.len()
+    }
+}
+
+/// Cache key for a query: uppercase, single spaces, and ", " between address parts,
+/// so "233 e main st,Bozeman" and "233 E Main St, Bozeman" share an entry.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split(',')
+        .map(|part| part.split_whitespace().collect::<Vec<_>>().join(" ").to_uppercase())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// How long entries are trusted without asking the geocoder again.
+pub fn ttl() -> Duration {
+    Duration::days(config::get().geocoding.cache_ttl_days as i64)
+}
+
+static CACHE: OnceLock<Mutex<GeocodeCache>> = OnceLock::new();
+
+// The process-wide cache, loaded on first use; an unreadable file starts empty
+fn shared() -> &'static Mutex<GeocodeCache> {
+    CACHE.get_or_init(|| {
+        let path = GeocodeCache::default_path();
+        Mutex::new(GeocodeCache::load(&path).unwrap_or_else(|_| GeocodeCache {
+            version: CACHE_VERSION,
+            entries: BTreeMap::new(),
+            path,
+        }))
+    })
+}
+
+/// The cached match for `query`, fresh or expired.
+pub fn lookup(query: &str) -> Option<CachedGeocode> {
+    shared().lock().ok()?.get(query).cloned()
+}
+
+/// Records a match and writes the cache file. A cache that cannot be written
+/// only costs a repeat request later, so write errors are ignored.
+pub fn store(query: &str, geocoded: &GeocodedAddress) {
+    store_all([(query, geocoded)]);
+}
+
+/// Records several matches with a single write of the cache file.
+pub fn store_all<'a>(matches: impl IntoIterator<Item = (&'a str, &'a GeocodedAddress)>) {
+    if let Ok(mut cache) = shared().lock() {
+        for (query, geocoded) in matches {
+            cache.insert(query, geocoded);
+        }
+        let _ = cache.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geocoded(address: &str) -> GeocodedAddress {
+        GeocodedAddress { matched_address: address.to_string(), lat: 45.6793, lon: -111.0373, provider: GeocoderKind::Census }
+    }
+
+    fn empty_cache() -> GeocodeCache {
+        GeocodeCache { version: CACHE_VERSION, entries: BTreeMap::new(), path: PathBuf::from(GEOCODE_CACHE_FILE) }
+    }
+
+    #[test]
+    fn normalizes_case_spacing_and_separators() {
+        assert_eq!(normalize_query("233 e main st,Bozeman ,  mt   59715"), "233 E MAIN ST, BOZEMAN, MT 59715");
+        assert_eq!(normalize_query("  233 E Main St, , Bozeman,"), "233 E MAIN ST, BOZEMAN");
+        assert_eq!(normalize_query(" \t "), "");
+
+        let mut cache = empty_cache();
+        cache.insert("233 e main st,Bozeman", &geocoded("233 E MAIN ST, BOZEMAN, MT, 59715"));
+        assert_eq!(cache.get("233 E Main St, Bozeman").map(|e| e.matched_address.as_str()), Some("233 E MAIN ST, BOZEMAN, MT, 59715"));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn clears_only_expired_entries_when_asked() {
+        let mut cache = empty_cache();
+        cache.insert("fresh", &geocoded("FRESH"));
+        cache.insert("old", &geocoded("OLD"));
+        cache.insert("older", &geocoded("OLDER"));
+        let long_ago = Utc::now() - Duration::days(365 * 100);
+        for key in ["OLD", "OLDER"] {
+            cache.entries.get_mut(key).unwrap().cached_at = long_ago;
+        }
+        assert!(cache.get("fresh").unwrap().is_fresh());
+        assert!(!cache.get("old").unwrap().is_fresh());
+
+        assert_eq!(cache.clear(true), 2);
+        assert_eq!(cache.entries().map(|(key, _)| key.as_str()).collect::<Vec<_>>(), ["FRESH"]);
+        assert_eq!(cache.clear(true), 0);
+        assert_eq!(cache.clear(false), 1);
+        assert!(cache.is_empty());
+    }
+}
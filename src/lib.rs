@@ -0,0 +1,83 @@
+//! Reather library: geocoding, NWS weather and alerts, derived comfort metrics, METAR/TAF decoding and flight
+//! categories, USGS earthquakes, NOAA tides and the OurAirports database behind a typed async API.
+//!
+//! The functions here fetch and return domain structs; printing is left to
+//! the caller (the `reather` binary is one such caller).
+
+#[macro_use]
+extern crate lazy_static;
+
+pub mod addresses;
+pub mod airports;
+pub mod alerts;
+pub mod altitude;
+pub mod aviation_weather;
+pub mod config;
+pub mod dataset_cache;
+pub mod earthquakes;
+pub mod error;
+pub mod flight_rules;
+pub mod geocode;
+pub mod geocode_cache;
+pub mod metar;
+pub mod meteo;
+pub mod nws;
+pub mod ourairports;
+pub mod report;
+pub mod runways;
+pub mod tides;
+pub mod units;
+
+pub use addresses::{AddressBook, StoredAddress};
+pub use alerts::Alert;
+pub use earthquakes::Earthquake;
+pub use error::AppError;
+pub use geocode::GeocodedAddress;
+pub use nws::{ForecastPeriod, NearestStation, Observation};
+pub use tides::{TidePrediction, TideStation};
+pub use units::UnitSystem;
+
+pub const APP_USER_AGENT: &str = "reather-app/0.1 (rust-cli-weather-app; https://github.com/yourusername/reather)"; // Replace with actual repo URL if available
+
+lazy_static! {
+    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder()
+        .user_agent(APP_USER_AGENT)
+        .build()
+        .expect("Failed to build HTTP client");
+}
+
+/// Great-circle distance in kilometres between two lat/lon points.
+pub fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let r = 6371.0; // km
+    let dlat = (lat2 - lat1).to_radians();
+    let dlon = (lon2 - lon1).to_radians(); // Fixed: match Python logic
+    let a = (dlat / 2.0).sin().powi(2)
+        + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlon / 2.0).sin().powi(2);
+    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
+    r * c
+}
+
+/// Initial great-circle bearing in degrees true (0-360) from the first point to the second.
+pub fn initial_bearing(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
+    let (phi1, phi2) = (lat1.to_radians(), lat2.to_radians());
+    let dlon = (lon2 - lon1).to_radians();
+    let y = dlon.sin() * phi2.cos();
+    let x = phi1.cos() * phi2.sin() - phi1.sin() * phi2.cos() * dlon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+/// 16-point compass abbreviation ("N", "NNE", ...) for a bearing in degrees.
+pub fn compass_point(degrees: f64) -> &'static str {
+    const POINTS: [&str; 16] = ["N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW", "NNW"];
+    POINTS[((degrees.rem_euclid(360.0) / 22.5).round() as usize) % 16]
+}
+
+// Reads the body of a failed response into an `AppError::Api` message
+async fn api_error(service: &str, url: &str, response: reqwest::Response) -> AppError {
+    let status = response.status();
+    let text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
+    AppError::Api(format!(
+        "{} request failed (Status: {}). URL: {}. Details: {}",
+        service, status, url, text
+    ))
+}
@@ -1,18 +1,47 @@
-use serde::Deserialize;
-use std::fs::{File, OpenOptions};
-use std::io::{self, BufRead, BufReader, Write};
-use std::path::{Path, PathBuf};
-use std::fmt;
+use std::fs;
+use std::io::{self, Write};
 use std::env;
+use std::path::{Path, PathBuf};
 
-mod airports;
-
-#[macro_use]
-extern crate lazy_static;
-
-const DATA_DIR: &str = "data";
-const ADDRESS_FILE: &str = "addresses.txt";
-const APP_USER_AGENT: &str = "reather-app/0.1 (rust-cli-weather-app; https://github.com/yourusername/reather)"; // Replace with actual repo URL if available
+use reather::addresses::{self, AddressBook, ImportOutcome, ImportStatus, StoredAddress};
+use reather::airports::{self, AirportFilter};
+use reather::alerts::{self, Alert};
+use reather::altitude::{self, FieldAltitudes};
+use reather::aviation_weather;
+use reather::config::{self, Config};
+use reather::dataset_cache;
+use reather::earthquakes;
+use reather::error::io_error_with_path;
+use reather::flight_rules::FlightCategory;
+use reather::geocode::{self, geocode_address, GeocodedAddress, GeocoderKind, MatchStatus};
+use reather::geocode_cache::GeocodeCache;
+use reather::metar::{Metar, Taf};
+use reather::meteo;
+use reather::nws::{self, find_nearest_station, NearestStation};
+use reather::ourairports;
+use reather::report::{self, Report};
+use reather::runways::{self, RunwayWindAnalysis};
+use reather::tides;
+use reather::units::{METRES_TO_FEET, MPS_TO_KNOTS, PA_TO_INHG};
+use reather::{compass_point, AppError, UnitSystem};
+
+mod cli;
+
+use cli::ForecastView;
+
+// Defaults for the nearby-airports listing
+const NEARBY_AIRPORT_RADIUS_KM: f64 = 50.0;
+const NEARBY_AIRPORT_LIMIT: usize = 10;
+
+// Navaids listed in airport details
+const NEARBY_NAVAID_RADIUS_KM: f64 = 50.0;
+const NEARBY_NAVAID_LIMIT: usize = 5;
+
+// Airports the flight category filter will look up METARs for (three requests)
+const FLIGHT_CATEGORY_MAX_AIRPORTS: usize = 300;
+
+// Frequency types shown in airport details: common traffic advisory, tower and ATIS
+const KEY_FREQUENCY_TYPES: [&str; 3] = ["CTAF", "TWR", "ATIS"];
 
 // Hardcoded seed addresses from data/seed.txt
 const SEED_ADDRESSES: [&str; 7] = [
@@ -25,458 +54,708 @@ const SEED_ADDRESSES: [&str; 7] = [
     "1600 PENNSYLVANIA AVE NW, WASHINGTON, DC, 20500",
 ];
 
-lazy_static! {
-    static ref HTTP_CLIENT: reqwest::Client = reqwest::Client::builder()
-        .user_agent(APP_USER_AGENT)
-        .build()
-        .expect("Failed to build HTTP client");
-}
-
-// Custom Error Type
-#[derive(Debug)]
-enum AppError {
-    Io(io::Error, Option<String>),
-    Network(reqwest::Error),
-    Api(String), // For API-specific errors (e.g., bad status, missing data)
-    JsonParse(serde_json::Error),
-    UserInput(String),
-}
-
-impl fmt::Display for AppError {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            AppError::Io(err, path) => {
-                if let Some(p) = path {
-                    write!(f, "File I/O error for \'{}\': {}", p, err)
-                } else {
-                    write!(f, "File I/O error: {}", err)
-                }
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let cli::Invocation { format, units, no_cache, command } = match cli::parse_args(env::args().skip(1)) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Some(cli::Command::Help) = command {
+        println!("{}", cli::USAGE);
+        return Ok(());
+    }
+    match Config::load() {
+        Ok(mut loaded) => {
+            if let Some(units) = units {
+                loaded.display.units = units;
+            }
+            if no_cache {
+                loaded.geocoding.cache = false;
             }
-            AppError::Network(err) => write!(f, "Network error: {}. Please check your internet connection.", err),
-            AppError::Api(msg) => write!(f, "API error: {}", msg),
-            AppError::JsonParse(err) => write!(f, "JSON parsing error: {}", err),
-            AppError::UserInput(msg) => write!(f, "Invalid input: {}", msg),
+            config::init(loaded)?
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
         }
     }
-}
-
-impl std::error::Error for AppError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
-        match self {
-            AppError::Io(err, _) => Some(err),
-            AppError::Network(err) => Some(err),
-            AppError::JsonParse(err) => Some(err),
-            _ => None,
+    if let Some(cli::Command::AirportsRefresh) = command {
+        // Blocking download, so it runs before the async runtime starts
+        match airports::refresh_airports() {
+            Ok((count, status)) => {
+                report_dataset_status(&status);
+                println!("Airport database refreshed: {} airports", count);
+                let companions = ourairports::refresh_datasets();
+                for result in std::iter::once(runways::refresh_runways()).chain(companions) {
+                    match result {
+                        Ok(status) => report_dataset_status(&status),
+                        Err(e) => eprintln!("Warning: {}", e),
+                    }
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            }
         }
     }
-}
-
-// Convert io::Error to AppError
-impl From<io::Error> for AppError {
-    fn from(err: io::Error) -> AppError {
-        AppError::Io(err, None)
+    if command.as_ref().is_none_or(|c| c.needs_airports()) {
+        let status = airports::init_airports()?;
+        report_dataset_status(&status);
+        // Runways and the companion datasets only add to airport details, so the app
+        // carries on without them
+        let companions = ourairports::init_datasets();
+        let names = std::iter::once("runways.csv").chain(ourairports::DATASETS);
+        for (name, result) in names.zip(std::iter::once(runways::init_runways()).chain(companions)) {
+            match result {
+                Ok(status) => report_dataset_status(&status),
+                Err(e) => eprintln!("Warning: {} unavailable: {}", name, e),
+            }
+        }
     }
-}
-// Helper to create AppError::Io with a path
-fn io_error_with_path(err: io::Error, path: &Path) -> AppError {
-    AppError::Io(err, Some(path.display().to_string()))
-}
-
-
-// Convert reqwest::Error to AppError
-impl From<reqwest::Error> for AppError {
-    fn from(err: reqwest::Error) -> AppError {
-        AppError::Network(err)
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    if let Some(command) = command {
+        // Scripts only care about the message and the exit status
+        let result = match format {
+            cli::OutputFormat::Text => runtime.block_on(run_command(command)),
+            cli::OutputFormat::Json => runtime.block_on(run_command_json(command)),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
     }
+    let airport_count = airports::get_airport_count();
+    println!("Reather - a Rust-based Weather App");
+    println!("USA airport database loaded: {} airports", airport_count);
+    println!();
+    Ok(runtime.block_on(async_main())?)
 }
 
-// Convert serde_json::Error to AppError
-impl From<serde_json::Error> for AppError {
-    fn from(err: serde_json::Error) -> AppError {
-        AppError::JsonParse(err)
+// Tells the user when a downloaded dataset could not be refreshed
+fn report_dataset_status(status: &dataset_cache::DatasetStatus) {
+    match &status.source {
+        dataset_cache::DatasetSource::StaleCache { error } => {
+            eprintln!(
+                "Warning: could not refresh {} ({}). Using the cached copy from {}.",
+                status.name, error, status.fetched_at.format("%Y-%m-%d %H:%M UTC")
+            );
+        }
+        dataset_cache::DatasetSource::NotModified => {
+            println!("{} is up to date (checked {}).", status.name, status.fetched_at.format("%Y-%m-%d %H:%M UTC"));
+        }
+        dataset_cache::DatasetSource::Cache | dataset_cache::DatasetSource::Downloaded => {}
     }
 }
 
-// Structs for deserializing Census Geocoding API response
-#[derive(Deserialize, Debug)]
-struct GeocodeResponse {
-    result: GeocodeResult,
-}
-
-#[derive(Deserialize, Debug)]
-struct GeocodeResult {
-    #[serde(rename = "addressMatches")]
-    address_matches: Vec<AddressMatch>,
-}
-
-#[derive(Deserialize, Debug)]
-struct AddressMatch {
-    #[serde(rename = "matchedAddress")]
-    matched_address: String,
-    coordinates: Coordinates,
-}
-
-#[derive(Deserialize, Debug)]
-struct Coordinates {
-    x: f64, // longitude
-    y: f64, // latitude
-}
-
-// Structs for deserializing NOAA API /points response
-#[derive(Deserialize, Debug)]
-struct NWSPointResponse {
-    properties: NWSPointProperties,
-}
-
-#[derive(Deserialize, Debug)]
-struct NWSPointProperties {
-    #[serde(rename = "observationStations")]
-    observation_stations_url: String, // URL to fetch list of observation stations
-    #[serde(rename = "relativeLocation")]
-    relative_location: Option<NWSRelativeLocation>, // For station name fallback
-    forecast: String, // URL for the zone forecast
-}
-
-#[derive(Deserialize, Debug)]
-struct NWSRelativeLocation {
-    properties: NWSRelativeLocationProperties,
-}
-
-#[derive(Deserialize, Debug)]
-struct NWSRelativeLocationProperties {
-    city: String,
-    state: String,
-}
-
-
-// Structs for deserializing NOAA API /stations response (list of stations)
-#[derive(Deserialize, Debug)]
-struct NWSStationsResponse {
-    features: Vec<NWSStationFeature>,
+// A location given on the command line, resolved to coordinates
+struct ResolvedLocation {
+    label: String,
+    lat: f64,
+    lon: f64,
+    state: Option<String>,
+    /// Set when the location is a saved address
+    address_id: Option<u32>,
+    /// Observation station chosen for a saved address
+    nws_station_id: Option<String>,
 }
 
-#[derive(Deserialize, Debug)]
-struct NWSStationFeature {
-    properties: NWSStationProperties,
-    geometry: Option<NWSGeometry>, // Added to capture station coordinates
+impl ResolvedLocation {
+    fn to_report_location(&self) -> report::Location {
+        report::Location { label: self.label.clone(), lat: self.lat, lon: self.lon }
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct NWSStationProperties {
-    #[serde(rename = "stationIdentifier")]
-    station_identifier: String,
-    name: String,
+// Looks up a 3-4 character IATA or ICAO code in the airport database
+fn find_airport_by_code(code: &str) -> Option<&'static airports::Airport> {
+    let looks_like_code = (3..=4).contains(&code.len()) && code.chars().all(|c| c.is_ascii_alphanumeric());
+    if looks_like_code {
+        airports::get_airport_by_icao(code)
+    } else {
+        None
+    }
 }
 
-// Added struct to represent GeoJSON geometry for station coordinates
-#[derive(Deserialize, Debug)]
-struct NWSGeometry {
-    coordinates: Option<Vec<f64>>, // [longitude, latitude]
+// Resolves an airport code (IATA or ICAO) or, failing that, geocodes a street address
+async fn resolve_location(query: &str) -> Result<ResolvedLocation, AppError> {
+    let query = query.trim();
+    let book = AddressBook::load(&AddressBook::default_path())?;
+    if let Some(entry) = book.find_by_nickname(query) {
+        return Ok(ResolvedLocation {
+            label: entry.label(),
+            lat: entry.lat,
+            lon: entry.lon,
+            state: extract_state_from_address(&entry.address),
+            address_id: Some(entry.id),
+            nws_station_id: entry.nws_station_id.clone(),
+        });
+    }
+    if let Some(airport) = find_airport_by_code(query) {
+        if let (Ok(lat), Ok(lon)) = (airport.latitude_deg.parse::<f64>(), airport.longitude_deg.parse::<f64>()) {
+            return Ok(ResolvedLocation {
+                label: format!("{} ({})", airport.name, airport.ident),
+                lat,
+                lon,
+                state: extract_state_from_airport(airport),
+                address_id: None,
+                nws_station_id: None,
+            });
+        }
+    }
+    match geocode_address(query).await? {
+        Some(GeocodedAddress { matched_address, lat, lon, .. }) => Ok(ResolvedLocation {
+            state: extract_state_from_address(&matched_address),
+            label: matched_address,
+            lat,
+            lon,
+            address_id: None,
+            nws_station_id: None,
+        }),
+        None => Err(AppError::Api(format!("Could not find a match for the address or airport: '{}'", query))),
+    }
 }
 
-// Structs for deserializing NOAA API /stations/{stationId}/observations/latest response
-#[derive(Deserialize, Debug)]
-struct WeatherObservationResponse {
-    properties: Option<WeatherProperties>, // Make properties itself optional for robustness
+// The closest stations to a location and its preferred station. `use_station`
+// becomes the preferred station of a saved address.
+async fn location_stations(location: &ResolvedLocation, use_station: Option<&str>) -> Result<(Vec<nws::StationStatus>, Option<String>), AppError> {
+    let point = nws::fetch_point_stations(location.lat, location.lon).await?
+        .ok_or_else(|| AppError::Api(format!("No weather observation stations found near '{}'", location.label)))?;
+    let statuses = nws::station_statuses(&point, config::get().stations.candidates).await;
+    let Some(station_id) = use_station else {
+        return Ok((statuses, location.nws_station_id.clone()));
+    };
+    let address_id = location.address_id.ok_or_else(|| {
+        AppError::UserInput("--use needs a saved address nickname, e.g. 'reather stations Home --use KBZN'.".to_string())
+    })?;
+    // Saved with the spelling NWS uses, whatever case was typed
+    let station_id = point.stations.iter()
+        .find(|s| s.station_id.eq_ignore_ascii_case(station_id))
+        .map(|s| s.station_id.clone())
+        .ok_or_else(|| AppError::UserInput(format!("NWS does not list station '{}' near '{}'.", station_id, location.label)))?;
+    let mut book = AddressBook::load(&AddressBook::default_path())?;
+    let entry = book.get_mut(address_id).ok_or_else(|| AppError::UserInput(format!("No address with id {}.", address_id)))?;
+    entry.nws_station_id = Some(station_id.clone());
+    entry.touch();
+    book.save()?;
+    Ok((statuses, Some(station_id)))
 }
 
-#[derive(Deserialize, Debug)]
-struct WeatherProperties {
-    temperature: Option<WeatherValueUnit>,
-    #[serde(rename = "heatIndex")]
-    heat_index: Option<WeatherValueUnit>,
-    #[serde(rename = "textDescription")]
-    text_description: Option<String>,
-    #[serde(rename = "windDirection")]
-    wind_direction: Option<WeatherValueUnit>,
-    wind_speed: Option<WeatherValueUnit>,
-    #[serde(rename = "windGust")]
-    wind_gust: Option<WeatherValueUnit>,
-    #[serde(rename = "relativeHumidity")]
-    relative_humidity: Option<WeatherValueUnit>,
-    #[serde(rename = "cloudLayers")]
-    cloud_layers: Option<Vec<CloudLayer>>, // Make cloud_layers optional
-    visibility: Option<WeatherValueUnit>,
-    #[serde(rename = "barometricPressure")]
-    barometric_pressure: Option<WeatherValueUnit>,
-    // Not strictly needed for display, but good to have if we want to show observation time
-    // timestamp: Option<String>, 
+// Runs a single non-interactive subcommand and returns
+async fn run_command(command: cli::Command) -> Result<(), AppError> {
+    match command {
+        cli::Command::Weather { location } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            let choice = nws::select_station(location.lat, location.lon, location.nws_station_id.as_deref()).await?
+                .ok_or_else(|| AppError::Api(format!("No weather observation stations found near '{}'", location.label)))?;
+            display_skipped_stations(&choice);
+            fetch_and_display_weather(&choice.station.station_id, &choice.station.station_name, choice.station.time_zone.as_deref()).await
+        }
+        cli::Command::Stations { location, use_station } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            let (statuses, preferred) = location_stations(&location, use_station.as_deref()).await?;
+            println!("\nClosest observation stations:");
+            display_station_statuses(&statuses, preferred.as_deref());
+            if let Some(id) = use_station {
+                println!("\nSaved {} as the observation station for {}.", id, location.label);
+            }
+            Ok(())
+        }
+        cli::Command::History { location, hours } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            let choice = nws::select_station(location.lat, location.lon, location.nws_station_id.as_deref()).await?
+                .ok_or_else(|| AppError::Api(format!("No weather observation stations found near '{}'", location.label)))?;
+            display_skipped_stations(&choice);
+            let station = choice.station;
+            fetch_and_display_history(&station.station_id, &station.station_name, station.time_zone.as_deref(), hours).await
+        }
+        cli::Command::Forecast { location, periods, view } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            let station = find_nearest_station(location.lat, location.lon).await?
+                .ok_or_else(|| AppError::Api(format!("No NWS forecast available for '{}'", location.label)))?;
+            fetch_and_display_local_forecast(&station.forecast_url, &station.station_name, periods, view).await
+        }
+        cli::Command::Hourly { location, hours } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            let station = find_nearest_station(location.lat, location.lon).await?
+                .ok_or_else(|| AppError::Api(format!("No NWS forecast available for '{}'", location.label)))?;
+            let url = station.forecast_hourly_url
+                .ok_or_else(|| AppError::Api(format!("No NWS hourly forecast available for '{}'", location.label)))?;
+            fetch_and_display_hourly_forecast(&url, &station.station_name, hours).await
+        }
+        cli::Command::Alerts { location } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            fetch_and_display_alerts(location.lat, location.lon).await
+        }
+        cli::Command::Tides { location } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            find_and_display_tide_station(location.lat, location.lon, location.state.as_deref()).await.map(|_| ())
+        }
+        cli::Command::Lookup { coordinates: (lat, lon), save, nickname } => {
+            let place = geocode::reverse_geocode(lat, lon).await?;
+            display_place(&place);
+            if save {
+                let mut book = AddressBook::load(&AddressBook::default_path())?;
+                let (id, added) = save_place(&mut book, &place, nickname.as_deref())?;
+                if !added {
+                    println!("\nAlready saved as address {}.", id);
+                }
+                display_address_book_entry(book.get(id).expect("saved entry exists"));
+            }
+            Ok(())
+        }
+        cli::Command::Quakes { min_mag, hours } => {
+            fetch_and_display_earthquakes_filtered(min_mag, hours).await
+        }
+        cli::Command::AirportSearch { pattern, filter, category } => {
+            let mut results = filtered_airport_search(&pattern, &filter);
+            if let Some(category) = category {
+                results = retain_flight_category(results, category).await?;
+            }
+            if results.is_empty() {
+                match category {
+                    Some(category) => println!("No {} airports found matching '{}'.", category, pattern),
+                    None => println!("No airports found matching '{}'.", pattern),
+                }
+            }
+            for airport in results {
+                println!("{} ({}) - {}, {}", airport.name, airport.ident, airport.municipality, airport.iso_region);
+            }
+            Ok(())
+        }
+        cli::Command::AirportsNear { location, radius_km, limit, filter } => {
+            let location = resolve_location(&location).await?;
+            println!("Location: {} (Lat: {}, Lon: {})", location.label, location.lat, location.lon);
+            display_nearby_airports(location.lat, location.lon, radius_km, limit, &filter);
+            Ok(())
+        }
+        cli::Command::AirportDetails { code } => {
+            let airport = find_airport_by_code(code.trim())
+                .ok_or_else(|| AppError::UserInput(format!("No airport found with code '{}'.", code)))?;
+            println!("{} ({}) - {}, {}", airport.name, airport.ident, airport.municipality, airport.iso_region);
+            show_airport_details(airport).await
+        }
+        cli::Command::Addresses(command) => {
+            let mut book = AddressBook::load(&AddressBook::default_path())?;
+            match run_address_command(&mut book, command).await? {
+                AddressCommandResult::List(tag) => display_address_book(&book, tag.as_deref()),
+                AddressCommandResult::Changed(entry) => display_address_book_entry(&entry),
+                AddressCommandResult::Deleted(entry) => println!("Deleted address {}: {}", entry.id, entry.label()),
+                AddressCommandResult::Imported(path, outcomes) => display_import_outcomes(&path, &outcomes),
+                AddressCommandResult::Normalized(results) => display_normalized_addresses(&results),
+                AddressCommandResult::Exported(Some(export)) => {
+                    println!("Exported {} addresses to {} ({}).", export.count, export.path, export.format)
+                }
+                AddressCommandResult::Exported(None) => {}
+            }
+            Ok(())
+        }
+        cli::Command::Cache(cli::CacheCommand::List) => {
+            let cache = GeocodeCache::load(&GeocodeCache::default_path())?;
+            display_geocode_cache(&cache);
+            Ok(())
+        }
+        cli::Command::Cache(cli::CacheCommand::Clear { expired_only }) => {
+            let (removed, remaining) = clear_geocode_cache(expired_only)?;
+            println!("Removed {} cached geocoder matches; {} remain.", removed, remaining);
+            Ok(())
+        }
+        // Handled in main() before the runtime starts
+        cli::Command::Help | cli::Command::AirportsRefresh => Ok(()),
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct WeatherValueUnit {
-    value: Option<f64>, // Value can be null for some fields like heatIndex or windGust
+fn display_geocode_cache(cache: &GeocodeCache) {
+    if cache.is_empty() {
+        println!("The geocoding cache at '{}' is empty.", cache.path().display());
+        return;
+    }
+    println!(
+        "Geocoding cache '{}': {} entries, kept for {} days",
+        cache.path().display(), cache.len(), config::get().geocoding.cache_ttl_days
+    );
+    for (query, entry) in cache.entries() {
+        let age = (chrono::Utc::now() - entry.cached_at).num_days();
+        let expired = if entry.is_fresh() { "" } else { " (expired)" };
+        println!("\n{}", query);
+        println!("    -> {} (Lat: {}, Lon: {}) via {}", entry.matched_address, entry.lat, entry.lon, entry.provider);
+        println!("    cached {} days ago{}", age, expired);
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct CloudLayer {
-    base: Option<CloudBase>,
-    amount: Option<String>, // e.g., SKC, FEW, SCT, BKN, OVC
+// Returns (removed, remaining)
+fn clear_geocode_cache(expired_only: bool) -> Result<(usize, usize), AppError> {
+    let mut cache = GeocodeCache::load(&GeocodeCache::default_path())?;
+    let removed = cache.clear(expired_only);
+    cache.save()?;
+    Ok((removed, cache.len()))
 }
 
-#[derive(Deserialize, Debug)]
-struct CloudBase {
-    value: Option<f64>, // meters
-     // unit_code: Option<String>, // Typically "wmoUnit:m"
+// What an `addresses` subcommand did, for rendering as text or JSON
+enum AddressCommandResult {
+    List(Option<String>),
+    Changed(StoredAddress),
+    Deleted(StoredAddress),
+    Imported(PathBuf, Vec<ImportOutcome>),
+    Normalized(Vec<report::NormalizedAddress>),
+    /// Nothing to report when the export went to stdout
+    Exported(Option<report::AddressExportReport>),
 }
 
-// Structs for deserializing NOAA API Forecast response
-#[derive(Deserialize, Debug)]
-struct ForecastResponse {
-    properties: ForecastProperties,
+async fn run_address_command(book: &mut AddressBook, command: cli::AddressCommand) -> Result<AddressCommandResult, AppError> {
+    let find_id = |book: &AddressBook, key: &str| {
+        book.find(key).map(|e| e.id).ok_or_else(|| AppError::UserInput(format!("No address with id or nickname '{}'.", key)))
+    };
+    let id = match command {
+        cli::AddressCommand::List { tag } => return Ok(AddressCommandResult::List(tag)),
+        cli::AddressCommand::Add { address, edits } => {
+            if let Some(nickname) = edits.nickname.as_deref().filter(|n| !n.is_empty()) {
+                book.check_nickname(nickname, None)?;
+            }
+            let id = geocode_and_store(book, &address).await?;
+            apply_address_edits(book, id, &edits)?;
+            id
+        }
+        cli::AddressCommand::Edit { key, edits } => {
+            let id = find_id(book, &key)?;
+            apply_address_edits(book, id, &edits)?;
+            id
+        }
+        cli::AddressCommand::Delete { key } => {
+            let id = find_id(book, &key)?;
+            let entry = book.remove(id).expect("id was just looked up");
+            book.save()?;
+            return Ok(AddressCommandResult::Deleted(entry));
+        }
+        cli::AddressCommand::Move { key, position } => {
+            let id = find_id(book, &key)?;
+            book.move_to(id, position.saturating_sub(1))?;
+            id
+        }
+        cli::AddressCommand::Import { path } => {
+            let (rows, mut outcomes) = addresses::read_import_csv(&path)?;
+            outcomes.extend(book.import_rows(rows).await?);
+            outcomes.sort_by_key(|o| o.line);
+            book.save()?;
+            return Ok(AddressCommandResult::Imported(path, outcomes));
+        }
+        cli::AddressCommand::Normalize => {
+            let results = normalize_address_book(book).await?;
+            return Ok(AddressCommandResult::Normalized(results));
+        }
+        cli::AddressCommand::Export { path, format, tag } => {
+            let entries: Vec<&StoredAddress> = book.addresses().iter().filter(|a| tag.as_deref().is_none_or(|t| a.has_tag(t))).collect();
+            let output = addresses::export(&entries, format)?;
+            if path.as_os_str() == "-" {
+                print!("{}", output);
+                return Ok(AddressCommandResult::Exported(None));
+            }
+            fs::write(&path, output).map_err(|e| io_error_with_path(e, &path))?;
+            return Ok(AddressCommandResult::Exported(Some(report::AddressExportReport {
+                path: path.display().to_string(),
+                format,
+                count: entries.len(),
+            })));
+        }
+    };
+    book.save()?;
+    Ok(AddressCommandResult::Changed(book.get(id).cloned().expect("entry exists")))
 }
 
-#[derive(Deserialize, Debug)]
-struct ForecastProperties {
-    periods: Vec<ForecastPeriod>,
+// Runs a single subcommand and prints its result as a JSON report
+async fn run_command_json(command: cli::Command) -> Result<(), AppError> {
+    match command {
+        cli::Command::Weather { location } => {
+            let location = resolve_location(&location).await?;
+            let choice = nws::select_station(location.lat, location.lon, location.nws_station_id.as_deref()).await?
+                .ok_or_else(|| AppError::Api(format!("No weather observation stations found near '{}'", location.label)))?;
+            let observation = match choice.observation {
+                Some(observation) => observation,
+                None => nws::fetch_latest_observation(&choice.station.station_id).await?,
+            };
+            print_json(&Report::new("conditions", report::ConditionsReport {
+                location: location.to_report_location(),
+                station: choice.station,
+                stale: observation.is_stale(),
+                comfort: observation.comfort(),
+                flight_category: observation.flight_category(),
+                observation,
+                skipped_stations: choice.skipped,
+            }))
+        }
+        cli::Command::Stations { location, use_station } => {
+            let location = resolve_location(&location).await?;
+            let (stations, preferred_station_id) = location_stations(&location, use_station.as_deref()).await?;
+            print_json(&Report::new("stations", report::StationsReport {
+                location: location.to_report_location(),
+                stations,
+                preferred_station_id,
+                max_observation_age_minutes: config::get().stations.max_observation_age_minutes,
+            }))
+        }
+        cli::Command::History { location, hours } => {
+            let location = resolve_location(&location).await?;
+            let station = nws::select_station(location.lat, location.lon, location.nws_station_id.as_deref()).await?
+                .ok_or_else(|| AppError::Api(format!("No weather observation stations found near '{}'", location.label)))?
+                .station;
+            let observations = nws::fetch_observation_history(&station.station_id, hours).await?;
+            print_json(&Report::new("observation_history", report::ObservationHistoryReport {
+                location: location.to_report_location(),
+                station,
+                hours,
+                summary: nws::HistorySummary::from_observations(&observations),
+                observations,
+            }))
+        }
+        cli::Command::Forecast { location, periods: count, .. } => {
+            let location = resolve_location(&location).await?;
+            let station = find_nearest_station(location.lat, location.lon).await?
+                .ok_or_else(|| AppError::Api(format!("No NWS forecast available for '{}'", location.label)))?;
+            let mut periods = nws::fetch_forecast(&station.forecast_url).await?;
+            periods.truncate(count);
+            print_json(&Report::new("forecast", report::ForecastReport {
+                location: location.to_report_location(),
+                station,
+                periods,
+            }))
+        }
+        cli::Command::Hourly { location, hours } => {
+            let location = resolve_location(&location).await?;
+            let station = find_nearest_station(location.lat, location.lon).await?
+                .ok_or_else(|| AppError::Api(format!("No NWS forecast available for '{}'", location.label)))?;
+            let url = station.forecast_hourly_url.clone()
+                .ok_or_else(|| AppError::Api(format!("No NWS hourly forecast available for '{}'", location.label)))?;
+            let mut periods = nws::fetch_hourly_forecast(&url).await?;
+            periods.truncate(hours);
+            print_json(&Report::new("hourly_forecast", report::ForecastReport {
+                location: location.to_report_location(),
+                station,
+                periods,
+            }))
+        }
+        cli::Command::Alerts { location } => {
+            let location = resolve_location(&location).await?;
+            let alerts = alerts::fetch_active_alerts(location.lat, location.lon).await?;
+            print_json(&Report::new("alerts", report::AlertsReport {
+                location: location.to_report_location(),
+                alerts,
+            }))
+        }
+        cli::Command::Tides { location } => {
+            let location = resolve_location(&location).await?;
+            let stations = tides::fetch_tide_stations().await?;
+            let station = tides::nearest_tide_station(&stations, location.lat, location.lon, location.state.as_deref())
+                .cloned()
+                .ok_or_else(|| AppError::Api(format!("No NOAA tide station found near '{}'", location.label)))?;
+            let today = chrono::Utc::now().date_naive();
+            let mut days = Vec::new();
+            for date in [today, today.succ_opt().unwrap()] {
+                let predictions = tides::fetch_tide_predictions(&station.id, date).await?;
+                days.push(report::TideDay { date, predictions });
+            }
+            print_json(&Report::new("tides", report::TideReport {
+                location: location.to_report_location(),
+                station,
+                days,
+            }))
+        }
+        cli::Command::Lookup { coordinates: (lat, lon), save, nickname } => {
+            let place = geocode::reverse_geocode(lat, lon).await?;
+            let saved = if save {
+                let mut book = AddressBook::load(&AddressBook::default_path())?;
+                let (id, _) = save_place(&mut book, &place, nickname.as_deref())?;
+                book.get(id).cloned()
+            } else {
+                None
+            };
+            print_json(&Report::new("place", report::PlaceReport { place, saved }))
+        }
+        cli::Command::Quakes { min_mag, hours } => {
+            let earthquakes = earthquakes::fetch_earthquakes(min_mag, hours).await?;
+            print_json(&Report::new("earthquakes", report::EarthquakeReport {
+                min_magnitude: min_mag,
+                hours,
+                earthquakes,
+            }))
+        }
+        cli::Command::AirportSearch { pattern, filter, category } => {
+            let mut results = filtered_airport_search(&pattern, &filter);
+            if let Some(category) = category {
+                results = retain_flight_category(results, category).await?;
+            }
+            let airports = results.into_iter().cloned().collect();
+            print_json(&Report::new("airport_search", report::AirportSearchReport { pattern, flight_category: category, airports }))
+        }
+        cli::Command::AirportsNear { location, radius_km, limit, filter } => {
+            let location = resolve_location(&location).await?;
+            let mut airports = airports::nearest_airports(location.lat, location.lon, radius_km, &filter);
+            airports.truncate(limit);
+            print_json(&Report::new("nearby_airports", report::NearbyAirportsReport {
+                location: location.to_report_location(),
+                radius_km,
+                airports,
+            }))
+        }
+        cli::Command::AirportDetails { code } => {
+            let airport = find_airport_by_code(code.trim())
+                .ok_or_else(|| AppError::UserInput(format!("No airport found with code '{}'.", code)))?;
+            let mut station = None;
+            let mut observation = None;
+            let mut forecast = Vec::new();
+            let mut active_alerts = Vec::new();
+            if let (Ok(lat), Ok(lon)) = (airport.latitude_deg.parse::<f64>(), airport.longitude_deg.parse::<f64>()) {
+                // Weather is best-effort: many airports have no nearby NWS station
+                if let Ok(Some(choice)) = nws::select_station(lat, lon, None).await {
+                    observation = choice.observation;
+                    forecast = nws::fetch_forecast(&choice.station.forecast_url).await.unwrap_or_default();
+                    active_alerts = alerts::fetch_active_alerts(lat, lon).await.unwrap_or_default();
+                    station = Some(choice.station);
+                }
+            }
+            let (metar, taf) = match airport.icao_code() {
+                Some(icao) => {
+                    let (metar, taf) = fetch_aviation_weather(icao).await;
+                    (metar.ok().flatten(), taf.ok().flatten())
+                }
+                None => (None, None),
+            };
+            let flight_category = metar
+                .as_ref()
+                .and_then(|m| m.flight_category())
+                .or_else(|| observation.as_ref().and_then(|obs| obs.flight_category()));
+            let runway_winds = runway_wind_analysis(airport, metar.as_ref(), observation.as_ref());
+            let altitudes = field_altitudes(airport, metar.as_ref(), observation.as_ref());
+            let mut navaids = match (airport.latitude_deg.parse::<f64>(), airport.longitude_deg.parse::<f64>()) {
+                (Ok(lat), Ok(lon)) => ourairports::navaids_near(lat, lon, NEARBY_NAVAID_RADIUS_KM),
+                _ => Vec::new(),
+            };
+            navaids.truncate(NEARBY_NAVAID_LIMIT);
+            print_json(&Report::new("airport", report::AirportReport {
+                airport: airport.clone(),
+                station,
+                observation,
+                forecast,
+                alerts: active_alerts,
+                flight_category,
+                runways: airport.runways().to_vec(),
+                runway_winds,
+                altitudes,
+                country: airport.country().cloned(),
+                region: airport.region().cloned(),
+                frequencies: airport.frequencies().to_vec(),
+                navaids,
+                metar,
+                taf,
+            }))
+        }
+        cli::Command::Addresses(command) => {
+            let mut book = AddressBook::load(&AddressBook::default_path())?;
+            match run_address_command(&mut book, command).await? {
+                AddressCommandResult::List(tag) => {
+                    let addresses = book.addresses().iter().filter(|a| tag.as_deref().is_none_or(|t| a.has_tag(t))).cloned().collect();
+                    print_json(&Report::new("addresses", report::AddressListReport { addresses }))
+                }
+                AddressCommandResult::Changed(address) => {
+                    print_json(&Report::new("address", report::AddressReport { address, deleted: false }))
+                }
+                AddressCommandResult::Deleted(address) => {
+                    print_json(&Report::new("address", report::AddressReport { address, deleted: true }))
+                }
+                AddressCommandResult::Imported(path, rows) => {
+                    print_json(&Report::new("address_import", report::AddressImportReport::new(&path, rows)))
+                }
+                AddressCommandResult::Normalized(addresses) => {
+                    print_json(&Report::new("address_normalize", report::AddressNormalizeReport { addresses }))
+                }
+                AddressCommandResult::Exported(Some(export)) => print_json(&Report::new("address_export", export)),
+                AddressCommandResult::Exported(None) => Ok(()),
+            }
+        }
+        cli::Command::Cache(cli::CacheCommand::List) => {
+            let cache = GeocodeCache::load(&GeocodeCache::default_path())?;
+            print_json(&Report::new("geocode_cache", report::GeocodeCacheReport::new(&cache)))
+        }
+        cli::Command::Cache(cli::CacheCommand::Clear { expired_only }) => {
+            let (removed, remaining) = clear_geocode_cache(expired_only)?;
+            print_json(&Report::new("geocode_cache_clear", report::GeocodeCacheClearReport { removed, remaining }))
+        }
+        // Handled in main() before the runtime starts
+        cli::Command::Help | cli::Command::AirportsRefresh => Ok(()),
+    }
 }
 
-#[derive(Deserialize, Debug)]
-struct ForecastPeriod {
-    name: String,
-    temperature: f64,
-    #[serde(rename = "temperatureUnit")]
-    temperature_unit: String,
-    #[serde(rename = "detailedForecast")]
-    detailed_forecast: String,
-    // We're ignoring these fields as they're not used in our display
-    #[serde(skip)]
-    _wind_direction: Option<String>,
-    #[serde(skip)]
-    _wind_speed: Option<String>,
+fn print_json<T: serde::Serialize>(value: &T) -> Result<(), AppError> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
 }
 
-#[derive(Debug, Deserialize)]
-struct EarthquakeFeatureCollection {
-    features: Vec<EarthquakeFeature>,
+// Text search plus the US-state and scheduled-service filters used by the airport menus
+fn filtered_airport_search(pattern: &str, filter: &AirportFilter) -> Vec<&'static airports::Airport> {
+    let mut results = airports::search_airports(pattern);
+    results.retain(|a| filter.matches(a));
+    results
 }
 
-#[derive(Debug, Deserialize)]
-struct EarthquakeFeature {
-    properties: EarthquakeProperties,
-    geometry: Option<EarthquakeGeometry>,
-}
-
-#[derive(Debug, Deserialize)]
-struct EarthquakeProperties {
-    mag: Option<f64>,
-    place: Option<String>,
-    time: Option<i64>,
-    url: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-struct EarthquakeGeometry {
-    coordinates: Vec<f64>, // [lon, lat, depth]
-}
-
-async fn geocode_address(
-    address_query: &str,
-) -> Result<Option<(String, f64, f64)>, AppError> {
-    let benchmark = "Public_AR_Current";
-    let format = "json";
-    let url = format!(
-        "https://geocoding.geo.census.gov/geocoder/locations/onelineaddress?address={}&benchmark={}&format={}", // Corrected URL
-        urlencoding::encode(address_query),
-        benchmark,
-        format
-    );
-
-    // println!("Geocoding with URL: {}", url); // Debugging, can be removed
-
-    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
-
-    if response.status().is_success() {
-        let geocode_data: GeocodeResponse = response.json().await.map_err(|e| AppError::Api(format!("Failed to parse JSON response from geocoding service: {}", e)))?;
-        if let Some(first_match) = geocode_data.result.address_matches.into_iter().next() {
-            Ok(Some((
-                first_match.matched_address, // Return the matched address string
-                first_match.coordinates.y, // latitude
-                first_match.coordinates.x, // longitude
-            )))
-        } else {
-            Ok(None) // No matches found by the API
-        }
-    } else {
-        let status = response.status();
-        let error_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        Err(AppError::Api(format!(
-            "Geocoding service returned an error (Status: {}). Details: {}",
-            status, error_text
-        )))
+// Keeps the airports whose latest METAR is in `category`. Only airports that may
+// report a METAR are asked about; the rest (most small fields) are dropped because
+// their category is unknown. Refuses searches with too many of them to check.
+async fn retain_flight_category(
+    airports: Vec<&'static airports::Airport>,
+    category: FlightCategory,
+) -> Result<Vec<&'static airports::Airport>, AppError> {
+    let icaos: Vec<&str> = airports.iter().filter(|a| a.may_report_metar()).filter_map(|a| a.icao_code()).collect();
+    if icaos.len() > FLIGHT_CATEGORY_MAX_AIRPORTS {
+        return Err(AppError::UserInput(format!(
+            "{} airports with weather reports match; the flight category filter checks at most {}. Narrow the search pattern or add the US-only or passenger-only filter.",
+            icaos.len(),
+            FLIGHT_CATEGORY_MAX_AIRPORTS
+        )));
     }
+    let categories = aviation_weather::fetch_flight_categories(&icaos).await?;
+    Ok(airports
+        .into_iter()
+        .filter(|a| a.icao_code().and_then(|icao| categories.get(icao)) == Some(&category))
+        .collect())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    airports::init_airports()?;
-    let airport_count = airports::get_airport_count();
-    println!("Reather - a Rust-based Weather App");
-    println!("USA airport database loaded: {} airports", airport_count);
-    println!("");
-    Ok(tokio::runtime::Builder::new_multi_thread()
-        .enable_all()
-        .build()?
-        .block_on(async_main())?)
+// Lists up to `limit` airports within `radius_km`, nearest first
+fn display_nearby_airports(lat: f64, lon: f64, radius_km: f64, limit: usize, filter: &AirportFilter) {
+    let results = airports::nearest_airports(lat, lon, radius_km, filter);
+    let units = config::units();
+    if results.is_empty() {
+        println!("No airports found within {}.", units.distance(radius_km));
+        return;
+    }
+    println!("\nAirports within {} ({} found):", units.distance(radius_km), results.len());
+    for nearby in results.iter().take(limit) {
+        let airport = nearby.airport;
+        println!(
+            "{:>10} {:>3} ({:03.0}°)  {} ({}) - {}, {}",
+            units.distance(nearby.distance_km),
+            compass_point(nearby.bearing_deg),
+            nearby.bearing_deg,
+            airport.name,
+            airport.ident,
+            airport.municipality,
+            airport.iso_region
+        );
+    }
 }
 
 async fn async_main() -> Result<(), AppError> {
-    // Get the executable directory as a fallback location
-    let exe_dir = env::current_exe()
-        .map(|path| path.parent().map(|p| p.to_path_buf()))
-        .unwrap_or(None)
-        .unwrap_or_else(|| PathBuf::from("."));
-    
-    // Check if the data directory exists
-    let data_dir_path = PathBuf::from(DATA_DIR);
-    
-    // Decide which directory to use for addresses.txt
-    // Use data directory if it exists, otherwise use executable directory
-    let addresses_path = if data_dir_path.exists() {
-        data_dir_path.join(ADDRESS_FILE)
-    } else {
-        // Do NOT create the data directory, just use the executable directory
-        exe_dir.join(ADDRESS_FILE)
-    };
-
-    // --- NEW: Geocode addresses missing lat/lon in addresses.txt ---
-    if addresses_path.exists() {
-        let file = File::open(&addresses_path).map_err(|e| io_error_with_path(e, &addresses_path))?;
-        let reader = BufReader::new(file);
-        let mut lines: Vec<String> = Vec::new();
-        let mut changed = false;
-        for line in reader.lines() {
-            let line = line.map_err(|e| io_error_with_path(e, &addresses_path))?;
-            let parts: Vec<&str> = line.split(';').collect();
-            if parts.len() == 1 && !parts[0].trim().is_empty() {
-                // Only address, missing lat/lon
-                let addr = parts[0].trim();
-                match geocode_address(addr).await {
-                    Ok(Some((matched_address, lat, lon))) => {
-                        lines.push(format!("{};{};{}", matched_address.to_uppercase(), lat, lon));
-                        changed = true;
-                    }
-                    Ok(None) => {
-                        // Could not geocode, keep as is
-                        lines.push(line);
-                    }
-                    Err(e) => {
-                        eprintln!("Error geocoding address '{}': {}. Keeping as is.", addr, e);
-                        lines.push(line);
-                    }
-                }
-            } else if parts.len() == 3 {
-                // Replace address with uppercase matched address if possible
-                let addr = parts[0].trim();
-                match geocode_address(addr).await {
-                    Ok(Some((matched_address, lat, lon))) => {
-                        // Only update if lat/lon match what's in the file
-                        let lat_ok = format!("{:.8}", lat) == format!("{:.8}", parts[1].parse::<f64>().unwrap_or(lat));
-                        let lon_ok = format!("{:.8}", lon) == format!("{:.8}", parts[2].parse::<f64>().unwrap_or(lon));
-                        if lat_ok && lon_ok {
-                            lines.push(format!("{};{};{}", matched_address.to_uppercase(), lat, lon));
-                            changed = true;
-                        } else {
-                            lines.push(line);
-                        }
-                    }
-                    _ => lines.push(line),
-                }
-            } else {
-                lines.push(line);
-            }
-        }
-        if changed {
-            let mut file = OpenOptions::new().write(true).truncate(true).open(&addresses_path).map_err(|e| io_error_with_path(e, &addresses_path))?;
-            for l in &lines {
-                writeln!(file, "{}", l).map_err(|e| io_error_with_path(e, &addresses_path))?;
-            }
-        }
-    }
-
-    if !addresses_path.exists() {
-        println!("\'{}\' does not exist.", addresses_path.display());
-        println!("Would you like to populate it with seed addresses? (yes/no)");
-        
-        let mut user_input = String::new();
-        io::stdin().read_line(&mut user_input)?;
-        if user_input.trim().eq_ignore_ascii_case("yes") {
-            println!("Processing seed addresses...");
-            let mut addresses_file = OpenOptions::new()
-                .create(true)
-                .write(true)
-                .truncate(true)
-                .open(&addresses_path)
-                .map_err(|e| io_error_with_path(e, &addresses_path))?;
-
-            for address_to_geocode in SEED_ADDRESSES.iter() {
-                println!("Geocoding seed address: {}", address_to_geocode);
-                match geocode_address(address_to_geocode).await {
-                    Ok(Some((matched_address, lat, lon))) => {
-                        writeln!(addresses_file, "{};{};{}", matched_address, lat, lon)
-                            .map_err(|e| io_error_with_path(e, &addresses_path))?;
-                        println!("  Stored: {};{};{}", matched_address, lat, lon);
-                    }
-                    Ok(None) => {
-                        eprintln!("{}", AppError::Api(format!("Could not geocode seed address: '{}'. Skipping.", address_to_geocode)));
-                    }
-                    Err(e) => {
-                        eprintln!("  Error geocoding seed address \'{}\': {}. Skipping.", address_to_geocode, e);
-                    }
-                }
-            }
-            println!("Seed addresses processed and stored in \'{}\'.", addresses_path.display());
-        } else {
-            println!("Skipping seed address population. You can add addresses manually.");
-            File::create(&addresses_path).map_err(|e| io_error_with_path(e, &addresses_path))?; // Create empty addresses.txt
-            // Write a special marker comment to indicate user explicitly declined seed data
-            let mut marker_file = OpenOptions::new()
-                .write(true)
-                .open(&addresses_path)
-                .map_err(|e| io_error_with_path(e, &addresses_path))?;
-            writeln!(marker_file, "# User declined seed data").map_err(|e| io_error_with_path(e, &addresses_path))?;
-        }
-    } else if addresses_path.metadata().map_err(|e| io_error_with_path(e, &addresses_path))?.len() == 0 {
-        // File exists but is completely empty - we should ask about seed data
-        println!("\'{}\' exists but is empty.", addresses_path.display());
-        println!("Would you like to populate it with seed addresses? (yes/no)");
-        
-        let mut user_input = String::new();
-        io::stdin().read_line(&mut user_input)?;
-        if user_input.trim().eq_ignore_ascii_case("yes") {
-            println!("Processing seed addresses...");
-            let mut addresses_file = OpenOptions::new()
-                .write(true)
-                .truncate(true)
-                .open(&addresses_path)
-                .map_err(|e| io_error_with_path(e, &addresses_path))?;
-
-            for address_to_geocode in SEED_ADDRESSES.iter() {
-                println!("Geocoding seed address: {}", address_to_geocode);
-                match geocode_address(address_to_geocode).await {
-                    Ok(Some((matched_address, lat, lon))) => {
-                        writeln!(addresses_file, "{};{};{}", matched_address, lat, lon)
-                            .map_err(|e| io_error_with_path(e, &addresses_path))?;
-                        println!("  Stored: {};{};{}", matched_address, lat, lon);
-                    }
-                    Ok(None) => {
-                        eprintln!("{}", AppError::Api(format!("Could not geocode seed address: '{}'. Skipping.", address_to_geocode)));
-                    }
-                    Err(e) => {
-                        eprintln!("  Error geocoding seed address \'{}\': {}. Skipping.", address_to_geocode, e);
-                    }
-                }
-            }
-            println!("Seed addresses processed and stored in \'{}\'.", addresses_path.display());
-        } else {
-            println!("Skipping seed address population. You can add addresses manually.");
-            // Write a special marker comment to indicate user explicitly declined seed data
-            let mut marker_file = OpenOptions::new()
-                .write(true)
-                .open(&addresses_path)
-                .map_err(|e| io_error_with_path(e, &addresses_path))?;
-            writeln!(marker_file, "# User declined seed data").map_err(|e| io_error_with_path(e, &addresses_path))?;
-        }
-    }
+    let mut book = open_address_book().await?;
 
     loop {
         println!("\nMain Menu:");
@@ -485,7 +764,10 @@ async fn async_main() -> Result<(), AppError> {
         println!("3. Airport search");
         println!("4. Earthquakes");
         println!("5. Tides");
-        println!("6. Exit");
+        println!("6. Weather alerts for all stored addresses");
+        println!("7. Manage address book");
+        println!("8. Look up coordinates or a Google Maps link");
+        println!("9. Exit");
         print!("Please enter your choice: ");
         io::stdout().flush()?;
 
@@ -500,56 +782,26 @@ async fn async_main() -> Result<(), AppError> {
                 let new_address_query = new_address_query.trim();
 
                 if !new_address_query.is_empty() {
-                    match geocode_address(new_address_query).await {
-                        Ok(Some((matched_address, lat, lon))) => {
-                            add_address_to_file(&matched_address, lat, lon, &addresses_path)?;
-                            println!("Address geocoded and added: {} (Lat: {}, Lon: {})", matched_address, lat, lon);
-                            if let Err(e) = show_address_submenu(matched_address, lat, lon).await {
+                    match geocode_and_store(&mut book, new_address_query).await {
+                        Ok(id) => {
+                            if let Err(e) = show_address_submenu(&mut book, id).await {
                                 eprintln!("Error in address submenu: {}", e);
                             }
                         }
-                        Ok(None) => {
-                            eprintln!("{}", AppError::Api(format!("Could not find a match for the address: \'{}\'", new_address_query)));
-                        }
-                        Err(e) => {
-                            eprintln!("Error geocoding address \'{}\': {}", new_address_query, e);
-                        }
+                        Err(e) => eprintln!("{}", e),
                     }
                 } else {
                     eprintln!("{}", AppError::UserInput("Address cannot be empty. Please try again.".to_string()));
                 }
             }
             "2" => {
-                let stored_data = load_addresses(&addresses_path)?;
-                if stored_data.is_empty() {
+                if book.is_empty() {
                     println!("No stored addresses found. Please add an address first (Option 1).");
                     continue;
                 }
-                println!("\nStored Addresses:");
-                for (i, (addr, _, _)) in stored_data.iter().enumerate() {
-                    println!("{}. {}", i + 1, addr);
-                }
-                println!("{}. Return to Main Menu", stored_data.len() + 1);
-                print!("Select an address number or return: ");
-                io::stdout().flush()?;
-
-                let mut selection_str = String::new();
-                io::stdin().read_line(&mut selection_str)?;
-                match selection_str.trim().parse::<usize>() {
-                    Ok(n) if n > 0 && n <= stored_data.len() => {
-                        let (selected_address, lat, lon) = stored_data[n - 1].clone();
-                        println!(); // Add a blank line for separation
-                        println!("Selected address: {} (Lat: {}, Lon: {})", selected_address, lat, lon);
-                        if let Err(e) = show_address_submenu(selected_address, lat, lon).await {
-                             eprintln!("Error in address submenu: {}", e);
-                        }
-                    }
-                    Ok(n) if n == stored_data.len() + 1 => continue,
-                    Ok(_) => {
-                        eprintln!("{}", AppError::UserInput("Invalid selection number. Please choose from the list.".to_string()));
-                    }
-                    Err(_) => {
-                        eprintln!("{}", AppError::UserInput("Invalid input. Please enter a number corresponding to an address or to return.".to_string()));
+                if let Some(id) = prompt_address_selection(&book, "Return to Main Menu")? {
+                    if let Err(e) = show_address_submenu(&mut book, id).await {
+                         eprintln!("Error in address submenu: {}", e);
                     }
                 }
             }
@@ -560,165 +812,594 @@ async fn async_main() -> Result<(), AppError> {
                 earthquake_menu().await?;
             }
             "5" => {
-                tides_menu().await?;
+                tides_menu(&mut book).await?;
             }
             "6" => {
+                check_alerts_for_all_addresses(&book).await?;
+            }
+            "7" => {
+                address_book_menu(&mut book).await?;
+            }
+            "8" => {
+                if let Err(e) = lookup_coordinates_interactive(&mut book).await {
+                    eprintln!("{}", e);
+                }
+            }
+            "9" => {
                 println!("Exiting Reather. Goodbye!");
                 break;
             }
-            _ => eprintln!("{}", AppError::UserInput("Invalid choice. Please enter 1, 2, 3, 4, 5, or 6.".to_string())),
+            _ => eprintln!("{}", AppError::UserInput("Invalid choice. Please enter a number from 1 to 9.".to_string())),
         }
     }
 
     Ok(())
 }
 
-fn load_addresses(path: &Path) -> Result<Vec<(String, f64, f64)>, AppError> {
-    if !path.exists() {
-        return Ok(Vec::new()); // Not an error, just no file yet
+// Loads the address book. The first run migrates a legacy addresses.txt, or
+// offers to populate the book with the seed addresses.
+async fn open_address_book() -> Result<AddressBook, AppError> {
+    let path = AddressBook::default_path();
+    if path.exists() {
+        return AddressBook::load(&path);
     }
-    let file = File::open(path).map_err(|e| io_error_with_path(e, path))?;
-    let reader = BufReader::new(file);
-    let mut addresses = Vec::new();
-    for (line_num, line_result) in reader.lines().enumerate() {
-        let line_content = line_result.map_err(|e| io_error_with_path(e, path))?;
-        
-        // Skip comment lines (starting with #)
-        if line_content.trim().starts_with('#') {
+    let mut book = AddressBook::new(&path);
+
+    let legacy_path = config::data_dir().join(addresses::LEGACY_ADDRESS_FILE);
+    if legacy_path.exists() {
+        println!("Migrating '{}' to '{}'...", legacy_path.display(), path.display());
+        let (entries, malformed) = addresses::read_legacy_file(&legacy_path)?;
+        for line in malformed {
+            eprintln!("  Skipping malformed line: '{}'", line);
+        }
+        // Address-only lines were geocoded at startup by older versions; do them in one batch
+        let (located, address_only): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.coords.is_some());
+        for entry in located {
+            let (lat, lon) = entry.coords.expect("partitioned on coords");
+            if book.find_duplicate(&entry.address, lat, lon).is_none() {
+                book.add(&entry.address, lat, lon);
+            }
+        }
+        if !address_only.is_empty() {
+            println!("Geocoding {} addresses without coordinates...", address_only.len());
+            let queries: Vec<String> = address_only.into_iter().map(|e| e.address).collect();
+            add_batch_matches(&mut book, &queries).await?;
+        }
+        book.save()?;
+        let backup_path = legacy_path.with_extension("txt.migrated");
+        fs::rename(&legacy_path, &backup_path).map_err(|e| io_error_with_path(e, &legacy_path))?;
+        println!("Migrated {} addresses. The old file was kept as '{}'.", book.len(), backup_path.display());
+        return Ok(book);
+    }
+
+    println!("\'{}\' does not exist.", path.display());
+    println!("Would you like to populate it with seed addresses? (yes/no)");
+    let mut user_input = String::new();
+    io::stdin().read_line(&mut user_input)?;
+    if user_input.trim().eq_ignore_ascii_case("yes") {
+        println!("Processing seed addresses...");
+        let queries: Vec<String> = SEED_ADDRESSES.iter().map(|a| a.to_string()).collect();
+        add_batch_matches(&mut book, &queries).await?;
+        println!("Seed addresses processed and stored in \'{}\'.", path.display());
+    } else {
+        // Saving the empty book means we don't ask again
+        println!("Skipping seed address population. You can add addresses manually.");
+    }
+    book.save()?;
+    Ok(book)
+}
+
+fn display_place(place: &geocode::PlaceDescription) {
+    println!("\n--- {:.5}, {:.5} ---", place.lat, place.lon);
+    if let Some(name) = &place.display_name {
+        println!("Place: {}", name);
+    }
+    let with_fips = |value: &Option<String>, fips: &Option<String>| match (value, fips) {
+        (Some(value), Some(fips)) => Some(format!("{} (FIPS {})", value, fips)),
+        (Some(value), None) => Some(value.clone()),
+        (None, Some(fips)) => Some(format!("FIPS {}", fips)),
+        (None, None) => None,
+    };
+    let state = place.state.as_ref().map(|state| match &place.state_code {
+        Some(code) => format!("{}, {}", state, code),
+        None => state.clone(),
+    });
+    let rows = [
+        ("City", place.city.clone()),
+        ("County", with_fips(&place.county, &place.county_fips)),
+        ("State", with_fips(&state, &place.state_fips)),
+        ("ZIP", place.zip.clone()),
+        ("Country", place.country_code.as_ref().map(|c| c.to_uppercase())),
+        ("Census block", place.block_fips.clone()),
+    ];
+    for (label, value) in rows {
+        println!("{:<13} {}", format!("{}:", label), value.as_deref().unwrap_or("-"));
+    }
+    println!("Google Maps:  https://www.google.com/maps?q={},{}", place.lat, place.lon);
+}
+
+// Saves a described coordinate as a typed-coordinates entry; returns the id and
+// whether it was added, or the id of the entry already at that place. Only the
+// coordinates count as a duplicate, since many places share a "City, ST, ZIP" label.
+fn save_place(book: &mut AddressBook, place: &geocode::PlaceDescription, nickname: Option<&str>) -> Result<(u32, bool), AppError> {
+    let label = place.label();
+    if let Some(existing) = book.find_near(place.lat, place.lon) {
+        return Ok((existing.id, false));
+    }
+    if let Some(nickname) = nickname {
+        book.check_nickname(nickname, None)?;
+    }
+    let entry = book.add_geocoded(&GeocodedAddress { matched_address: label, lat: place.lat, lon: place.lon, provider: GeocoderKind::Manual });
+    entry.nickname = nickname.map(|n| n.trim().to_string());
+    let id = entry.id;
+    book.save()?;
+    Ok((id, true))
+}
+
+// Main-menu coordinate lookup; offers to save the place and open its submenu
+async fn lookup_coordinates_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    print!("Enter lat,lon or paste a Google Maps link: ");
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let (lat, lon) = geocode::parse_coordinates(&input).ok_or_else(|| {
+        AppError::UserInput(format!("'{}' is not a lat,lon pair or a Google Maps link with coordinates.", input.trim()))
+    })?;
+    let place = geocode::reverse_geocode(lat, lon).await?;
+    display_place(&place);
+
+    print!("\nSave '{}' to the address book? (y/N): ", place.label());
+    io::stdout().flush()?;
+    input.clear();
+    io::stdin().read_line(&mut input)?;
+    if !input.trim().eq_ignore_ascii_case("y") {
+        return Ok(());
+    }
+    let nickname = prompt_field("Nickname", None)?.filter(|n| !n.is_empty());
+    let (id, added) = save_place(book, &place, nickname.as_deref())?;
+    if !added {
+        println!("Already saved as address {}.", id);
+    }
+    show_address_submenu(book, id).await
+}
+
+// Batch-geocodes the queries and adds each new match with its Census match status;
+// rows the batch could not match go through the configured geocoder chain
+async fn add_batch_matches(book: &mut AddressBook, queries: &[String]) -> Result<(), AppError> {
+    let matches = geocode::geocode_batch(queries).await?;
+    for (query, batch_match) in queries.iter().zip(matches) {
+        let resolved = match batch_match.geocoded {
+            Some(geocoded) => Some(geocoded),
+            None => geocode_address(query).await.ok().flatten(),
+        };
+        let Some(geocoded) = resolved else {
+            eprintln!("  Could not geocode '{}' ({}). Skipping.", query, batch_match.status.as_str());
+            continue;
+        };
+        if book.find_duplicate(&geocoded.matched_address, geocoded.lat, geocoded.lon).is_some() {
             continue;
         }
-        
-        let parts: Vec<&str> = line_content.split(';').collect();
-        if parts.len() == 3 {
-            let lat_result = parts[1].parse::<f64>();
-            let lon_result = parts[2].parse::<f64>();
-            match (lat_result, lon_result) {
-                (Ok(lat), Ok(lon)) => {
-                    addresses.push((parts[0].to_string(), lat, lon));
-                }
-                _ => {
-                    eprintln!(
-                        "Warning: Malformed data in \'{}\' at line {}: Could not parse latitude/longitude for address \'{}\'. Skipping this entry.",
-                        path.display(), line_num + 1, parts[0]
-                    );
-                }
+        book.add_geocoded(&geocoded).match_status = Some(batch_match.status);
+        println!(
+            "  Stored: {} (Lat: {}, Lon: {}, {}){}",
+            geocoded.matched_address, geocoded.lat, geocoded.lon, batch_match.status.as_str(), geocoder_note(geocoded.provider)
+        );
+    }
+    Ok(())
+}
+
+// Geocodes a query and saves the match, unless it is already in the book.
+// Returns the id of the new or existing entry.
+async fn geocode_and_store(book: &mut AddressBook, query: &str) -> Result<u32, AppError> {
+    let geocoded = geocode_address(query).await?
+        .ok_or_else(|| AppError::Api(format!("Could not find a match for the address: '{}'", query)))?;
+    if let Some(existing) = book.find_duplicate(&geocoded.matched_address, geocoded.lat, geocoded.lon) {
+        println!("Already saved as address {}: {}", existing.id, existing.label());
+        return Ok(existing.id);
+    }
+    let id = book.add_geocoded(&geocoded).id;
+    book.save()?;
+    println!(
+        "Address geocoded by {} and added: {} (Lat: {}, Lon: {})",
+        geocoded.provider, geocoded.matched_address, geocoded.lat, geocoded.lon
+    );
+    Ok(id)
+}
+
+// Lists the book by position and returns the chosen entry's id
+fn prompt_address_selection(book: &AddressBook, return_label: &str) -> Result<Option<u32>, AppError> {
+    println!("\nStored Addresses:");
+    for (i, entry) in book.addresses().iter().enumerate() {
+        println!("{}. {}", i + 1, entry.label());
+    }
+    println!("{}. {}", book.len() + 1, return_label);
+    print!("Select an address number or return: ");
+    io::stdout().flush()?;
+
+    let mut selection_str = String::new();
+    io::stdin().read_line(&mut selection_str)?;
+    match selection_str.trim().parse::<usize>() {
+        Ok(n) if n > 0 && n <= book.len() => Ok(Some(book.addresses()[n - 1].id)),
+        Ok(n) if n == book.len() + 1 => Ok(None),
+        Ok(_) => Err(AppError::UserInput("Invalid selection number. Please choose from the list.".to_string())),
+        Err(_) => Err(AppError::UserInput("Invalid input. Please enter a number corresponding to an address or to return.".to_string())),
+    }
+}
+
+async fn address_book_menu(book: &mut AddressBook) -> Result<(), AppError> {
+    loop {
+        println!("\n--- Address Book ({} addresses) ---", book.len());
+        println!("1. List addresses");
+        println!("2. Add an address");
+        println!("3. Edit an address");
+        println!("4. Delete an address");
+        println!("5. Move an address");
+        println!("6. Import from CSV");
+        println!("7. Export (CSV, GeoJSON or vCard)");
+        println!("8. Re-geocode all addresses (Census batch)");
+        println!("9. Return to Main Menu");
+        print!("Please enter your choice: ");
+        io::stdout().flush()?;
+
+        let mut choice = String::new();
+        io::stdin().read_line(&mut choice)?;
+        let result = match choice.trim() {
+            "1" => {
+                display_address_book(book, None);
+                Ok(())
             }
-        } else if !line_content.trim().is_empty() { // Ignore empty lines silently
-            eprintln!(
-                "Warning: Malformed line in \'{}\' at line {}: \'{}\'. Expected 3 parts separated by semicolons. Skipping this entry.",
-                path.display(), line_num + 1, line_content
-            );
+            "2" => add_address_interactive(book).await,
+            "3" => edit_address_interactive(book),
+            "4" => delete_address_interactive(book),
+            "5" => move_address_interactive(book),
+            "6" => import_addresses_interactive(book).await,
+            "7" => export_addresses_interactive(book),
+            "8" => normalize_address_book(book).await.map(|results| display_normalized_addresses(&results)),
+            "9" => break,
+            _ => Err(AppError::UserInput("Invalid choice, please try again.".to_string())),
+        };
+        if let Err(e) = result {
+            eprintln!("{}", e);
         }
     }
-    Ok(addresses)
+    Ok(())
 }
 
-fn add_address_to_file(address: &str, lat: f64, lon: f64, path: &Path) -> Result<(), AppError> {
-    let mut file = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(path)
-        .map_err(|e| io_error_with_path(e, path))?;
-    writeln!(file, "{};{};{}", address, lat, lon).map_err(|e| io_error_with_path(e, path))?;
+fn display_address_book(book: &AddressBook, tag: Option<&str>) {
+    let entries: Vec<&StoredAddress> = book.addresses().iter().filter(|a| tag.is_none_or(|t| a.has_tag(t))).collect();
+    if entries.is_empty() {
+        println!("No stored addresses{}.", tag.map(|t| format!(" tagged '{}'", t)).unwrap_or_default());
+        return;
+    }
+    for entry in entries {
+        display_address_book_entry(entry);
+    }
+}
+
+async fn import_addresses_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    let Some(path) = prompt_field("CSV file to import", None)?.filter(|p| !p.is_empty()) else {
+        return Ok(());
+    };
+    let path = PathBuf::from(path);
+    let (rows, mut outcomes) = addresses::read_import_csv(&path)?;
+    println!("Geocoding {} rows...", rows.len());
+    outcomes.extend(book.import_rows(rows).await?);
+    outcomes.sort_by_key(|o| o.line);
+    book.save()?;
+    display_import_outcomes(&path, &outcomes);
     Ok(())
 }
 
-async fn find_nearest_station(lat: f64, lon: f64) -> Result<Option<(String, String, Option<f64>, Option<f64>, String)>, AppError> {
-    let points_url = format!("https://api.weather.gov/points/{},{}" , lat, lon); // Corrected URL
-    // println!("Fetching station grid from: {}", points_url); // Debugging
+fn export_addresses_interactive(book: &AddressBook) -> Result<(), AppError> {
+    let Some(path) = prompt_field("Export to file (.csv, .geojson or .vcf)", None)?.filter(|p| !p.is_empty()) else {
+        return Ok(());
+    };
+    let path = PathBuf::from(path);
+    let format = addresses::ExportFormat::from_path(&path)
+        .ok_or_else(|| AppError::UserInput(format!("Cannot tell the export format from '{}'; use .csv, .geojson or .vcf.", path.display())))?;
+    let entries: Vec<&StoredAddress> = book.addresses().iter().collect();
+    fs::write(&path, addresses::export(&entries, format)?).map_err(|e| io_error_with_path(e, &path))?;
+    println!("Exported {} addresses to {} ({}).", entries.len(), path.display(), format);
+    Ok(())
+}
 
-    let points_response = HTTP_CLIENT.get(&points_url).send().await.map_err(AppError::Network)?;
+// Re-geocodes the whole book in one batch and saves it
+async fn normalize_address_book(book: &mut AddressBook) -> Result<Vec<report::NormalizedAddress>, AppError> {
+    let results = book.normalize().await?;
+    book.save()?;
+    Ok(results
+        .into_iter()
+        .map(|(id, previous_address, batch_match)| report::NormalizedAddress {
+            id,
+            previous_address,
+            match_status: batch_match.status,
+            matched: batch_match.geocoded,
+        })
+        .collect())
+}
 
-    if !points_response.status().is_success() {
-        let status = points_response.status();
-        let text = points_response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        return Err(AppError::Api(format!(
-            "NWS Points API request failed (Status: {}). URL: {}. Details: {}",
-            status, points_url, text
-        )));
+fn display_normalized_addresses(results: &[report::NormalizedAddress]) {
+    println!("\n--- Census batch geocoding ---");
+    for result in results {
+        let via = result.matched.as_ref().map(|m| geocoder_note(m.provider)).unwrap_or_default();
+        match &result.matched {
+            Some(matched) if matched.matched_address != result.previous_address => println!(
+                "[{}] {:<9} {} -> {}{}",
+                result.id, result.match_status.as_str(), result.previous_address, matched.matched_address, via
+            ),
+            Some(_) => println!("[{}] {:<9} {}{}", result.id, result.match_status.as_str(), result.previous_address, via),
+            None => println!("[{}] {:<9} {} (kept as is)", result.id, result.match_status.as_str(), result.previous_address),
+        }
     }
+    let count = |status| results.iter().filter(|r| r.match_status == status).count();
+    let fallback = results.iter().filter(|r| matches!(r.match_status, MatchStatus::NoMatch | MatchStatus::Tie) && r.matched.is_some()).count();
+    println!(
+        "\n{} exact, {} non-exact, {} matched by the geocoder chain, {} without a match.",
+        count(MatchStatus::Exact),
+        count(MatchStatus::NonExact),
+        fallback,
+        results.iter().filter(|r| r.matched.is_none()).count()
+    );
+}
 
-    let points_data: NWSPointResponse = points_response.json().await.map_err(|e| {
-        AppError::Api(format!("Failed to parse JSON response from NWS Points API (URL: {}): {}", points_url, e))
-    })?;
-    
-    let stations_url = points_data.properties.observation_stations_url; // Restored correct URL
-    let forecast_url = points_data.properties.forecast;
-    // println!("Fetching stations from: {}", stations_url); // Debugging
-
-    let stations_response = HTTP_CLIENT.get(&stations_url).send().await.map_err(AppError::Network)?;
-
-    if !stations_response.status().is_success() {
-        let status = stations_response.status();
-        let text = stations_response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        return Err(AppError::Api(format!(
-            "NWS Stations API request failed (Status: {}). URL: {}. Details: {}",
-            status, stations_url, text
-        )));
+// " (via nominatim)" for matches from a geocoder other than the Census batch
+fn geocoder_note(provider: GeocoderKind) -> String {
+    if provider == GeocoderKind::Census {
+        String::new()
+    } else {
+        format!(" (via {})", provider)
     }
+}
 
-    let stations_data: NWSStationsResponse = stations_response.json().await.map_err(|e| {
-         AppError::Api(format!("Failed to parse JSON response from NWS Stations API (URL: {}): {}", stations_url, e))
-    })?;
+fn display_import_outcomes(path: &Path, outcomes: &[ImportOutcome]) {
+    println!("\n--- Import from {} ---", path.display());
+    for outcome in outcomes {
+        let status = match outcome.status {
+            ImportStatus::Added => "added",
+            ImportStatus::Duplicate => "skipped",
+            ImportStatus::Failed => "FAILED",
+        };
+        let id = outcome.id.map(|id| format!(" [{}]", id)).unwrap_or_default();
+        println!("Line {:>4}: {:<7}{} {}", outcome.line, status, id, outcome.query);
+        if let Some(message) = &outcome.message {
+            let via = outcome.provider.filter(|_| outcome.status == ImportStatus::Added).map(geocoder_note).unwrap_or_default();
+            println!("           {}{}", message, via);
+        }
+    }
+    let count = |status| outcomes.iter().filter(|o| o.status == status).count();
+    println!(
+        "\n{} added, {} duplicates skipped, {} failed.",
+        count(ImportStatus::Added), count(ImportStatus::Duplicate), count(ImportStatus::Failed)
+    );
+}
 
-    if let Some(first_station_feature) = stations_data.features.into_iter().next() {
-        let station_id = first_station_feature.properties.station_identifier;
-        let station_name = first_station_feature.properties.name;
-        
-        let (station_lat, station_lon) = 
-            if let Some(geometry) = first_station_feature.geometry {
-                if let Some(coords) = geometry.coordinates {
-                    if coords.len() == 2 {
-                        (Some(coords[1]), Some(coords[0])) // lat, lon
-                    } else {
-                        eprintln!("Warning: Station {} geometry coordinates array does not have 2 elements.", station_id);
-                        (None, None)
-                    }
-                } else {
-                    (None, None)
+fn display_address_book_entry(entry: &StoredAddress) {
+    println!("\n[{}] {}", entry.id, entry.nickname.as_deref().unwrap_or("(no nickname)"));
+    println!("    {} (Lat: {}, Lon: {})", entry.address, entry.lat, entry.lon);
+    if !entry.tags.is_empty() {
+        println!("    Tags: {}", entry.tags.join(", "));
+    }
+    if let Some(notes) = &entry.notes {
+        println!("    Notes: {}", notes);
+    }
+    let stations: Vec<String> = [("NWS station", &entry.nws_station_id), ("Tide station", &entry.tide_station_id)]
+        .iter()
+        .filter_map(|(label, id)| id.as_ref().map(|id| format!("{} {}", label, id)))
+        .collect();
+    if !stations.is_empty() {
+        println!("    {}", stations.join(", "));
+    }
+    match (entry.provider, entry.match_status) {
+        (Some(provider), Some(status)) => println!("    Geocoded by {} ({})", provider, status.as_str()),
+        (Some(provider), None) => println!("    Geocoded by {}", provider),
+        (None, Some(status)) => println!("    Census match: {}", status.as_str()),
+        (None, None) => {}
+    }
+    println!(
+        "    Added {}, updated {}",
+        entry.created_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+        entry.updated_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+    );
+}
+
+// Reads one line; Enter keeps the current value, '-' clears it
+fn prompt_field(label: &str, current: Option<&str>) -> Result<Option<String>, AppError> {
+    match current {
+        Some(value) => print!("{} [{}] (Enter to keep, '-' to clear): ", label, value),
+        None => print!("{} (Enter to skip): ", label),
+    }
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(match input.trim() {
+        "" => None,
+        "-" => Some(String::new()),
+        value => Some(value.to_string()),
+    })
+}
+
+fn prompt_address_edits(entry: Option<&StoredAddress>) -> Result<cli::AddressEdits, AppError> {
+    let current_tags = entry.map(|e| e.tags.join(", ")).filter(|t| !t.is_empty());
+    Ok(cli::AddressEdits {
+        nickname: prompt_field("Nickname", entry.and_then(|e| e.nickname.as_deref()))?,
+        tags: prompt_field("Tags, comma-separated", current_tags.as_deref())?.map(|t| addresses::split_tags(&t)),
+        notes: prompt_field("Notes", entry.and_then(|e| e.notes.as_deref()))?,
+    })
+}
+
+// Applies nickname/tags/notes changes; an empty nickname or notes value clears it
+fn apply_address_edits(book: &mut AddressBook, id: u32, edits: &cli::AddressEdits) -> Result<(), AppError> {
+    if let Some(nickname) = edits.nickname.as_deref().filter(|n| !n.is_empty()) {
+        book.check_nickname(nickname, Some(id))?;
+    }
+    let entry = book.get_mut(id).ok_or_else(|| AppError::UserInput(format!("No address with id {}.", id)))?;
+    if let Some(nickname) = &edits.nickname {
+        entry.nickname = Some(nickname.trim().to_string()).filter(|n| !n.is_empty());
+    }
+    if let Some(tags) = &edits.tags {
+        entry.tags = tags.clone();
+    }
+    if let Some(notes) = &edits.notes {
+        entry.notes = Some(notes.trim().to_string()).filter(|n| !n.is_empty());
+    }
+    entry.touch();
+    Ok(())
+}
+
+async fn add_address_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    print!("Enter new address: ");
+    io::stdout().flush()?;
+    let mut query = String::new();
+    io::stdin().read_line(&mut query)?;
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::UserInput("Address cannot be empty. Please try again.".to_string()));
+    }
+    let id = geocode_and_store(book, query).await?;
+    let edits = prompt_address_edits(book.get(id))?;
+    apply_address_edits(book, id, &edits)?;
+    book.save()
+}
+
+fn edit_address_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    let Some(id) = prompt_address_selection(book, "Cancel")? else {
+        return Ok(());
+    };
+    let edits = prompt_address_edits(book.get(id))?;
+    apply_address_edits(book, id, &edits)?;
+    book.save()?;
+    println!("Address {} updated.", id);
+    Ok(())
+}
+
+fn delete_address_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    let Some(id) = prompt_address_selection(book, "Cancel")? else {
+        return Ok(());
+    };
+    let label = book.get(id).map(|e| e.label()).unwrap_or_default();
+    print!("Delete '{}'? (y/N): ", label);
+    io::stdout().flush()?;
+    let mut confirm = String::new();
+    io::stdin().read_line(&mut confirm)?;
+    if confirm.trim().eq_ignore_ascii_case("y") {
+        book.remove(id);
+        book.save()?;
+        println!("Deleted '{}'.", label);
+    }
+    Ok(())
+}
+
+fn move_address_interactive(book: &mut AddressBook) -> Result<(), AppError> {
+    let Some(id) = prompt_address_selection(book, "Cancel")? else {
+        return Ok(());
+    };
+    print!("New position (1-{}): ", book.len());
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let position = input.trim().parse::<usize>()
+        .ok()
+        .filter(|p| (1..=book.len()).contains(p))
+        .ok_or_else(|| AppError::UserInput(format!("Position must be between 1 and {}.", book.len())))?;
+    book.move_to(id, position - 1)?;
+    book.save()
+}
+
+async fn fetch_and_display_alerts(lat: f64, lon: f64) -> Result<(), AppError> {
+    println!("\nChecking NWS alerts for Lat: {}, Lon: {}...", lat, lon);
+    let alerts = alerts::fetch_active_alerts(lat, lon).await?;
+    display_alerts(&alerts);
+    Ok(())
+}
+
+fn display_alerts(alerts: &[Alert]) {
+    if alerts.is_empty() {
+        println!("No active weather alerts.");
+        return;
+    }
+    println!("\n--- Active Weather Alerts ({}) ---", alerts.len());
+    for alert in alerts {
+        println!("\n{} [{} / {}]", alert.event.to_uppercase(), alert.severity, alert.urgency);
+        if let Some(headline) = &alert.headline {
+            println!("{}", headline);
+        }
+        let format_time = |t: &Option<chrono::DateTime<chrono::FixedOffset>>| {
+            t.map(|t| t.format("%a %b %-d %-I:%M %p").to_string()).unwrap_or_else(|| "N/A".to_string())
+        };
+        println!("Onset: {}  Expires: {}", format_time(&alert.onset), format_time(&alert.expires));
+        if let Some(area) = &alert.area_desc {
+            println!("Area: {}", area);
+        }
+        if let Some(instruction) = &alert.instruction {
+            println!("Instructions: {}", instruction.split_whitespace().collect::<Vec<_>>().join(" "));
+        }
+    }
+}
+
+// Main-menu sweep: one alerts request per stored address, summarised
+async fn check_alerts_for_all_addresses(book: &AddressBook) -> Result<(), AppError> {
+    if book.is_empty() {
+        println!("No stored addresses found. Please add an address first (Option 1).");
+        return Ok(());
+    }
+    println!("\nChecking NWS alerts for {} stored addresses...", book.len());
+    let mut with_alerts = 0;
+    for entry in book.addresses() {
+        let address = entry.label();
+        match alerts::fetch_active_alerts(entry.lat, entry.lon).await {
+            Ok(alerts) if alerts.is_empty() => println!("\n{}: no active alerts", address),
+            Ok(alerts) => {
+                with_alerts += 1;
+                println!("\n{}:", address);
+                for alert in &alerts {
+                    println!("  {} [{} / {}]{}", alert.event, alert.severity, alert.urgency,
+                        alert.expires.map(|t| format!(" until {}", t.format("%a %-I:%M %p"))).unwrap_or_default());
                 }
-            } else {
-                (None, None)
-            };
-        Ok(Some((station_id, station_name, station_lat, station_lon, forecast_url)))
-    } else {
-        // Attempt to use relative location as a fallback name if no stations are found
-        let fallback_name = points_data.properties.relative_location
-            .map(|loc| format!("area of {}, {}", loc.properties.city, loc.properties.state))
-            .unwrap_or_else(|| "the specified location".to_string());
-        println!("No observation stations found directly listed for {}. The weather data might be from a broader area.", fallback_name);
-        // It might be better to return Ok(None) here if no station is truly found,
-        // or a specific error/message indicating no stations.
-        // For now, let's stick to the previous logic of a fallback if the API itself provides one.
-        // The current structure implies `UNKNOWN_STATION_API_EMPTY` is a specific case.
-        // Let's refine this: if `features` is empty, it means no stations.
-        Ok(None)
+            }
+            Err(e) => eprintln!("\n{}: error fetching alerts: {}", address, e),
+        }
     }
+    println!("\n{} of {} addresses have active alerts.", with_alerts, book.len());
+    Ok(())
 }
 
-async fn show_address_submenu(address: String, lat: f64, lon: f64) -> Result<(), AppError> {
+async fn show_address_submenu(book: &mut AddressBook, address_id: u32) -> Result<(), AppError> {
+    let entry = book.get(address_id).cloned().ok_or_else(|| AppError::UserInput(format!("No address with id {}.", address_id)))?;
+    let (address, lat, lon) = (entry.address.clone(), entry.lat, entry.lon);
     println!(
         "\nOperating for address: {} (Lat: {}, Lon: {})",
-        address, lat, lon
+        entry.label(), lat, lon
     );
 
     let mut station_id = "UNKNOWN_STATION".to_string();
     let mut station_name = "Unknown Station Name".to_string();
     let mut station_lat: Option<f64> = None;
     let mut station_lon: Option<f64> = None;
+    let mut station_time_zone: Option<String> = None;
     let mut forecast_url: Option<String> = None;
+    let mut forecast_hourly_url: Option<String> = None;
 
-    match find_nearest_station(lat, lon).await {
-        Ok(Some((id, name, s_lat, s_lon, f_url))) => {
+    match nws::select_station(lat, lon, entry.nws_station_id.as_deref()).await {
+        Ok(Some(choice)) => {
+            display_skipped_stations(&choice);
+            let first_choice = choice.is_first_choice();
+            let NearestStation { station_id: id, station_name: name, lat: s_lat, lon: s_lon, time_zone, forecast_url: f_url, forecast_hourly_url: h_url, .. } = choice.station;
             station_id = id;
             station_name = name;
             station_lat = s_lat;
             station_lon = s_lon;
+            station_time_zone = time_zone;
             forecast_url = Some(f_url);
+            forecast_hourly_url = h_url;
+            // A stale-station fallback is temporary; only a first lookup that got the nearest
+            // station, or an explicit choice, is remembered
+            if entry.nws_station_id.is_none() && first_choice {
+                remember_station(book, address_id, |e| e.nws_station_id = Some(station_id.clone()));
+            }
             if let (Some(s_lat_val), Some(s_lon_val)) = (station_lat, station_lon) {
-                println!("Found nearest station: {} ({}) - Lat: {}, Lon: {}", station_name, station_id, s_lat_val, s_lon_val);
+                println!("Using station: {} ({}) - Lat: {}, Lon: {}", station_name, station_id, s_lat_val, s_lon_val);
             } else {
-                println!("Found nearest station: {} ({}) (Coordinates not available from API)", station_name, station_id);
+                println!("Using station: {} ({}) (Coordinates not available from API)", station_name, station_id);
             }
         }
         Ok(None) => {
@@ -732,11 +1413,17 @@ async fn show_address_submenu(address: String, lat: f64, lon: f64) -> Result<(),
     }
 
     loop {
-        println!("\n--- Submenu for {} ---", address);
+        println!("\n--- Submenu for {} ---", entry.label());
         println!("1. Get Current Conditions");
         println!("2. Get Local Forecast");
-        println!("3. External Links (Maps, Flights, Real Estate)");
-        println!("4. Return to Main Menu");
+        println!("3. Get Hourly Forecast");
+        println!("4. Active Weather Alerts");
+        println!("5. External Links (Maps, Flights, Real Estate)");
+        println!("6. Nearby Airports");
+        println!("7. Tides");
+        println!("8. Choose Observation Station");
+        println!("9. Observation History and Trends");
+        println!("10. Return to Main Menu");
         print!("Please enter your choice: ");
         io::stdout().flush()?;
 
@@ -745,13 +1432,14 @@ async fn show_address_submenu(address: String, lat: f64, lon: f64) -> Result<(),
 
         match choice.trim() {
             "1" => {
-                if let Err(e) = fetch_and_display_weather(&station_id, &station_name).await {
+                if let Err(e) = fetch_and_display_weather(&station_id, &station_name, station_time_zone.as_deref()).await {
                     eprintln!("Error fetching weather: {}", e);
                 }
             }
             "2" => {
                 if let Some(url) = &forecast_url {
-                    if let Err(e) = fetch_and_display_local_forecast(url, &station_name).await {
+                    let (periods, view) = prompt_forecast_options()?;
+                    if let Err(e) = fetch_and_display_local_forecast(url, &station_name, periods, view).await {
                         eprintln!("Error fetching local forecast: {}", e);
                     }
                 } else {
@@ -759,9 +1447,73 @@ async fn show_address_submenu(address: String, lat: f64, lon: f64) -> Result<(),
                 }
             }
             "3" => {
-                display_external_links(&address, lat, lon, &station_id, &station_name, station_lat, station_lon).await;
+                if let Some(url) = &forecast_hourly_url {
+                    let hours = prompt_hourly_forecast_hours()?;
+                    if let Err(e) = fetch_and_display_hourly_forecast(url, &station_name, hours).await {
+                        eprintln!("Error fetching hourly forecast: {}", e);
+                    }
+                } else {
+                    eprintln!("Hourly forecast URL not available for this location.");
+                }
             }
             "4" => {
+                if let Err(e) = fetch_and_display_alerts(lat, lon).await {
+                    eprintln!("Error fetching weather alerts: {}", e);
+                }
+            }
+            "5" => {
+                display_external_links(&address, lat, lon, &station_id, &station_name, station_lat, station_lon).await;
+            }
+            "6" => {
+                print!("Search radius in km (default {}): ", NEARBY_AIRPORT_RADIUS_KM);
+                io::stdout().flush()?;
+                let mut radius_input = String::new();
+                io::stdin().read_line(&mut radius_input)?;
+                let radius_km = match radius_input.trim() {
+                    "" => NEARBY_AIRPORT_RADIUS_KM,
+                    value => match value.parse::<f64>() {
+                        Ok(r) if r > 0.0 && r <= airports::MAX_RADIUS_KM => r,
+                        _ => {
+                            eprintln!("{}", AppError::UserInput(format!("Radius must be a positive number of kilometres, at most {}.", airports::MAX_RADIUS_KM)));
+                            continue;
+                        }
+                    },
+                };
+                print!("Passenger airports (scheduled service) only? (y/N): ");
+                io::stdout().flush()?;
+                let mut passenger_input = String::new();
+                io::stdin().read_line(&mut passenger_input)?;
+                let filter = AirportFilter { us_only: false, scheduled_only: passenger_input.trim().eq_ignore_ascii_case("y") };
+                display_nearby_airports(lat, lon, radius_km, NEARBY_AIRPORT_LIMIT, &filter);
+            }
+            "7" => {
+                if let Err(e) = tides_for_address(book, address_id).await {
+                    eprintln!("Error fetching tides: {}", e);
+                }
+            }
+            "8" => match choose_station(book, address_id, lat, lon).await {
+                Ok(Some(station)) => {
+                    station_id = station.station_id;
+                    station_name = station.station_name;
+                    station_lat = station.lat;
+                    station_lon = station.lon;
+                    station_time_zone = station.time_zone;
+                    println!("Using station: {} ({}) for this address from now on.", station_name, station_id);
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("Error listing stations: {}", e),
+            },
+            "9" => {
+                if station_id.starts_with("UNKNOWN_STATION") {
+                    eprintln!("Cannot fetch observation history: no station was found.");
+                    continue;
+                }
+                let hours = prompt_history_hours()?;
+                if let Err(e) = fetch_and_display_history(&station_id, &station_name, station_time_zone.as_deref(), hours).await {
+                    eprintln!("Error fetching observation history: {}", e);
+                }
+            }
+            "10" => {
                 println!("Returning to Main Menu...");
                 break;
             }
@@ -771,37 +1523,144 @@ async fn show_address_submenu(address: String, lat: f64, lon: f64) -> Result<(),
     Ok(())
 }
 
-async fn fetch_and_display_local_forecast(forecast_url: &str, station_name: &str) -> Result<(), AppError> {
+// Asks how many forecast periods to show and in which view
+fn prompt_forecast_options() -> Result<(usize, ForecastView), AppError> {
+    print!("How many periods (1-{}, default {} = 7 days): ", cli::FORECAST_PERIODS, cli::FORECAST_PERIODS);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let periods = match input.trim().parse::<usize>() {
+        Ok(n) if (1..=cli::FORECAST_PERIODS).contains(&n) => n,
+        _ => cli::FORECAST_PERIODS,
+    };
+    print!("View: 1. Table  2. Detailed (default 1): ");
+    io::stdout().flush()?;
+    input.clear();
+    io::stdin().read_line(&mut input)?;
+    let view = if input.trim() == "2" { ForecastView::Detailed } else { ForecastView::Table };
+    Ok((periods, view))
+}
+
+async fn fetch_and_display_local_forecast(forecast_url: &str, station_name: &str, count: usize, view: ForecastView) -> Result<(), AppError> {
     println!("\nFetching local forecast for area near {}...", station_name);
 
-    let response = HTTP_CLIENT.get(forecast_url).send().await.map_err(AppError::Network)?;
+    let periods = nws::fetch_forecast(forecast_url).await?;
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        return Err(AppError::Api(format!(
-            "NWS Forecast API request failed (Status: {}). URL: {}. Details: {}",
-            status, forecast_url, text
-        )));
+    println!("\n--- Local Forecast for area near {} ---", station_name);
+    display_forecast_periods(&periods, count, view);
+    Ok(())
+}
+
+fn display_forecast_periods(periods: &[nws::ForecastPeriod], count: usize, view: ForecastView) {
+    if periods.is_empty() {
+        println!("No forecast periods available for this location.");
+        return;
     }
+    let periods = &periods[..count.min(periods.len())];
+    let units = config::units();
+    match view {
+        ForecastView::Table => {
+            println!("{:<16} {:>6} {:>6} {:<16} Forecast", "Period", "Temp", "Precip", "Wind");
+            for period in periods {
+                let wind = forecast_wind(period, units);
+                println!(
+                    "{:<16} {:>6} {:>6} {:<16} {}",
+                    period.name,
+                    units.forecast_temperature(period.temperature, &period.temperature_unit),
+                    period.probability_of_precipitation.map(|p| format!("{:.0}%", p)).unwrap_or_default(),
+                    wind,
+                    period.short_forecast
+                );
+            }
+        }
+        ForecastView::Detailed => {
+            for period in periods {
+                println!("\n{} ({})", period.name, units.forecast_temperature(period.temperature, &period.temperature_unit));
+                if period.wind_speed.is_some() {
+                    println!("Wind: {}", forecast_wind(period, units));
+                }
+                if let Some(precip) = period.probability_of_precipitation {
+                    println!("Chance of precipitation: {:.0}%", precip);
+                }
+                println!("{}", period.detailed_forecast);
+            }
+        }
+    }
+}
 
-    let forecast_data: ForecastResponse = response.json().await.map_err(|e| {
-        AppError::Api(format!("Failed to parse JSON response from NWS Forecast API (URL: {}): {}", forecast_url, e))
-    })?;
+// Asks how many hours of the hourly forecast to show
+fn prompt_hourly_forecast_hours() -> Result<usize, AppError> {
+    print!("How many hours (1-{}, default {}): ", cli::HOURLY_FORECAST_MAX_HOURS, cli::HOURLY_FORECAST_DEFAULT_HOURS);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(match input.trim().parse::<usize>() {
+        Ok(n) if (1..=cli::HOURLY_FORECAST_MAX_HOURS).contains(&n) => n,
+        _ => cli::HOURLY_FORECAST_DEFAULT_HOURS,
+    })
+}
 
-    // Show detailed forecast for next 24 hours
-    println!("\n--- Local Forecast for area near {} ---", station_name);
-    
-    if !forecast_data.properties.periods.is_empty() {
-        // Get the first period (usually "Today" or "Tonight")
-        let first_period = &forecast_data.properties.periods[0];
-        println!("\n{} ({}°{})", first_period.name, first_period.temperature, first_period.temperature_unit);
-        println!("{}", first_period.detailed_forecast);
-    } else {
-        println!("No forecast periods available for this location.");
+async fn fetch_and_display_hourly_forecast(forecast_hourly_url: &str, station_name: &str, hours: usize) -> Result<(), AppError> {
+    println!("\nFetching hourly forecast for area near {}...", station_name);
+
+    let periods = nws::fetch_hourly_forecast(forecast_hourly_url).await?;
+    if periods.is_empty() {
+        println!("No hourly forecast available for this location.");
+        return Ok(());
+    }
+    let periods = &periods[..hours.min(periods.len())];
+
+    let units = config::units();
+    println!("\n--- Hourly Forecast for area near {} (next {} hours) ---", station_name, periods.len());
+    println!("{:<12} {:>6} {:>6} {:<14} Forecast", "Time", "Temp", "Precip", "Wind");
+    for period in periods {
+        let time = period.start_time.map(|t| t.format("%a %l %p").to_string()).unwrap_or_default();
+        println!(
+            "{:<12} {:>6} {:>6} {:<14} {}",
+            time,
+            units.forecast_temperature(period.temperature, &period.temperature_unit),
+            period.probability_of_precipitation.map(|p| format!("{:.0}%", p)).unwrap_or_default(),
+            forecast_wind(period, units),
+            period.short_forecast
+        );
     }
 
-    Ok(())
+    let temperatures: Vec<f64> = periods.iter().map(|p| p.temperature).collect();
+    let min = temperatures.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = temperatures.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let unit = &periods[0].temperature_unit;
+    println!("\nTemperature trend ({} to {}):", units.forecast_temperature(min, unit), units.forecast_temperature(max, unit));
+    println!("{}", sparkline(&temperatures));
+    Ok(())
+}
+
+// "SW 5 to 10 mph" in the configured units
+fn forecast_wind(period: &nws::ForecastPeriod, units: UnitSystem) -> String {
+    match (&period.wind_direction, &period.wind_speed) {
+        (Some(dir), Some(speed)) => format!("{} {}", dir, units.forecast_wind_speed(speed)),
+        (None, Some(speed)) => units.forecast_wind_speed(speed),
+        _ => "N/A".to_string(),
+    }
+}
+
+// One block character per value, scaled between the series minimum and maximum
+fn sparkline(values: &[f64]) -> String {
+    const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let range = max - min;
+    values
+        .iter()
+        .map(|v| {
+            // A spread within floating-point rounding of the values counts as a flat
+            // series and is drawn as a mid-height line
+            if range <= 1e-9 * max.abs().max(1.0) {
+                BLOCKS[3]
+            } else {
+                BLOCKS[(((v - min) / range) * (BLOCKS.len() - 1) as f64).round() as usize]
+            }
+        })
+        .collect()
 }
 
 async fn display_external_links(address_str: &str, addr_lat: f64, addr_lon: f64, station_id: &str, station_name: &str, station_lat: Option<f64>, station_lon: Option<f64>) {
@@ -868,114 +1727,334 @@ async fn display_external_links(address_str: &str, addr_lat: f64, addr_lon: f64,
     }
 }
 
-async fn fetch_and_display_weather(station_id: &str, station_name: &str) -> Result<(), AppError> {
-    if station_id.starts_with("UNKNOWN_STATION") { // Covers UNKNOWN_STATION and UNKNOWN_STATION_API_EMPTY
-        eprintln!("Cannot fetch weather: Station ID is unknown or no station was found.");
-        return Ok(()); // Not an error in program flow, but an inability to proceed.
+// "23 min ago", "5 h 10 min ago", "3 days ago"
+fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    match minutes {
+        0 => "just now".to_string(),
+        1..=59 => format!("{} min ago", minutes),
+        60..=1439 if minutes % 60 == 0 => format!("{} h ago", minutes / 60),
+        60..=1439 => format!("{} h {} min ago", minutes / 60, minutes % 60),
+        1440..=2879 => "1 day ago".to_string(),
+        _ => format!("{} days ago", minutes / 1440),
     }
-    let url = format!(
-        "https://api.weather.gov/stations/{}/observations/latest", // Corrected URL
-        station_id
-    );
-    // println!("Fetching weather from: {}", url); // Debugging
+}
 
-    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+fn station_age_label(status: &nws::StationStatus) -> String {
+    status.age().map(format_age).unwrap_or_else(|| "no recent observation".to_string())
+}
 
-    if !response.status().is_success() {
-        let status = response.status();
-        let text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-        return Err(AppError::Api(format!(
-            "NWS Observations API request failed (Status: {}). URL: {}. Details: {}",
-            status, url, text
-        )));
+// Explains why the preferred or nearest station was passed over
+fn display_skipped_stations(choice: &nws::StationChoice) {
+    if choice.all_stale {
+        if !choice.skipped.is_empty() {
+            println!(
+                "No nearby station has a recent observation; using {} ({}).",
+                choice.station.station_name, choice.station.station_id
+            );
+        }
+        return;
+    }
+    for skipped in &choice.skipped {
+        println!(
+            "Station {} ({}) last reported {}; using {} ({}) instead.",
+            skipped.station.station_name,
+            skipped.station.station_id,
+            station_age_label(skipped),
+            choice.station.station_name,
+            choice.station.station_id
+        );
     }
+}
 
-    // Now that we know the request was successful, we can parse the JSON.
-    let weather_data_response: WeatherObservationResponse = response.json().await.map_err(|e| {
-        AppError::Api(format!("Failed to parse JSON response from NWS Observations API (URL: {}): {}", url, e))
-    })?;
+fn display_station_statuses(statuses: &[nws::StationStatus], current: Option<&str>) {
+    let max_age = nws::max_observation_age();
+    let units = config::units();
+    println!("{:>3}  {:<7} {:<32} {:>10}  Last observation", "#", "Station", "Name", "Distance");
+    for (i, status) in statuses.iter().enumerate() {
+        let station = &status.station;
+        let mut notes = Vec::new();
+        if status.age().is_none_or(|age| age > max_age) {
+            notes.push("stale");
+        }
+        if current.is_some_and(|id| id.eq_ignore_ascii_case(&station.station_id)) {
+            notes.push("chosen");
+        }
+        let notes = if notes.is_empty() { String::new() } else { format!(" ({})", notes.join(", ")) };
+        println!(
+            "{:>3}. {:<7} {:<32} {:>10}  {}{}",
+            i + 1,
+            station.station_id,
+            station.station_name.chars().take(32).collect::<String>(),
+            station.distance_km.map(|km| units.distance(km)).unwrap_or_else(|| "?".to_string()),
+            station_age_label(status),
+            notes
+        );
+    }
+}
+
+// Lists the closest stations and lets the user pick one for an address; the choice is saved
+async fn choose_station(book: &mut AddressBook, address_id: u32, lat: f64, lon: f64) -> Result<Option<NearestStation>, AppError> {
+    let point = nws::fetch_point_stations(lat, lon).await?
+        .ok_or_else(|| AppError::Api(format!("No weather observation stations found near Lat: {}, Lon: {}", lat, lon)))?;
+    let statuses = nws::station_statuses(&point, config::get().stations.candidates).await;
+    let current = book.get(address_id).and_then(|e| e.nws_station_id.clone());
+    println!("\nClosest observation stations:");
+    display_station_statuses(&statuses, current.as_deref());
+    print!("Choose a station (1-{}, Enter to keep the current one): ", statuses.len());
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let status = input.parse::<usize>().ok()
+        .and_then(|n| n.checked_sub(1))
+        .and_then(|i| statuses.get(i))
+        .ok_or_else(|| AppError::UserInput(format!("'{}' is not a station number from the list.", input)))?;
+    remember_station(book, address_id, |e| e.nws_station_id = Some(status.station.station_id.clone()));
+    Ok(Some(point.with_forecast(&status.station)))
+}
+
+// Formats a time in the station's time zone when NWS gives one, else in UTC
+fn station_time(time: chrono::DateTime<chrono::FixedOffset>, time_zone: Option<&str>, format: &str) -> String {
+    match time_zone.and_then(|tz| tz.parse::<chrono_tz::Tz>().ok()) {
+        Some(tz) => time.with_timezone(&tz).format(format).to_string(),
+        None => time.with_timezone(&chrono::Utc).format(format).to_string(),
+    }
+}
+
+// "Fri Oct 16 9:46 PM MDT (23 min ago)"
+fn format_observation_time(observed_at: chrono::DateTime<chrono::FixedOffset>, time_zone: Option<&str>) -> String {
+    let local = station_time(observed_at, time_zone, "%a %b %-d %-I:%M %p %Z");
+    format!("{} ({})", local, format_age(chrono::Utc::now() - observed_at.with_timezone(&chrono::Utc)))
+}
+
+// Suffix naming a reading's quality-control flag, e.g. "  [QC: verified]"
+fn qc_note(observation: &nws::Observation, field: &str) -> String {
+    match observation.quality(field) {
+        Some(qc) if qc.is_suspect() => format!("  [QC: {} - may be wrong]", qc),
+        Some(qc) => format!("  [QC: {}]", qc),
+        None => String::new(),
+    }
+}
 
-    if let Some(properties) = weather_data_response.properties {
+// Observation time line plus a warning when the station has not reported recently
+fn display_observation_time(observation: &nws::Observation, time_zone: Option<&str>) {
+    match observation.observed_at {
+        Some(observed_at) => println!("Observed: {}", format_observation_time(observed_at, time_zone)),
+        None => println!("Observed: unknown time"),
+    }
+    if observation.is_stale() {
         println!(
-            "\n--- Current Conditions at {} ({}) ---",
-            station_name,
-            station_id
+            "Warning: this observation is older than {} minutes; the station may be offline and conditions may have changed.",
+            nws::max_observation_age().num_minutes()
         );
+    }
+}
 
-        // Temperature (Celsius to Fahrenheit)
-        let temp_str = properties.temperature.as_ref()
-            .and_then(|t| t.value.map(|c| format!("{:.1} °F", c * 9.0/5.0 + 32.0)))
-            .unwrap_or_else(|| "N/A".to_string());
-        println!("Temperature: {}", temp_str);
-
-        // Heat Index (Celsius to Fahrenheit)
-        let heat_index_str = properties.heat_index.as_ref()
-            .and_then(|hi| hi.value.map(|c| format!("{:.1} °F", c * 9.0/5.0 + 32.0)))
-            .unwrap_or_else(|| "N/A".to_string());
-        println!("Heat Index: {}", heat_index_str);
-
-        // Conditions
-        println!("Conditions: {}", properties.text_description.as_deref().unwrap_or("N/A"));
-
-        // Wind (m/s to mph)
-        let wind_dir_str = properties.wind_direction.as_ref()
-            .and_then(|wd| wd.value.map(|v| format!("{:.0}", v)));
-        let wind_speed_str = properties.wind_speed.as_ref()
-            .and_then(|ws| ws.value.map(|mps| format!("{:.1} mph", mps * 2.23694)));
-        
-        let wind_str = match (wind_dir_str, wind_speed_str) {
-            (Some(dir), Some(speed)) => {
-                let gust_str = properties.wind_gust.as_ref()
-                    .and_then(|wg| wg.value.map(|mps_gust| format!(" (gusts to {:.1} mph)", mps_gust * 2.23694)))
-                    .unwrap_or_else(|| "".to_string());
-                format!("{} deg at {}{}", dir, speed, gust_str)
-            }
-            _ => "N/A".to_string(),
-        };
-        println!("Wind: {}", wind_str);
-        
-        // Humidity
-        let humidity_str = properties.relative_humidity.as_ref()
-            .and_then(|rh| rh.value.map(|v| format!("{:.1} %", v)))
-            .unwrap_or_else(|| "N/A".to_string());
-        println!("Humidity: {}", humidity_str);
-
-        // Ceiling (meters to feet)
-        // Prioritize "SKC" or "CLR" if present, otherwise use the first layer with a base value.
-        let ceiling_str = properties.cloud_layers.as_ref().and_then(|layers| {
-            if layers.iter().any(|layer| matches!(layer.amount.as_deref(), Some("SKC") | Some("CLR"))) {
-                Some("Clear (>12,000 ft)".to_string())
-            } else {
-                layers.iter().find_map(|layer| {
-                    layer.base.as_ref().and_then(|b| b.value.map(|meters| format!("{:.0} ft", meters * 3.28084)))
-                })
-            }
-        }).unwrap_or_else(|| "N/A".to_string());
-        println!("Ceiling: {}", ceiling_str);
+async fn fetch_and_display_weather(station_id: &str, station_name: &str, time_zone: Option<&str>) -> Result<(), AppError> {
+    if station_id.starts_with("UNKNOWN_STATION") { // Covers UNKNOWN_STATION and UNKNOWN_STATION_API_EMPTY
+        eprintln!("Cannot fetch weather: Station ID is unknown or no station was found.");
+        return Ok(()); // Not an error in program flow, but an inability to proceed.
+    }
+    let observation = nws::fetch_latest_observation(station_id).await?;
+
+    println!(
+        "\n--- Current Conditions at {} ({}) ---",
+        station_name,
+        station_id
+    );
+    display_observation_time(&observation, time_zone);
+
+    let units = config::units();
+
+    // Temperature
+    let temp_str = observation.temperature_c
+        .map(|c| units.temperature(c))
+        .unwrap_or_else(|| "N/A".to_string());
+    println!("Temperature: {}{}", temp_str, qc_note(&observation, "temperature_c"));
+
+    // Comfort metrics; NWS often leaves dew point, wind chill and heat index null
+    let comfort = observation.comfort();
+    let derived = |value: Option<meteo::Derived>, format: &dyn Fn(f64) -> String| match value {
+        Some(d) => format!("{} ({})", format(d.value), match d.source {
+            meteo::Source::Reported => "reported",
+            meteo::Source::Computed => "computed",
+        }),
+        None => "N/A".to_string(),
+    };
+    let temperature = |c: f64| units.temperature(c);
+    let reported_qc = |value: Option<meteo::Derived>, field: &str| match value {
+        Some(d) if d.source == meteo::Source::Reported => qc_note(&observation, field),
+        _ => String::new(),
+    };
+    println!("Dew Point: {}{}", derived(comfort.dew_point_c, &temperature), reported_qc(comfort.dew_point_c, "dewpoint_c"));
+    println!("Heat Index: {}{}", derived(comfort.heat_index_c, &temperature), reported_qc(comfort.heat_index_c, "heat_index_c"));
+    println!("Wind Chill: {}{}", derived(comfort.wind_chill_c, &temperature), reported_qc(comfort.wind_chill_c, "wind_chill_c"));
+    println!("Feels Like: {}", derived(comfort.feels_like_c, &temperature));
+    println!("Apparent Temperature: {}", derived(comfort.apparent_temperature_c, &temperature));
+    println!("Humidex: {}", derived(comfort.humidex, &|h| format!("{:.0}", h)));
+
+    // Conditions
+    println!("Conditions: {}", observation.text_description.as_deref().unwrap_or("N/A"));
+
+    // Wind
+    let wind_dir_str = observation.wind_direction_deg.map(|v| format!("{:.0}", v));
+    let wind_speed_str = observation.wind_speed_mps.map(|mps| units.speed(mps));
+
+    let wind_str = match (wind_dir_str, wind_speed_str) {
+        (Some(dir), Some(speed)) => {
+            let gust_str = observation.wind_gust_mps
+                .map(|mps_gust| format!(" (gusts to {})", units.speed(mps_gust)))
+                .unwrap_or_default();
+            format!("{} deg at {}{}", dir, speed, gust_str)
+        }
+        _ => "N/A".to_string(),
+    };
+    let wind_qc = ["wind_speed_mps", "wind_direction_deg", "wind_gust_mps"]
+        .iter()
+        .map(|field| qc_note(&observation, field))
+        .find(|note| note.contains("may be wrong"))
+        .unwrap_or_else(|| qc_note(&observation, "wind_speed_mps"));
+    println!("Wind: {}{}", wind_str, wind_qc);
+
+    // Humidity
+    let humidity_str = observation.relative_humidity
+        .map(|v| format!("{:.1} %", v))
+        .unwrap_or_else(|| "N/A".to_string());
+    println!("Humidity: {}{}", humidity_str, qc_note(&observation, "relative_humidity"));
+
+    // Ceiling: the lowest broken, overcast or obscured layer; FEW and SCT layers don't count
+    let layers = &observation.cloud_layers;
+    let ceiling_str = match observation.ceiling_m() {
+        Some(meters) => units.height(meters),
+        // Automated stations only report clouds up to 12,000 ft
+        None if layers.iter().any(|layer| matches!(layer.amount.as_deref(), Some("SKC") | Some("CLR"))) => {
+            format!("Clear (>{})", units.height(12_000.0 / METRES_TO_FEET))
+        }
+        None if !layers.is_empty() => "None (no broken or overcast layer)".to_string(),
+        None => "N/A".to_string(),
+    };
+    println!("Ceiling: {}", ceiling_str);
 
-        // Visibility (meters to miles)
-        let visibility_str = properties.visibility.as_ref()
-            .and_then(|v| v.value.map(|meters| format!("{:.1} mi", meters * 0.000621371)))
-            .unwrap_or_else(|| "N/A".to_string());
-        println!("Visibility: {}", visibility_str);
+    // Visibility
+    let visibility_str = observation.visibility_m
+        .map(|meters| units.visibility(meters))
+        .unwrap_or_else(|| "N/A".to_string());
+    println!("Visibility: {}{}", visibility_str, qc_note(&observation, "visibility_m"));
+    display_flight_category(observation.flight_category());
 
-        // Pressure (Pascals to inHg)
-        let pressure_str = properties.barometric_pressure.as_ref()
-            .and_then(|p| p.value.map(|pa| format!("{:.2} inHg", pa * 0.0002953)))
-            .unwrap_or_else(|| "N/A".to_string());
-        println!("Pressure: {}", pressure_str);
+    // Pressure
+    let pressure_str = observation.barometric_pressure_pa
+        .map(|pa| units.pressure(pa))
+        .unwrap_or_else(|| "N/A".to_string());
+    println!("Pressure: {}{}", pressure_str, qc_note(&observation, "barometric_pressure_pa"));
 
-    } else {
-        println!("Weather data properties are missing in the API response for station {}.", station_id);
+    Ok(())
+}
+
+async fn fetch_and_display_history(station_id: &str, station_name: &str, time_zone: Option<&str>, hours: u32) -> Result<(), AppError> {
+    let observations = nws::fetch_observation_history(station_id, hours).await?;
+    println!("\n--- Last {} hours at {} ({}) ---", hours, station_name, station_id);
+    let (Some(first), Some(last)) = (
+        observations.first().and_then(|o| o.observed_at),
+        observations.last().and_then(|o| o.observed_at),
+    ) else {
+        println!("No observations reported in the last {} hours.", hours);
+        return Ok(());
+    };
+    println!(
+        "{} observations, {} to {}",
+        observations.len(),
+        station_time(first, time_zone, "%a %-I:%M %p"),
+        station_time(last, time_zone, "%a %-I:%M %p %Z")
+    );
+
+    let units = config::units();
+    let summary = nws::HistorySummary::from_observations(&observations);
+    let extremes = |label: &str, extremes: Option<nws::Extremes>, format: &dyn Fn(f64) -> String| match extremes {
+        Some(e) => println!(
+            "\n{:<12} min {} ({}), max {} ({})",
+            label,
+            format(e.min),
+            station_time(e.min_at, time_zone, "%a %-I:%M %p"),
+            format(e.max),
+            station_time(e.max_at, time_zone, "%a %-I:%M %p")
+        ),
+        None => println!("\n{:<12} not reported", label),
+    };
+    let chart = |field: fn(&nws::Observation) -> Option<f64>| {
+        let values = hourly_averages(&observations, field);
+        if !values.is_empty() {
+            println!("{}", sparkline(&values));
+        }
+    };
+
+    extremes("Temperature", summary.temperature_c, &|c| units.temperature(c));
+    chart(|o| o.temperature_c);
+    extremes("Wind", summary.wind_speed_mps, &|mps| units.speed(mps));
+    if let Some(gusts) = summary.wind_gust_mps {
+        println!("{:<12} peak gust {} ({})", "", units.speed(gusts.max), station_time(gusts.max_at, time_zone, "%a %-I:%M %p"));
     }
+    chart(|o| o.wind_speed_mps);
+    extremes("Humidity", summary.relative_humidity, &|rh| format!("{:.0} %", rh));
+    chart(|o| o.relative_humidity);
+    extremes("Pressure", summary.barometric_pressure_pa, &|pa| units.pressure(pa));
+    chart(|o| o.barometric_pressure_pa);
+
+    match summary.pressure_tendency {
+        Some(tendency) => {
+            let sign = if tendency.change_pa < 0.0 { "-" } else { "+" };
+            let trend = match tendency.trend {
+                nws::PressureTrend::Rising => "rising",
+                nws::PressureTrend::Steady => "steady",
+                nws::PressureTrend::Falling => "falling",
+            };
+            println!(
+                "\n3-hour pressure tendency: {} ({}{} over {:.1} h)",
+                trend,
+                sign,
+                units.pressure(tendency.change_pa.abs()),
+                tendency.over_hours
+            );
+        }
+        None => println!("\n3-hour pressure tendency: not enough pressure readings"),
+    }
+    println!("Charts show one column per hour, oldest on the left.");
     Ok(())
 }
 
+// Averages a reading over each hour of the history; hours without a reading are skipped
+fn hourly_averages(observations: &[nws::Observation], field: fn(&nws::Observation) -> Option<f64>) -> Vec<f64> {
+    let mut buckets: Vec<(i64, f64, usize)> = Vec::new();
+    for (hour, value) in observations.iter().filter_map(|o| Some((o.observed_at?.timestamp() / 3600, field(o)?))) {
+        match buckets.last_mut() {
+            Some((h, sum, count)) if *h == hour => {
+                *sum += value;
+                *count += 1;
+            }
+            _ => buckets.push((hour, value, 1)),
+        }
+    }
+    buckets.into_iter().map(|(_, sum, count)| sum / count as f64).collect()
+}
+
+// Asks how many hours of observation history to show
+fn prompt_history_hours() -> Result<u32, AppError> {
+    print!("How many hours (1-{}, default {}): ", nws::MAX_HISTORY_HOURS, cli::HISTORY_DEFAULT_HOURS);
+    io::stdout().flush()?;
+    let mut input = String::new();
+    io::stdin().read_line(&mut input)?;
+    Ok(match input.trim().parse::<u32>() {
+        Ok(n) if (1..=nws::MAX_HISTORY_HOURS).contains(&n) => n,
+        _ => cli::HISTORY_DEFAULT_HOURS,
+    })
+}
+
 async fn airport_search_menu() -> Result<(), AppError> {
     use std::io::Write;
-    let us_states = [
-        "AL","AK","AZ","AR","CA","CO","CT","DE","FL","GA","HI","ID","IL","IN","IA","KS","KY","LA","ME","MD","MA","MI","MN","MS","MO","MT","NE","NV","NH","NJ","NM","NY","NC","ND","OH","OK","OR","PA","RI","SC","SD","TN","TX","UT","VT","VA","WA","WV","WI","WY"
-    ];
     let us_only;
     let passenger_only;
     // Ask user if they want US states only
@@ -1013,6 +2092,21 @@ async fn airport_search_menu() -> Result<(), AppError> {
             println!("Please enter Y for passenger airports only, or N for all airports.");
         }
     }
+    // Optionally filter by current flight category
+    let category = loop {
+        print!("Filter by flight category (VFR, MVFR, IFR or LIFR), or press Enter for any: ");
+        io::stdout().flush()?;
+        let mut category_input = String::new();
+        io::stdin().read_line(&mut category_input)?;
+        let category_input = category_input.trim();
+        if category_input.is_empty() {
+            break None;
+        }
+        match category_input.parse::<FlightCategory>() {
+            Ok(category) => break Some(category),
+            Err(e) => println!("{}", e),
+        }
+    };
     loop {
         println!("\n--- Airport Search ---");
         println!("Search by airport code, state, municipality, or name. Use * as a wildcard: 'Rome*' for names starting with Rome, '*Rome' for names ending with Rome, '*Rome*' for names containing Rome, or 'Rome' for exact match.");
@@ -1024,18 +2118,26 @@ async fn airport_search_menu() -> Result<(), AppError> {
         if search.is_empty() {
             break;
         }
-        let mut results = airports::search_airports(search);
-        if us_only {
-            results.retain(|a| {
-                let region = a.iso_region.trim();
-                region.starts_with("US-") && us_states.contains(&region[3..].to_uppercase().as_str())
-            });
-        }
-        if passenger_only {
-            results.retain(|a| a.scheduled_service == "yes");
+        let mut results = filtered_airport_search(search, &AirportFilter { us_only, scheduled_only: passenger_only });
+        if let Some(category) = category {
+            println!("Checking current METARs...");
+            results = match retain_flight_category(results, category).await {
+                Ok(results) => results,
+                Err(e @ AppError::UserInput(_)) => {
+                    eprintln!("{}", e);
+                    continue;
+                }
+                Err(e) => {
+                    eprintln!("Error fetching METARs: {}", e);
+                    continue;
+                }
+            };
         }
         if results.is_empty() {
-            println!("No airports found matching '{}'.", search);
+            match category {
+                Some(category) => println!("No {} airports found matching '{}'.", category, search),
+                None => println!("No airports found matching '{}'.", search),
+            }
             continue;
         }
         'result_loop: loop {
@@ -1054,22 +2156,27 @@ async fn airport_search_menu() -> Result<(), AppError> {
                 return Ok(());
             } else if let Ok(idx) = sel.parse::<usize>() {
                 if idx > 0 && idx <= results.len() {
-                    show_airport_details(&results[idx - 1]).await?;
+                    show_airport_details(results[idx - 1]).await?;
                     // After showing details, offer to select another, start over, or return
-                    loop {
-                        println!("\nOptions:");
-                        println!("1. Select another airport from the filtered list");
-                        println!("2. Start search over again");
-                        println!("3. Return to main menu");
-                        print!("Enter your choice: ");
-                        io::stdout().flush()?;
-                        let mut opt = String::new();
-                        io::stdin().read_line(&mut opt)?;
-                        match opt.trim() {
-                            "1" => continue 'result_loop, // re-show the list and prompt again
-                            "2" => break 'result_loop,    // start search over
-                            _ => return Ok(()), // return to main menu
+                    println!("\nOptions:");
+                    println!("1. Select another airport from the filtered list");
+                    println!("2. Start search over again");
+                    println!("3. Hourly forecast for this airport");
+                    println!("4. Return to main menu");
+                    print!("Enter your choice: ");
+                    io::stdout().flush()?;
+                    let mut opt = String::new();
+                    io::stdin().read_line(&mut opt)?;
+                    match opt.trim() {
+                        "1" => continue 'result_loop, // re-show the list and prompt again
+                        "2" => break 'result_loop,    // start search over
+                        "3" => {
+                            if let Err(e) = airport_hourly_forecast(results[idx - 1]).await {
+                                eprintln!("Error fetching hourly forecast: {}", e);
+                            }
+                            continue 'result_loop;
                         }
+                        _ => return Ok(()), // return to main menu
                     }
                 } else {
                     println!("Invalid selection.");
@@ -1082,54 +2189,79 @@ async fn airport_search_menu() -> Result<(), AppError> {
     Ok(())
 }
 
+async fn airport_hourly_forecast(airport: &airports::Airport) -> Result<(), AppError> {
+    let (Ok(lat), Ok(lon)) = (airport.latitude_deg.parse::<f64>(), airport.longitude_deg.parse::<f64>()) else {
+        return Err(AppError::Api(format!("No coordinates for airport {}", airport.ident)));
+    };
+    let station = find_nearest_station(lat, lon).await?
+        .ok_or_else(|| AppError::Api(format!("No NWS forecast available for {}", airport.ident)))?;
+    let url = station.forecast_hourly_url
+        .ok_or_else(|| AppError::Api(format!("No NWS hourly forecast available for {}", airport.ident)))?;
+    let hours = prompt_hourly_forecast_hours()?;
+    fetch_and_display_hourly_forecast(&url, &station.station_name, hours).await
+}
+
 async fn show_airport_details(airport: &airports::Airport) -> Result<(), AppError> {
     println!("\nLatitude: {}, Longitude: {}", airport.latitude_deg, airport.longitude_deg);
+    display_airport_place(airport);
     println!("\nAirport Weather Conditions:");
     println!("Station ID: {}", airport.ident);
     println!("Labeled as: {}", airport.name);
     println!("Station Name: {}", airport.municipality);
     let lat = airport.latitude_deg.parse::<f64>().ok();
     let lon = airport.longitude_deg.parse::<f64>().ok();
-    let mut temp = None;
-    let mut wind_speed = None;
-    let mut wind_dir = None;
-    let mut conditions = None;
-    let mut forecast = None;
-    let mut found_weather = false;
+    let mut observation = None;
+    let mut forecast = Vec::new();
+    let mut active_alerts = Vec::new();
     let mut station_lat_lon: Option<(Option<f64>, Option<f64>)> = None;
+    let mut station_time_zone = None;
     if let (Some(lat), Some(lon)) = (lat, lon) {
-        if let Ok(Some((station_id, station_name, station_lat, station_lon, forecast_url))) = find_nearest_station(lat, lon).await {
+        if let Ok(Some(choice)) = nws::select_station(lat, lon, None).await {
+            display_skipped_stations(&choice);
+            let station = choice.station;
             // Store the station's coordinates
-            station_lat_lon = Some((station_lat, station_lon));
-            // Fetch current conditions
-            if let Ok(response) = HTTP_CLIENT.get(&format!("https://api.weather.gov/stations/{}/observations/latest", station_id)).send().await {
-                if let Ok(obs) = response.json::<WeatherObservationResponse>().await {
-                    if let Some(props) = obs.properties {
-                        temp = props.temperature.and_then(|t| t.value);
-                        wind_speed = props.wind_speed.and_then(|w| w.value);
-                        wind_dir = props.wind_direction.and_then(|w| w.value);
-                        conditions = props.text_description;
-                        found_weather = temp.is_some() || wind_speed.is_some() || wind_dir.is_some() || conditions.is_some();
-                    }
-                }
-            }
+            station_lat_lon = Some((station.lat, station.lon));
+            station_time_zone = station.time_zone.clone();
+            // Current conditions from the first station with a recent observation
+            observation = choice.observation;
             // Fetch forecast
-            if let Ok(response) = HTTP_CLIENT.get(&forecast_url).send().await {
-                if let Ok(forecast_data) = response.json::<ForecastResponse>().await {
-                    if let Some(first) = forecast_data.properties.periods.first() {
-                        forecast = Some(first.detailed_forecast.clone());
-                    }
-                }
-            }
-            println!("\nStation ID: {}", station_id);
+            forecast = nws::fetch_forecast(&station.forecast_url).await.unwrap_or_default();
+            active_alerts = alerts::fetch_active_alerts(lat, lon).await.unwrap_or_default();
+            println!("\nStation ID: {}", station.station_id);
             println!("Labeled as: {}", airport.name);
-            println!("Station Name: {}", station_name);
+            println!("Station Name: {}", station.station_name);
         }
     }
+    let found_weather = observation.as_ref().is_some_and(|obs| {
+        obs.temperature_c.is_some() || obs.wind_speed_mps.is_some() || obs.wind_direction_deg.is_some() || obs.text_description.is_some()
+    });
+    if let Some(obs) = observation.as_ref().filter(|_| found_weather) {
+        display_observation_time(obs, station_time_zone.as_deref());
+    }
+    let temp = observation.as_ref().and_then(|obs| obs.temperature_c);
+    let wind_speed = observation.as_ref().and_then(|obs| obs.wind_speed_mps);
+    let wind_dir = observation.as_ref().and_then(|obs| obs.wind_direction_deg);
+    let (metar, taf) = match airport.icao_code() {
+        Some(icao) => fetch_aviation_weather(icao).await,
+        None => (Ok(None), Ok(None)),
+    };
+    // The airport's own METAR is a better source than the nearest NWS station
+    let flight_category = match &metar {
+        Ok(Some(metar)) => metar.flight_category(),
+        _ => observation.as_ref().and_then(|obs| obs.flight_category()),
+    };
+    let airport_metar = metar.as_ref().ok().and_then(Option::as_ref);
+    let runway_winds = runway_wind_analysis(airport, airport_metar, observation.as_ref());
+    let altitudes = field_altitudes(airport, airport_metar, observation.as_ref());
+    let conditions = observation.and_then(|obs| obs.text_description);
+    if found_weather || flight_category.is_some() {
+        display_flight_category(flight_category);
+    }
     if found_weather {
         println!("Current Conditions:");
-        println!("Temperature: {}", temp.map(|t| format!("{:.1} °F", t * 9.0/5.0 + 32.0)).unwrap_or("None".to_string()));
-        println!("Wind Speed: {}", wind_speed.map(|w| format!("{:.1} mph", w * 2.23694)).unwrap_or("None".to_string()));
+        let units = config::units();
+        println!("Temperature: {}", temp.map(|t| units.temperature(t)).unwrap_or("None".to_string()));
+        println!("Wind Speed: {}", wind_speed.map(|w| units.speed(w)).unwrap_or("None".to_string()));
         println!("Wind Direction: {}", wind_dir.map(|w| format!("{:.0}", w)).unwrap_or("None".to_string()));
         if let Some(cond) = &conditions {
             println!("Current Conditions: {}", cond);
@@ -1137,12 +2269,23 @@ async fn show_airport_details(airport: &airports::Airport) -> Result<(), AppErro
         else {
             println!("N/A");
         }
-        if let Some(forecast) = &forecast {
-            println!("Forecast: {}", forecast);
+        display_alerts(&active_alerts);
+        if !forecast.is_empty() {
+            println!("\nForecast:");
+            display_forecast_periods(&forecast, cli::FORECAST_PERIODS, ForecastView::Table);
         }
     } else {
         println!("No weather data available for this airport (may be international, a military or remote field).\n");
     }
+    display_frequencies(airport);
+    if let (Some(lat), Some(lon)) = (lat, lon) {
+        display_nearby_navaids(lat, lon);
+    }
+    display_field_altitudes(altitudes.as_ref());
+    display_runways(airport, runway_winds.as_ref());
+    if let Some(icao) = airport.icao_code() {
+        display_aviation_weather(icao, metar, taf);
+    }
     println!("\n");
 
     if let (Some(lat), Some(lon)) = (lat, lon) {
@@ -1170,36 +2313,33 @@ async fn show_airport_details(airport: &airports::Airport) -> Result<(), AppErro
             }
         }
         // Zillow links for US states only (always print after other output)
-        let us_states = [
-            "AL","AK","AZ","AR","CA","CO","CT","DE","FL","GA","HI","ID","IL","IN","IA","KS","KY","LA","ME","MD","MA","MI","MN","MS","MO","MT","NE","NV","NH","NJ","NM","NY","NC","ND","OH","OK","OR","PA","RI","SC","SD","TN","TX","UT","VT","VA","WA","WV","WI","WY"
-        ];
         let state_name_to_abbr = [
             ("Alabama", "AL"), ("Alaska", "AK"), ("Arizona", "AZ"), ("Arkansas", "AR"), ("California", "CA"), ("Colorado", "CO"), ("Connecticut", "CT"), ("Delaware", "DE"), ("Florida", "FL"), ("Georgia", "GA"), ("Hawaii", "HI"), ("Idaho", "ID"), ("Illinois", "IL"), ("Indiana", "IN"), ("Iowa", "IA"), ("Kansas", "KS"), ("Kentucky", "KY"), ("Louisiana", "LA"), ("Maine", "ME"), ("Maryland", "MD"), ("Massachusetts", "MA"), ("Michigan", "MI"), ("Minnesota", "MN"), ("Mississippi", "MS"), ("Missouri", "MO"), ("Montana", "MT"), ("Nebraska", "NE"), ("Nevada", "NV"), ("New Hampshire", "NH"), ("New Jersey", "NJ"), ("New Mexico", "NM"), ("New York", "NY"), ("North Carolina", "NC"), ("North Dakota", "ND"), ("Ohio", "OH"), ("Oklahoma", "OK"), ("Oregon", "OR"), ("Pennsylvania", "PA"), ("Rhode Island", "RI"), ("South Carolina", "SC"), ("South Dakota", "SD"), ("Tennessee", "TN"), ("Texas", "TX"), ("Utah", "UT"), ("Vermont", "VT"), ("Virginia", "VA"), ("Washington", "WA"), ("West Virginia", "WV"), ("Wisconsin", "WI"), ("Wyoming", "WY")
         ];
         let mut zillow_printed = false;
         // Await both lookups before printing
-        let county_state = get_county_state_from_latlon(lat, lon).await;
-        let city_state = get_city_state_from_latlon(lat, lon).await;
+        let county_state = geocode::get_county_state_from_latlon(lat, lon).await;
+        let city_state = geocode::get_city_state_from_latlon(lat, lon).await;
         // Remove debug output for Zillow troubleshooting
         if let Some(county_state) = county_state {
-            if let Some(state_abbr) = county_state.split('-').last() {
+            if let Some(state_abbr) = county_state.split('-').next_back() {
                 let state_abbr = state_abbr.trim();
                 let state_abbr = state_name_to_abbr.iter().find_map(|(name, abbr)| {
                     if state_abbr.eq_ignore_ascii_case(name) { Some(*abbr) } else { None }
                 }).unwrap_or(state_abbr);
-                if us_states.contains(&state_abbr) {
+                if airports::US_STATES.contains(&state_abbr) {
                     println!("Zillow (county): https://www.zillow.com/homes/for_sale/{}", county_state.replace(' ', "+"));
                     zillow_printed = true;
                 }
             }
         }
         if let Some(city_state) = city_state {
-            if let Some(state_abbr) = city_state.split('-').last() {
+            if let Some(state_abbr) = city_state.split('-').next_back() {
                 let state_abbr = state_abbr.trim();
                 let state_abbr = state_name_to_abbr.iter().find_map(|(name, abbr)| {
                     if state_abbr.eq_ignore_ascii_case(name) { Some(*abbr) } else { None }
                 }).unwrap_or(state_abbr);
-                if us_states.contains(&state_abbr) {
+                if airports::US_STATES.contains(&state_abbr) {
                     println!("Zillow (city): https://www.zillow.com/homes/for_sale/{}", city_state.replace(' ', "+"));
                     zillow_printed = true;
                 }
@@ -1212,44 +2352,280 @@ async fn show_airport_details(airport: &airports::Airport) -> Result<(), AppErro
     Ok(())
 }
 
-use serde_json::Value;
+// Region and country by name, falling back to the raw codes when the datasets are missing
+fn display_airport_place(airport: &airports::Airport) {
+    let region = airport.region().map(|r| format!("{} ({})", r.name, r.code)).unwrap_or_else(|| airport.iso_region.clone());
+    let country = airport.country().map(|c| format!("{} ({})", c.name, c.code)).unwrap_or_else(|| airport.iso_country.clone());
+    println!("Region: {}", region);
+    println!("Country: {}", country);
+}
 
-async fn get_county_state_from_latlon(lat: f64, lon: f64) -> Option<String> {
-    let url = format!("https://geo.fcc.gov/api/census/block/find?latitude={}&longitude={}&format=json", lat, lon);
-    match HTTP_CLIENT.get(&url).send().await {
-        Ok(resp) => {
-            if let Ok(json) = resp.json::<Value>().await {
-                let county = json.get("County").and_then(|c| c.get("name")).and_then(|v| v.as_str());
-                let state = json.get("State").and_then(|s| s.get("name")).and_then(|v| v.as_str());
-                if let (Some(county), Some(state)) = (county, state) {
-                    return Some(format!("{}-{}", county, state));
-                }
-            }
+fn display_frequencies(airport: &airports::Airport) {
+    let frequencies: Vec<_> = airport
+        .frequencies()
+        .iter()
+        .filter(|f| KEY_FREQUENCY_TYPES.iter().any(|t| f.type_.eq_ignore_ascii_case(t)))
+        .collect();
+    if frequencies.is_empty() {
+        return;
+    }
+    println!("\n--- Frequencies ---");
+    for frequency in frequencies {
+        let mhz = frequency.frequency_mhz.map(|f| format!("{:.3} MHz", f)).unwrap_or_else(|| "N/A".to_string());
+        let description = if frequency.description.is_empty() || frequency.description == frequency.type_ {
+            String::new()
+        } else {
+            format!("  ({})", frequency.description)
+        };
+        println!("{:<5} {}{}", frequency.type_, mhz, description);
+    }
+}
+
+fn display_nearby_navaids(lat: f64, lon: f64) {
+    let navaids = ourairports::navaids_near(lat, lon, NEARBY_NAVAID_RADIUS_KM);
+    if navaids.is_empty() {
+        return;
+    }
+    let units = config::units();
+    println!("\n--- Nearby Navaids ---");
+    for nearby in navaids.iter().take(NEARBY_NAVAID_LIMIT) {
+        let navaid = nearby.navaid;
+        println!(
+            "{:>10} {:>3} ({:03.0}°)  {} {} {}  {}",
+            units.distance(nearby.distance_km),
+            compass_point(nearby.bearing_deg),
+            nearby.bearing_deg,
+            navaid.ident,
+            navaid.type_,
+            navaid.frequency_label().unwrap_or_default(),
+            navaid.name
+        );
+    }
+}
+
+// Pressure and density altitude from the field elevation and the METAR's temperature
+// and altimeter, else the NWS observation's
+fn field_altitudes(
+    airport: &airports::Airport,
+    metar: Option<&Metar>,
+    observation: Option<&nws::Observation>,
+) -> Option<FieldAltitudes> {
+    let elevation_ft = airport.elevation_ft.trim().parse::<f64>().ok()?;
+    let temperature_c = metar
+        .and_then(|m| m.temperature_c)
+        .map(f64::from)
+        .or_else(|| observation.and_then(|obs| obs.temperature_c))?;
+    let altimeter_inhg = metar
+        .and_then(|m| m.altimeter)
+        .map(|a| a.inhg)
+        .or_else(|| observation.and_then(|obs| obs.barometric_pressure_pa).map(|pa| pa * PA_TO_INHG))?;
+    Some(FieldAltitudes::new(elevation_ft, altimeter_inhg, temperature_c, altitude::high_density_altitude_ft()))
+}
+
+fn display_field_altitudes(altitudes: Option<&FieldAltitudes>) {
+    let Some(a) = altitudes else {
+        return;
+    };
+    let units = config::units();
+    println!("\n--- Field Altitudes ---");
+    println!("Field Elevation: {}", units.height(a.elevation_ft / METRES_TO_FEET));
+    println!(
+        "Altimeter: {}  Temperature: {} (ISA {})",
+        units.pressure(a.altimeter_inhg / PA_TO_INHG),
+        units.temperature(a.temperature_c),
+        units.temperature_difference(a.isa_deviation_c)
+    );
+    println!("Pressure Altitude: {}", units.height(a.pressure_altitude_ft / METRES_TO_FEET));
+    println!("Density Altitude: {}", units.height(a.density_altitude_ft / METRES_TO_FEET));
+    if a.high_density_altitude {
+        println!(
+            "WARNING: High density altitude (at or above {}). Expect longer take-off rolls and reduced climb performance.",
+            units.height(altitude::high_density_altitude_ft() / METRES_TO_FEET)
+        );
+    }
+}
+
+// Runway wind components from the airport's METAR wind, else the NWS observation.
+// `None` without runways or a wind with a direction (calm or variable).
+fn runway_wind_analysis(
+    airport: &airports::Airport,
+    metar: Option<&Metar>,
+    observation: Option<&nws::Observation>,
+) -> Option<RunwayWindAnalysis> {
+    let runways = airport.runways();
+    if runways.is_empty() {
+        return None;
+    }
+    let (direction, speed_kt, gust_kt) = match metar.and_then(|m| m.wind) {
+        Some(wind) => (f64::from(wind.direction_deg?), f64::from(wind.speed_kt), wind.gust_kt.map(f64::from)),
+        None => {
+            let obs = observation?;
+            (obs.wind_direction_deg?, obs.wind_speed_mps? * MPS_TO_KNOTS, obs.wind_gust_mps.map(|g| g * MPS_TO_KNOTS))
         }
-        Err(_) => {}
+    };
+    if speed_kt < 0.5 {
+        return None;
     }
-    None
+    Some(RunwayWindAnalysis::new(runways, direction, speed_kt, gust_kt, runways::crosswind_limit_kt()))
 }
 
-async fn get_city_state_from_latlon(lat: f64, lon: f64) -> Option<String> {
-    let url = format!("https://nominatim.openstreetmap.org/reverse?format=jsonv2&lat={}&lon={}", lat, lon);
-    match HTTP_CLIENT.get(&url)
-        .header("User-Agent", APP_USER_AGENT)
-        .send().await {
-        Ok(resp) => {
-            if let Ok(json) = resp.json::<Value>().await {
-                if let Some(addr) = json.get("address") {
-                    let city = addr.get("city").or_else(|| addr.get("town")).or_else(|| addr.get("village")).and_then(|v| v.as_str());
-                    let state = addr.get("state").and_then(|v| v.as_str());
-                    if let (Some(city), Some(state)) = (city, state) {
-                        return Some(format!("{}-{}", city, state));
-                    }
-                }
-            }
+fn display_runways(airport: &airports::Airport, analysis: Option<&RunwayWindAnalysis>) {
+    let runways = airport.runways();
+    if runways.is_empty() {
+        return;
+    }
+    let units = config::units();
+    println!("\n--- Runways ---");
+    for runway in runways {
+        let size = match (runway.length_ft, runway.width_ft) {
+            (Some(length), Some(width)) => format!("{} x {}", units.height(length / METRES_TO_FEET), units.height(width / METRES_TO_FEET)),
+            (Some(length), None) => units.height(length / METRES_TO_FEET),
+            _ => "size unknown".to_string(),
+        };
+        let headings: Vec<String> = runway.ends().iter().map(|end| format!("{} {:03.0}°", end.ident, end.heading_deg)).collect();
+        let mut notes = Vec::new();
+        if !runway.surface.is_empty() {
+            notes.push(runway.surface.clone());
+        }
+        notes.push(if runway.lighted { "lighted".to_string() } else { "unlighted".to_string() });
+        if runway.closed {
+            notes.push("CLOSED".to_string());
         }
-        Err(_) => {}
+        println!("{:<9} {}  {}  (headings {})", runway.name(), size, notes.join(", "), headings.join(", "));
+    }
+    let Some(analysis) = analysis else {
+        println!("Wind components: N/A (no wind direction reported, or calm)");
+        return;
+    };
+    let gust = analysis.gust_kt.map(|g| format!(" gusting {:.0}", g)).unwrap_or_default();
+    println!(
+        "\nWind {:03.0}° at {:.0}{} kt (crosswind limit {:.0} kt):",
+        analysis.wind_direction_deg, analysis.wind_speed_kt, gust, analysis.crosswind_limit_kt
+    );
+    for end in &analysis.ends {
+        // Rounds to "headwind 0.0" rather than "tailwind 0.0" for a direct crosswind
+        let wind_along = if end.headwind_kt > -0.05 {
+            format!("headwind {:>4.1} kt", end.headwind_kt)
+        } else {
+            format!("tailwind {:>4.1} kt", -end.headwind_kt)
+        };
+        let gust_cross = end.gust_crosswind_kt.map(|g| format!(" (gusts {:.1})", g)).unwrap_or_default();
+        let side = if end.from_right { "from right" } else { "from left" };
+        let flag = if end.exceeds_limit { "  ** EXCEEDS CROSSWIND LIMIT **" } else { "" };
+        println!("  Runway {:<4} {}  crosswind {:>4.1} kt {}{}{}", end.end.ident, wind_along, end.crosswind_kt, side, gust_cross, flag);
+    }
+    match &analysis.recommended {
+        Some(ident) => println!("Recommended runway: {}", ident),
+        None if analysis.ends.iter().all(|end| end.exceeds_limit) => {
+            println!("No runway is within the crosswind limit.")
+        }
+        None => println!("Recommended runway: none (no runway within the crosswind limit has a headwind)"),
+    }
+}
+
+fn display_flight_category(category: Option<FlightCategory>) {
+    match category {
+        Some(category) => println!("Flight Category: [{}] {}", category, category.describe()),
+        None => println!("Flight Category: N/A"),
+    }
+}
+
+async fn fetch_aviation_weather(icao: &str) -> (Result<Option<Metar>, AppError>, Result<Option<Taf>, AppError>) {
+    tokio::join!(aviation_weather::fetch_metar(icao), aviation_weather::fetch_taf(icao))
+}
+
+// METAR and TAF are best-effort: many small fields issue neither
+fn display_aviation_weather(icao: &str, metar: Result<Option<Metar>, AppError>, taf: Result<Option<Taf>, AppError>) {
+    match metar {
+        Ok(Some(metar)) => display_metar(&metar),
+        Ok(None) => println!("\nNo METAR available for {}.", icao),
+        Err(e) => println!("\nMETAR unavailable for {}: {}", icao, e),
+    }
+    match taf {
+        Ok(Some(taf)) => display_taf(&taf),
+        Ok(None) => println!("No TAF issued for {}.", icao),
+        Err(e) => println!("TAF unavailable for {}: {}", icao, e),
+    }
+}
+
+fn display_metar(metar: &Metar) {
+    let units = config::units();
+    println!("\n--- METAR ---");
+    println!("{}", metar.raw);
+    if let Some(issued) = metar.issued {
+        let mut flags = String::new();
+        if metar.automated {
+            flags.push_str(" (automated)");
+        }
+        if metar.corrected {
+            flags.push_str(" (corrected)");
+        }
+        println!("Issued: {}{}", issued, flags);
+    }
+    if let Some(wind) = &metar.wind {
+        println!("Wind: {}", wind);
+    }
+    if let Some(visibility) = &metar.visibility {
+        println!("Visibility: {}", visibility);
+    }
+    for rvr in &metar.runway_visual_range {
+        println!("Runway Visual Range: {}", rvr);
+    }
+    if !metar.weather.is_empty() {
+        let weather: Vec<String> = metar.weather.iter().map(|w| w.to_string()).collect();
+        println!("Weather: {}", weather.join(", "));
+    }
+    for cloud in &metar.clouds {
+        println!("Clouds: {}", cloud);
+    }
+    match metar.ceiling_ft() {
+        Some(ceiling) => println!("Ceiling: {}", units.height(f64::from(ceiling) / METRES_TO_FEET)),
+        None => println!("Ceiling: none"),
+    }
+    if let Some(temperature) = metar.temperature_c {
+        let dewpoint = metar.dewpoint_c.map(|d| units.temperature(d as f64)).unwrap_or("None".to_string());
+        println!("Temperature: {}  Dew Point: {}", units.temperature(temperature as f64), dewpoint);
+    }
+    if let Some(altimeter) = &metar.altimeter {
+        println!("Altimeter: {}", altimeter);
+    }
+    if let Some(trend) = &metar.trend {
+        println!("Trend: {}", trend);
+    }
+    if let Some(remarks) = &metar.remarks {
+        println!("Remarks: {}", remarks);
+    }
+    if !metar.unparsed.is_empty() {
+        println!("Not decoded: {}", metar.unparsed.join(" "));
+    }
+}
+
+fn display_taf(taf: &Taf) {
+    println!("\n--- TAF ---");
+    println!("{}", taf.raw);
+    if let Some(issued) = taf.issued {
+        println!("Issued: {}{}", issued, if taf.amended { " (amended)" } else { "" });
+    }
+    if let Some(valid) = taf.valid {
+        println!("Valid: {}", valid);
+    }
+    for period in &taf.periods {
+        let mut parts = Vec::new();
+        if let Some(wind) = &period.wind {
+            parts.push(format!("wind {}", wind));
+        }
+        if let Some(visibility) = &period.visibility {
+            parts.push(format!("visibility {}", visibility));
+        }
+        parts.extend(period.weather.iter().map(|w| w.to_string()));
+        parts.extend(period.clouds.iter().map(|c| c.to_string()));
+        parts.extend(period.unparsed.iter().map(|u| format!("not decoded: {}", u)));
+        let valid = period.valid.map(|v| format!(" ({})", v)).unwrap_or_default();
+        println!("  {}{}: {}", period.change, valid, parts.join("; "));
+    }
+    if let Some(remarks) = &taf.remarks {
+        println!("Remarks: {}", remarks);
     }
-    None
 }
 
 // --- Earthquake menu and logic ---
@@ -1288,97 +2664,49 @@ async fn earthquake_menu() -> Result<(), AppError> {
     io::stdin().read_line(&mut time_choice)?;
     let time_choice = time_choice.trim();
     if time_choice.is_empty() { return Ok(()); }
-    let (url, filter_hours) = match time_choice {
-        "1" => ("https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/all_day.geojson", 24),
-        "2" => ("https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/all_day.geojson", 48),
-        "3" => ("https://earthquake.usgs.gov/earthquakes/feed/v1.0/summary/all_week.geojson", 168),
+    let filter_hours = match time_choice {
+        "1" => 24,
+        "2" => 48,
+        "3" => 168,
         _ => {
             println!("Invalid choice.");
             return Ok(());
         }
     };
     println!("\nFetching earthquake data from USGS...");
-    match fetch_and_display_earthquakes_filtered(url, mag_val, filter_hours).await {
+    match fetch_and_display_earthquakes_filtered(mag_val, filter_hours).await {
         Ok(_) => {},
         Err(e) => eprintln!("Error fetching earthquake data: {}", e),
     }
     Ok(())
 }
 
-async fn fetch_and_display_earthquakes_filtered(url: &str, min_mag: f64, max_age_hours: u64) -> Result<(), AppError> {
-    let resp = HTTP_CLIENT.get(url).send().await?.error_for_status()?;
-    let data: EarthquakeFeatureCollection = resp.json().await?;
-    if data.features.is_empty() {
+async fn fetch_and_display_earthquakes_filtered(min_mag: f64, max_age_hours: u64) -> Result<(), AppError> {
+    let quakes = earthquakes::fetch_earthquakes(min_mag, max_age_hours).await?;
+    if quakes.is_empty() {
         println!("No earthquakes found for this selection.");
         return Ok(());
     }
-    use chrono::{Utc, TimeZone};
-    let now = Utc::now();
-    let max_age = chrono::Duration::hours(max_age_hours as i64);
-    let mut shown = 0;
     println!("\nRecent Earthquakes:");
-    for feature in data.features.iter() {
-        let mag = feature.properties.mag.unwrap_or(-999.0);
-        if mag < min_mag { continue; }
-        let time_ms = feature.properties.time.unwrap_or(0);
-        let event_time = chrono::Utc.timestamp_millis_opt(time_ms).single();
-        if let Some(event_time) = event_time {
-            if now.signed_duration_since(event_time) > max_age { continue; }
-        } else {
-            continue;
-        }
-        shown += 1;
-        let mag_str = if mag < 0.0 { "?".to_string() } else { format!("{:.1}", mag) };
-        let place = feature.properties.place.as_deref().unwrap_or("Unknown location");
-        let time = feature.properties.time.map(|t| format_utc_time(t)).unwrap_or("?".to_string());
-        let url = feature.properties.url.as_deref().unwrap_or("");
-        let (lat, lon, depth) = if let Some(geom) = &feature.geometry {
-            let coords = &geom.coordinates;
-            if coords.len() >= 3 {
-                (Some(coords[1]), Some(coords[0]), Some(coords[2]))
-            } else if coords.len() == 2 {
-                (Some(coords[1]), Some(coords[0]), None)
-            } else {
-                (None, None, None)
-            }
-        } else {
-            (None, None, None)
-        };
-        println!("{}. M{} | {} | {}", shown, mag_str, place, time);
-        if let (Some(lat), Some(lon)) = (lat, lon) {
-            println!("    Location: {:.3}, {:.3} | Depth: {} km", lat, lon, depth.map(|d| format!("{:.1}", d)).unwrap_or("?".to_string()));
+    for (i, quake) in quakes.iter().enumerate() {
+        let mag_str = quake.magnitude.filter(|m| *m >= 0.0).map(|m| format!("{:.1}", m)).unwrap_or("?".to_string());
+        let place = quake.place.as_deref().unwrap_or("Unknown location");
+        let time = quake.time.format("%Y-%m-%d %H:%M:%S UTC");
+        println!("{}. M{} | {} | {}", i + 1, mag_str, place, time);
+        if let (Some(lat), Some(lon)) = (quake.lat, quake.lon) {
+            println!("    Location: {:.3}, {:.3} | Depth: {}", lat, lon, quake.depth_km.map(|d| config::units().depth(d)).unwrap_or("?".to_string()));
             println!("    Google Maps: https://www.google.com/maps?q={},{}&ll={},{}&z=7", lat, lon, lat, lon);
         }
-        if !url.is_empty() {
+        if let Some(url) = quake.url.as_deref().filter(|u| !u.is_empty()) {
             println!("    More info: {}", url);
         }
     }
-    if shown == 0 {
-        println!("No earthquakes found for this selection.");
-    }
     Ok(())
 }
 
-fn format_utc_time(ms_since_epoch: i64) -> String {
-    use std::time::{UNIX_EPOCH, Duration};
-    use chrono::{DateTime, Utc};
-    let dt = UNIX_EPOCH + Duration::from_millis(ms_since_epoch as u64);
-    let datetime: DateTime<Utc> = DateTime::<Utc>::from(dt);
-    datetime.format("%Y-%m-%d %H:%M:%S UTC").to_string()
-}
-
 // --- Tides menu and logic ---
 
-#[derive(Debug, Deserialize)]
-struct NWSNoaaTideStation {
-    id: String,
-    name: String,
-    state: String,
-    lat: f64,
-    lon: f64,
-}
-
-async fn tides_menu() -> Result<(), AppError> {
+async fn tides_menu(book: &mut AddressBook) -> Result<(), AppError> {
     use std::io::Write;
     println!("\n--- Tides ---");
     println!("1. Lookup tides by address");
@@ -1389,47 +2717,59 @@ async fn tides_menu() -> Result<(), AppError> {
     let mut choice = String::new();
     io::stdin().read_line(&mut choice)?;
     match choice.trim() {
-        "1" => tides_by_address().await?,
+        "1" => tides_by_address(book).await?,
         "2" => tides_by_airport().await?,
         _ => return Ok(()),
     }
     Ok(())
 }
 
-async fn tides_by_address() -> Result<(), AppError> {
-    // Use the same address file path logic as async_main
-    let data_dir_path = PathBuf::from(DATA_DIR);
-    let addresses_path = if data_dir_path.exists() {
-        data_dir_path.join(ADDRESS_FILE)
-    } else {
-        // Fallback: use current directory (where binary is run)
-        PathBuf::from(ADDRESS_FILE)
-    };
-    let addresses = load_addresses(&addresses_path)?;
-    if addresses.is_empty() {
+async fn tides_by_address(book: &mut AddressBook) -> Result<(), AppError> {
+    if book.is_empty() {
         println!("No stored addresses found. Please add an address first.");
         return Ok(());
     }
-    println!("\nStored Addresses:");
-    for (i, (addr, _, _)) in addresses.iter().enumerate() {
-        println!("{}. {}", i + 1, addr);
-    }
-    println!("{}. Return to Tides Menu", addresses.len() + 1);
-    print!("Select an address number or return: ");
-    io::stdout().flush()?;
-    let mut selection_str = String::new();
-    io::stdin().read_line(&mut selection_str)?;
-    let n = match selection_str.trim().parse::<usize>() {
-        Ok(n) if n > 0 && n <= addresses.len() => n - 1,
-        _ => return Ok(()),
+    let Some(id) = prompt_address_selection(book, "Return to Tides Menu")? else {
+        return Ok(());
     };
-    let (address, lat, lon) = &addresses[n];
-    println!("\nSelected address: {} (Lat: {}, Lon: {})", address, lat, lon);
-    let state = extract_state_from_address(address).unwrap_or_else(|| {
+    tides_for_address(book, id).await
+}
+
+// Tides at the station remembered on a stored address; the nearest station is looked
+// up (and remembered) when there is none yet or the user asks to change it
+async fn tides_for_address(book: &mut AddressBook, id: u32) -> Result<(), AppError> {
+    use std::io::Write;
+    let entry = book.get(id).cloned().ok_or_else(|| AppError::UserInput(format!("No address with id {}.", id)))?;
+    println!("\nSelected address: {} (Lat: {}, Lon: {})", entry.label(), entry.lat, entry.lon);
+    if let Some(saved) = &entry.tide_station_id {
+        print!("Saved NOAA tide station: {}. Use it? (Y/n, n looks up the nearest station again): ", saved);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        if !input.trim().eq_ignore_ascii_case("n") {
+            return fetch_and_display_tide_predictions(saved).await;
+        }
+    }
+    let state = extract_state_from_address(&entry.address).unwrap_or_else(|| {
         println!("Could not extract state from address. Defaulting to closest station by coordinates.");
         "".to_string()
     });
-    find_and_display_tide_station(*lat, *lon, Some(&state)).await
+    let station_id = find_and_display_tide_station(entry.lat, entry.lon, Some(&state)).await?;
+    if station_id.is_some() && station_id != entry.tide_station_id {
+        remember_station(book, id, |e| e.tide_station_id = station_id.clone());
+    }
+    Ok(())
+}
+
+// Caches a station id on an address; failing to save only costs a lookup next time
+fn remember_station(book: &mut AddressBook, id: u32, update: impl FnOnce(&mut StoredAddress)) {
+    if let Some(entry) = book.get_mut(id) {
+        update(entry);
+        entry.touch();
+        if let Err(e) = book.save() {
+            eprintln!("Warning: could not update the address book: {}", e);
+        }
+    }
 }
 
 async fn tides_by_airport() -> Result<(), AppError> {
@@ -1443,11 +2783,7 @@ async fn tides_by_airport() -> Result<(), AppError> {
     if search.is_empty() {
         return Ok(());
     }
-    let mut results = airports::search_airports(search);
-    let us_states = [
-        "AL","AK","AZ","AR","CA","CO","CT","DE","FL","GA","HI","ID","IL","IN","IA","KS","KY","LA","ME","MD","MA","MI","MN","MS","MO","MT","NE","NV","NH","NJ","NM","NY","NC","ND","OH","OK","OR","PA","RI","SC","SD","TN","TX","UT","VT","VA","WA","WV","WI","WY"
-    ];
-    results.retain(|a| a.iso_region.starts_with("US-") && us_states.contains(&a.iso_region[3..].to_uppercase().as_str()));
+    let results = filtered_airport_search(search, &AirportFilter { us_only: true, scheduled_only: false });
     if results.is_empty() {
         println!("No US airports found matching '{}'.", search);
         return Ok(());
@@ -1470,7 +2806,7 @@ async fn tides_by_airport() -> Result<(), AppError> {
         println!("Could not extract state from airport. Defaulting to closest station by coordinates.");
         "".to_string()
     });
-    find_and_display_tide_station(lat, lon, Some(&state)).await
+    find_and_display_tide_station(lat, lon, Some(&state)).await.map(|_| ())
 }
 
 fn extract_state_from_address(address: &str) -> Option<String> {
@@ -1493,53 +2829,20 @@ fn extract_state_from_airport(airport: &airports::Airport) -> Option<String> {
     }
 }
 
-async fn find_and_display_tide_station(lat: f64, lon: f64, state: Option<&str>) -> Result<(), AppError> {
+// Returns the id of the station shown, if any
+async fn find_and_display_tide_station(lat: f64, lon: f64, state: Option<&str>) -> Result<Option<String>, AppError> {
     // Fetch NOAA tide stations list (filter by state if possible)
-    let stations_url = "https://api.tidesandcurrents.noaa.gov/mdapi/prod/webapi/stations.json?type=tidepredictions&format=json".to_string();
-    let resp = HTTP_CLIENT.get(&stations_url).send().await.map_err(AppError::Network)?;
-    let json: serde_json::Value = resp.json().await.map_err(AppError::Network)?;
-    let mut stations = Vec::new();
-    if let Some(arr) = json["stations"].as_array() {
-        for s in arr {
-            let id = s["id"].as_str().unwrap_or("").to_string();
-            let name = s["name"].as_str().unwrap_or("").to_string();
-            let state_val = s["state"].as_str().unwrap_or("").to_string();
-            // NOAA returns lat/lon as floats, not strings, if available
-            let lat = s["lat"].as_f64().unwrap_or_else(|| s["lat"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0));
-            let lon = s["lng"].as_f64().unwrap_or_else(|| s["lng"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0));
-            stations.push(NWSNoaaTideStation { id, name, state: state_val, lat, lon });
-        }
-    }
+    let stations = tides::fetch_tide_stations().await?;
     if stations.is_empty() {
         println!("No tide stations available from NOAA API.");
-        return Ok(());
+        return Ok(None);
     }
-    // Filter by state if provided
-    let filtered: Vec<_> = if let Some(state) = state {
-        if !state.is_empty() {
-            let filtered: Vec<_> = stations.iter().filter(|s| s.state.eq_ignore_ascii_case(state)).collect();
-            if filtered.is_empty() {
-                println!("No tide stations found in state '{}', searching all stations...", state);
-                stations.iter().collect()
-            } else {
-                filtered
-            }
-        } else {
-            stations.iter().collect()
+    let nearest = tides::nearest_tide_station(&stations, lat, lon, state);
+    if let (Some(state), Some(station)) = (state.filter(|s| !s.is_empty()), nearest) {
+        if !station.state.eq_ignore_ascii_case(state) {
+            println!("No tide stations found in state '{}', searching all stations...", state);
         }
-    } else {
-        stations.iter().collect()
-    };
-    if filtered.is_empty() {
-        println!("No tide stations found (even after fallback). NOAA data may be incomplete.");
-        return Ok(());
     }
-    // Find nearest station
-    let nearest = filtered.iter().min_by(|a, b| {
-        let da = haversine_distance(lat, lon, a.lat, a.lon);
-        let db = haversine_distance(lat, lon, b.lat, b.lon);
-        da.partial_cmp(&db).unwrap()
-    });
     if let Some(station) = nearest {
         println!("\nNearest NOAA Tide Station with predictions:");
         println!("  {} ({})", station.name, station.id);
@@ -1554,48 +2857,64 @@ async fn find_and_display_tide_station(lat: f64, lon: f64, state: Option<&str>)
         println!("  Reference location: https://www.google.com/maps?q={},{}", lat, lon);
         // Fetch and display tide predictions (no need to show time zone info, NOAA returns local time)
         fetch_and_display_tide_predictions(&station.id).await?;
+        Ok(Some(station.id.clone()))
     } else {
         println!("No tide station found.");
+        Ok(None)
     }
-    Ok(())
-}
-
-fn haversine_distance(lat1: f64, lon1: f64, lat2: f64, lon2: f64) -> f64 {
-    let r = 6371.0; // km
-    let dlat = (lat2 - lat1).to_radians();
-    let dlon = (lon2 - lon1).to_radians(); // Fixed: match Python logic
-    let a = (dlat / 2.0).sin().powi(2)
-        + lat1.to_radians().cos() * lat2.to_radians().cos() * (dlon / 2.0).sin().powi(2);
-    let c = 2.0 * a.sqrt().atan2((1.0 - a).sqrt());
-    r * c
 }
 
-// Remove timezone logic, just print the time as returned by NOAA
+// NOAA returns local station time, so no timezone conversion is needed
 async fn fetch_and_display_tide_predictions(station_id: &str) -> Result<(), AppError> {
-    use chrono::{Utc};
-    let today = Utc::now().date_naive();
+    let today = chrono::Utc::now().date_naive();
     let tomorrow = today.succ_opt().unwrap();
     for day in [today, tomorrow] {
-        let url = format!("https://api.tidesandcurrents.noaa.gov/api/prod/datagetter?product=predictions&application=NOS.COOPS.TAC.WL&begin_date={}&end_date={}&datum=MLLW&station={}&time_zone=lst_ldt&units=english&interval=hilo&format=json", day.format("%Y%m%d"), day.format("%Y%m%d"), station_id);
-        let resp = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
-        let json: serde_json::Value = resp.json().await.map_err(AppError::Network)?;
+        let predictions = tides::fetch_tide_predictions(station_id, day).await?;
         println!("\nTide predictions for {} (local station time):", day);
-        if let Some(preds) = json["predictions"].as_array() {
-            for p in preds {
-                let t = p["t"].as_str().unwrap_or("");
-                let v = p["v"].as_str().unwrap_or("");
-                let typ = p["type"].as_str().unwrap_or("");
-                // Format time as am/pm
-                let t_ampm = if let Ok(dt) = chrono::NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M") {
-                    dt.format("%Y-%m-%d %I:%M %p").to_string()
-                } else {
-                    t.to_string()
-                };
-                println!("  {}: {} ft ({})", t_ampm, v, typ);
-            }
-        } else {
+        if predictions.is_empty() {
             println!("  No predictions available.");
         }
+        for p in &predictions {
+            // Format time as am/pm
+            println!("  {}: {} ({})", p.time.format("%Y-%m-%d %I:%M %p"), config::units().tide_height(p.height_ft), p.kind);
+        }
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_ages() {
+        let age = |minutes| format_age(chrono::Duration::minutes(minutes));
+        assert_eq!(age(-5), "just now");
+        assert_eq!(age(0), "just now");
+        assert_eq!(age(59), "59 min ago");
+        assert_eq!(age(120), "2 h ago");
+        assert_eq!(age(135), "2 h 15 min ago");
+        assert_eq!(age(1440), "1 day ago");
+        assert_eq!(age(2879), "1 day ago");
+        assert_eq!(age(2880), "2 days ago");
+    }
+
+    #[test]
+    fn averages_each_hour_with_readings() {
+        let observation = |time: &str, c: Option<f64>| nws::Observation {
+            observed_at: Some(chrono::DateTime::parse_from_rfc3339(time).unwrap()),
+            temperature_c: c,
+            ..nws::Observation::default()
+        };
+        let observations = [
+            observation("2024-01-15T10:00:00+00:00", Some(1.0)),
+            observation("2024-01-15T10:59:00+00:00", Some(2.0)),
+            observation("2024-01-15T11:15:00+00:00", None),
+            observation("2024-01-15T12:05:00+00:00", Some(4.0)),
+            // Buckets follow UTC hours, whatever the station's offset
+            observation("2024-01-15T05:30:00-07:00", Some(6.0)),
+        ];
+        assert_eq!(hourly_averages(&observations, |o| o.temperature_c), [1.5, 5.0]);
+        assert!(hourly_averages(&observations, |o| o.wind_speed_mps).is_empty());
+    }
+}
@@ -0,0 +1,958 @@
+// METAR and TAF decoder
+//
+// Parses raw aviation reports as issued by US and ICAO stations into typed groups:
+// wind, visibility, weather phenomena, cloud layers, temperature/dew point,
+// altimeter and remarks. It does no I/O; `aviation_weather` fetches the raw text.
+// Groups the decoder does not recognize are kept in `unparsed` rather than rejected,
+// so one odd group never hides the rest of a report.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::flight_rules::FlightCategory;
+use crate::AppError;
+
+/// Day of month and UTC time a report was issued, e.g. "171856Z".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ReportTime {
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl fmt::Display for ReportTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {} at {:02}:{:02} UTC", self.day, self.hour, self.minute)
+    }
+}
+
+/// Surface wind; speeds are in knots (MPS and KMH reports are converted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Wind {
+    /// Degrees true the wind blows from; `None` for variable (VRB)
+    pub direction_deg: Option<u16>,
+    pub speed_kt: u16,
+    pub gust_kt: Option<u16>,
+    /// Range of a variable direction, e.g. 250V310
+    pub variable_from_deg: Option<u16>,
+    pub variable_to_deg: Option<u16>,
+}
+
+impl Wind {
+    pub fn is_calm(&self) -> bool {
+        self.speed_kt == 0 && self.gust_kt.is_none()
+    }
+}
+
+impl fmt::Display for Wind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_calm() {
+            return f.write_str("calm");
+        }
+        match self.direction_deg {
+            Some(dir) => write!(f, "from {:03}° at {} kt", dir, self.speed_kt)?,
+            None => write!(f, "variable at {} kt", self.speed_kt)?,
+        }
+        if let Some(gust) = self.gust_kt {
+            write!(f, ", gusting {} kt", gust)?;
+        }
+        if let (Some(from), Some(to)) = (self.variable_from_deg, self.variable_to_deg) {
+            write!(f, " (varying {:03}°-{:03}°)", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a visibility is a bound rather than an exact value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bound {
+    /// "M1/4SM": less than
+    LessThan,
+    /// "P6SM", "9999" or CAVOK: more than
+    MoreThan,
+}
+
+/// Prevailing visibility in statute miles (metric reports are converted).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Visibility {
+    pub statute_miles: f64,
+    pub bound: Option<Bound>,
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bound {
+            Some(Bound::LessThan) => f.write_str("less than ")?,
+            Some(Bound::MoreThan) => f.write_str("more than ")?,
+            None => {}
+        }
+        let miles = self.statute_miles;
+        if miles.fract() == 0.0 {
+            write!(f, "{:.0} SM", miles)
+        } else {
+            write!(f, "{} SM", format_fraction(miles))
+        }
+    }
+}
+
+// 1.5 -> "1 1/2", 0.25 -> "1/4"; other values with two decimals
+fn format_fraction(miles: f64) -> String {
+    let whole = miles.trunc();
+    let eighths = ((miles - whole) * 16.0).round() as u32;
+    let fraction = match eighths {
+        2 => "1/8",
+        3 => "3/16",
+        4 => "1/4",
+        5 => "5/16",
+        6 => "3/8",
+        8 => "1/2",
+        10 => "5/8",
+        12 => "3/4",
+        14 => "7/8",
+        _ => return format!("{:.2}", miles),
+    };
+    if whole == 0.0 { fraction.to_string() } else { format!("{:.0} {}", whole, fraction) }
+}
+
+/// Sky cover of a cloud group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CloudCover {
+    /// Sky clear (human observer)
+    SKC,
+    /// No clouds below 12,000 ft (automated station)
+    CLR,
+    /// No significant cloud
+    NSC,
+    /// No cloud detected
+    NCD,
+    FEW,
+    SCT,
+    BKN,
+    OVC,
+    /// Vertical visibility into an obscured sky
+    VV,
+}
+
+impl CloudCover {
+    fn parse(code: &str) -> Option<CloudCover> {
+        Some(match code {
+            "SKC" => CloudCover::SKC,
+            "CLR" => CloudCover::CLR,
+            "NSC" => CloudCover::NSC,
+            "NCD" => CloudCover::NCD,
+            "FEW" => CloudCover::FEW,
+            "SCT" => CloudCover::SCT,
+            "BKN" => CloudCover::BKN,
+            "OVC" => CloudCover::OVC,
+            "VV" => CloudCover::VV,
+            _ => return None,
+        })
+    }
+
+    pub fn describe(&self) -> &'static str {
+        match self {
+            CloudCover::SKC => "sky clear",
+            CloudCover::CLR => "clear below 12,000 ft",
+            CloudCover::NSC => "no significant cloud",
+            CloudCover::NCD => "no cloud detected",
+            CloudCover::FEW => "few",
+            CloudCover::SCT => "scattered",
+            CloudCover::BKN => "broken",
+            CloudCover::OVC => "overcast",
+            CloudCover::VV => "vertical visibility",
+        }
+    }
+}
+
+/// One cloud group, e.g. "BKN079CB".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CloudGroup {
+    pub cover: CloudCover,
+    /// Height of the base above ground level
+    pub base_ft: Option<u32>,
+    /// "CB" (cumulonimbus) or "TCU" (towering cumulus)
+    pub cloud_type: Option<String>,
+}
+
+impl fmt::Display for CloudGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.cover.describe())?;
+        if let Some(base) = self.base_ft {
+            write!(f, " at {} ft", base)?;
+        }
+        match self.cloud_type.as_deref() {
+            Some("CB") => f.write_str(" (cumulonimbus)"),
+            Some("TCU") => f.write_str(" (towering cumulus)"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Intensity or proximity prefix of a weather group.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Intensity {
+    Light,
+    Moderate,
+    Heavy,
+    /// "VC": within 5 to 10 SM of the station
+    Vicinity,
+}
+
+/// A present-weather group such as "-SN", "+TSRA" or "BR".
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WeatherPhenomenon {
+    pub raw: String,
+    pub intensity: Intensity,
+    /// "SH", "TS", "FZ", ...
+    pub descriptor: Option<String>,
+    /// "RA", "SN", "BR", ...
+    pub phenomena: Vec<String>,
+}
+
+const DESCRIPTORS: [(&str, &str); 8] = [
+    ("MI", "shallow"),
+    ("PR", "partial"),
+    ("BC", "patches of"),
+    ("DR", "low drifting"),
+    ("BL", "blowing"),
+    ("SH", "showers of"),
+    ("TS", "thunderstorm"),
+    ("FZ", "freezing"),
+];
+
+const PHENOMENA: [(&str, &str); 22] = [
+    ("DZ", "drizzle"),
+    ("RA", "rain"),
+    ("SN", "snow"),
+    ("SG", "snow grains"),
+    ("IC", "ice crystals"),
+    ("PL", "ice pellets"),
+    ("GR", "hail"),
+    ("GS", "small hail"),
+    ("UP", "unknown precipitation"),
+    ("BR", "mist"),
+    ("FG", "fog"),
+    ("FU", "smoke"),
+    ("VA", "volcanic ash"),
+    ("DU", "widespread dust"),
+    ("SA", "sand"),
+    ("HZ", "haze"),
+    ("PY", "spray"),
+    ("PO", "dust whirls"),
+    ("SQ", "squalls"),
+    ("FC", "funnel cloud"),
+    ("SS", "sandstorm"),
+    ("DS", "duststorm"),
+];
+
+fn lookup(table: &[(&str, &'static str)], code: &str) -> Option<&'static str> {
+    table.iter().find(|(c, _)| *c == code).map(|(_, text)| *text)
+}
+
+impl WeatherPhenomenon {
+    fn parse(group: &str) -> Option<WeatherPhenomenon> {
+        let (intensity, rest) = if let Some(rest) = group.strip_prefix('-') {
+            (Intensity::Light, rest)
+        } else if let Some(rest) = group.strip_prefix('+') {
+            (Intensity::Heavy, rest)
+        } else if let Some(rest) = group.strip_prefix("VC") {
+            (Intensity::Vicinity, rest)
+        } else {
+            (Intensity::Moderate, group)
+        };
+        if rest.is_empty() || rest.len() % 2 != 0 || !rest.is_ascii() {
+            return None;
+        }
+        let mut codes: Vec<&str> = (0..rest.len()).step_by(2).map(|i| &rest[i..i + 2]).collect();
+        let descriptor = match codes.first() {
+            Some(code) if lookup(&DESCRIPTORS, code).is_some() => Some(codes.remove(0).to_string()),
+            _ => None,
+        };
+        if !codes.iter().all(|code| lookup(&PHENOMENA, code).is_some()) {
+            return None;
+        }
+        // A descriptor alone is only a complete group for thunderstorms and showers ("TS", "VCSH")
+        if codes.is_empty() && !matches!(descriptor.as_deref(), Some("TS") | Some("SH")) {
+            return None;
+        }
+        Some(WeatherPhenomenon {
+            raw: group.to_string(),
+            intensity,
+            descriptor,
+            phenomena: codes.into_iter().map(str::to_string).collect(),
+        })
+    }
+}
+
+impl fmt::Display for WeatherPhenomenon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut words: Vec<&str> = Vec::new();
+        match self.intensity {
+            Intensity::Light => words.push("light"),
+            Intensity::Heavy => words.push("heavy"),
+            Intensity::Moderate | Intensity::Vicinity => {}
+        }
+        let descriptor = self.descriptor.as_deref().and_then(|d| lookup(&DESCRIPTORS, d));
+        let phenomena: Vec<&str> = self.phenomena.iter().filter_map(|p| lookup(&PHENOMENA, p)).collect();
+        match (self.descriptor.as_deref(), descriptor) {
+            // "TSRA" reads better as "thunderstorm with rain"
+            (Some("TS"), Some(text)) if !phenomena.is_empty() => {
+                words.push(text);
+                words.push("with");
+            }
+            (Some("SH"), Some(_)) if phenomena.is_empty() => words.push("showers"),
+            (_, Some(text)) => words.push(text),
+            _ => {}
+        }
+        let joined = phenomena.join(" and ");
+        if !joined.is_empty() {
+            words.push(&joined);
+        }
+        if self.intensity == Intensity::Vicinity {
+            words.push("in the vicinity");
+        }
+        f.write_str(&words.join(" "))
+    }
+}
+
+/// Altimeter setting, given in inches of mercury ("A3000") or hectopascals ("Q1013").
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Altimeter {
+    pub inhg: f64,
+    pub hpa: f64,
+}
+
+impl fmt::Display for Altimeter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.2} inHg ({:.0} hPa)", self.inhg, self.hpa)
+    }
+}
+
+const HPA_PER_INHG: f64 = 33.8639;
+
+/// A decoded METAR or SPECI.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metar {
+    pub raw: String,
+    pub station: String,
+    pub issued: Option<ReportTime>,
+    /// Issued by an automated station without human oversight
+    pub automated: bool,
+    /// A correction of an earlier report
+    pub corrected: bool,
+    pub wind: Option<Wind>,
+    pub visibility: Option<Visibility>,
+    /// Runway visual range groups, undecoded (e.g. "R28L/2400FT")
+    pub runway_visual_range: Vec<String>,
+    pub weather: Vec<WeatherPhenomenon>,
+    pub clouds: Vec<CloudGroup>,
+    pub temperature_c: Option<i32>,
+    pub dewpoint_c: Option<i32>,
+    pub altimeter: Option<Altimeter>,
+    /// Trend forecast appended to ICAO reports, undecoded (e.g. "BECMG 4000 BR" or "NOSIG")
+    pub trend: Option<String>,
+    /// Everything after "RMK"
+    pub remarks: Option<String>,
+    /// Groups the decoder did not recognize
+    pub unparsed: Vec<String>,
+}
+
+impl Metar {
+    /// Base of the lowest broken, overcast or obscured layer; `None` when no layer forms a ceiling.
+    pub fn ceiling_ft(&self) -> Option<u32> {
+        ceiling_ft(&self.clouds)
+    }
+
+    /// FAA flight category from the ceiling and prevailing visibility.
+    pub fn flight_category(&self) -> Option<FlightCategory> {
+        if self.visibility.is_none() && self.clouds.is_empty() {
+            return None;
+        }
+        FlightCategory::from_conditions(self.ceiling_ft().map(f64::from), self.visibility.map(|v| v.statute_miles))
+    }
+}
+
+fn ceiling_ft(clouds: &[CloudGroup]) -> Option<u32> {
+    clouds
+        .iter()
+        .filter(|cloud| matches!(cloud.cover, CloudCover::BKN | CloudCover::OVC | CloudCover::VV))
+        .filter_map(|cloud| cloud.base_ft)
+        .min()
+}
+
+/// The change indicator that starts a TAF forecast period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum TafChange {
+    /// The opening forecast after the header
+    Initial,
+    /// "FM171800": conditions change completely from this time
+    From { time: ReportTime },
+    /// "BECMG": a gradual change during the period
+    Becoming,
+    /// "TEMPO": temporary fluctuations during the period
+    Temporary,
+    /// "PROB30" / "PROB40", optionally followed by TEMPO
+    Probability { percent: u8, temporary: bool },
+}
+
+impl fmt::Display for TafChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TafChange::Initial => f.write_str("Initially"),
+            TafChange::From { time } => write!(f, "From {}", time),
+            TafChange::Becoming => f.write_str("Becoming"),
+            TafChange::Temporary => f.write_str("Temporarily"),
+            TafChange::Probability { percent, temporary: false } => write!(f, "{}% chance", percent),
+            TafChange::Probability { percent, temporary: true } => write!(f, "{}% chance, temporarily", percent),
+        }
+    }
+}
+
+/// Start and end of a validity period, as day of month and UTC hour ("1718/1818").
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ValidPeriod {
+    pub from_day: u8,
+    pub from_hour: u8,
+    pub to_day: u8,
+    pub to_hour: u8,
+}
+
+impl fmt::Display for ValidPeriod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "day {} {:02}:00 to day {} {:02}:00 UTC", self.from_day, self.from_hour, self.to_day, self.to_hour)
+    }
+}
+
+/// One forecast period of a TAF.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TafPeriod {
+    pub change: TafChange,
+    pub valid: Option<ValidPeriod>,
+    pub wind: Option<Wind>,
+    pub visibility: Option<Visibility>,
+    pub weather: Vec<WeatherPhenomenon>,
+    pub clouds: Vec<CloudGroup>,
+    pub unparsed: Vec<String>,
+}
+
+/// A decoded terminal aerodrome forecast.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Taf {
+    pub raw: String,
+    pub station: String,
+    pub issued: Option<ReportTime>,
+    pub amended: bool,
+    pub valid: Option<ValidPeriod>,
+    pub periods: Vec<TafPeriod>,
+    pub remarks: Option<String>,
+}
+
+/// Decodes one METAR or SPECI report.
+pub fn parse_metar(raw: &str) -> Result<Metar, AppError> {
+    let raw = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (body, remarks) = split_remarks(&raw);
+    let mut tokens = body.split(' ').filter(|t| !t.is_empty()).peekable();
+    while tokens.next_if(|t| matches!(*t, "METAR" | "SPECI")).is_some() {}
+    let station = tokens
+        .next()
+        .filter(|t| is_station(t))
+        .ok_or_else(|| AppError::Api(format!("Not a METAR report: '{}'", raw)))?
+        .to_string();
+
+    let mut metar = Metar {
+        raw: raw.clone(),
+        station,
+        issued: None,
+        automated: false,
+        corrected: false,
+        wind: None,
+        visibility: None,
+        runway_visual_range: Vec::new(),
+        weather: Vec::new(),
+        clouds: Vec::new(),
+        temperature_c: None,
+        dewpoint_c: None,
+        altimeter: None,
+        trend: None,
+        remarks,
+        unparsed: Vec::new(),
+    };
+    metar.issued = tokens.next_if(|t| parse_report_time(t).is_some()).and_then(parse_report_time);
+
+    while let Some(token) = tokens.next() {
+        match token {
+            // The trend forecasts conditions; none of it was observed
+            "NOSIG" | "BECMG" | "TEMPO" => {
+                metar.trend = Some(std::iter::once(token).chain(tokens.by_ref()).collect::<Vec<_>>().join(" "));
+            }
+            "AUTO" => metar.automated = true,
+            "COR" => metar.corrected = true,
+            "CAVOK" => {
+                metar.visibility = Some(Visibility { statute_miles: 10.0 * KM_TO_SM, bound: Some(Bound::MoreThan) });
+                metar.clouds.push(CloudGroup { cover: CloudCover::NSC, base_ft: None, cloud_type: None });
+            }
+            _ => {
+                if let Some(wind) = parse_wind(token) {
+                    metar.wind = Some(wind);
+                } else if let (Some(wind), Some((from, to))) = (metar.wind.as_mut(), parse_variable_direction(token)) {
+                    wind.variable_from_deg = Some(from);
+                    wind.variable_to_deg = Some(to);
+                } else if let Some(visibility) = parse_visibility(token, &mut tokens) {
+                    metar.visibility = Some(visibility);
+                } else if token.starts_with('R') && token.contains('/') && token.len() > 4 {
+                    metar.runway_visual_range.push(token.to_string());
+                } else if let Some(weather) = WeatherPhenomenon::parse(token) {
+                    metar.weather.push(weather);
+                } else if let Some(cloud) = parse_cloud(token) {
+                    metar.clouds.push(cloud);
+                } else if let Some((temperature, dewpoint)) = parse_temperature(token) {
+                    metar.temperature_c = Some(temperature);
+                    metar.dewpoint_c = dewpoint;
+                } else if let Some(altimeter) = parse_altimeter(token) {
+                    metar.altimeter = Some(altimeter);
+                } else {
+                    metar.unparsed.push(token.to_string());
+                }
+            }
+        }
+    }
+    Ok(metar)
+}
+
+/// Decodes one TAF, including its FM, BECMG, TEMPO and PROB periods.
+pub fn parse_taf(raw: &str) -> Result<Taf, AppError> {
+    let raw = raw.split_whitespace().collect::<Vec<_>>().join(" ");
+    let (body, remarks) = split_remarks(&raw);
+    let mut tokens = body.split(' ').filter(|t| !t.is_empty()).peekable();
+    tokens.next_if_eq(&"TAF");
+    let mut amended = false;
+    while let Some(token) = tokens.next_if(|t| matches!(*t, "AMD" | "COR")) {
+        amended |= token == "AMD";
+    }
+    let station = tokens
+        .next()
+        .filter(|t| is_station(t))
+        .ok_or_else(|| AppError::Api(format!("Not a TAF report: '{}'", raw)))?
+        .to_string();
+    let issued = tokens.next_if(|t| parse_report_time(t).is_some()).and_then(parse_report_time);
+    let valid = tokens.next_if(|t| parse_valid_period(t).is_some()).and_then(parse_valid_period);
+
+    let mut periods = vec![TafPeriod::new(TafChange::Initial)];
+    while let Some(token) = tokens.next() {
+        let change = if let Some(time) = token.strip_prefix("FM").and_then(parse_from_time) {
+            Some(TafChange::From { time })
+        } else if token == "BECMG" {
+            Some(TafChange::Becoming)
+        } else if token == "TEMPO" {
+            Some(TafChange::Temporary)
+        } else if let Some(percent) = token.strip_prefix("PROB").and_then(|p| p.parse::<u8>().ok()) {
+            let temporary = tokens.next_if_eq(&"TEMPO").is_some();
+            Some(TafChange::Probability { percent, temporary })
+        } else {
+            None
+        };
+        if let Some(change) = change {
+            let mut period = TafPeriod::new(change);
+            period.valid = tokens.next_if(|t| parse_valid_period(t).is_some()).and_then(parse_valid_period);
+            periods.push(period);
+            continue;
+        }
+
+        let period = periods.last_mut().expect("periods starts with the initial forecast");
+        if token == "CAVOK" {
+            period.visibility = Some(Visibility { statute_miles: 10.0 * KM_TO_SM, bound: Some(Bound::MoreThan) });
+            period.clouds.push(CloudGroup { cover: CloudCover::NSC, base_ft: None, cloud_type: None });
+        } else if let Some(wind) = parse_wind(token) {
+            period.wind = Some(wind);
+        } else if let Some(visibility) = parse_visibility(token, &mut tokens) {
+            period.visibility = Some(visibility);
+        } else if let Some(weather) = WeatherPhenomenon::parse(token) {
+            period.weather.push(weather);
+        } else if let Some(cloud) = parse_cloud(token) {
+            period.clouds.push(cloud);
+        } else if token != "NSW" {
+            period.unparsed.push(token.to_string());
+        }
+    }
+    Ok(Taf { raw: raw.clone(), station, issued, amended, valid, periods, remarks })
+}
+
+impl TafPeriod {
+    fn new(change: TafChange) -> TafPeriod {
+        TafPeriod {
+            change,
+            valid: None,
+            wind: None,
+            visibility: None,
+            weather: Vec::new(),
+            clouds: Vec::new(),
+            unparsed: Vec::new(),
+        }
+    }
+}
+
+const KM_TO_SM: f64 = 0.621371;
+
+// Splits off "RMK ..." remarks
+fn split_remarks(raw: &str) -> (&str, Option<String>) {
+    match raw.find(" RMK") {
+        Some(index) => {
+            let remarks = raw[index + 4..].trim();
+            (&raw[..index], (!remarks.is_empty()).then(|| remarks.to_string()))
+        }
+        None => (raw, None),
+    }
+}
+
+fn is_station(token: &str) -> bool {
+    token.len() == 4 && token.chars().all(|c| c.is_ascii_alphanumeric()) && token.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+}
+
+fn digits(text: &str) -> bool {
+    !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+// "171856Z"
+fn parse_report_time(token: &str) -> Option<ReportTime> {
+    let digits_part = token.strip_suffix('Z')?;
+    if digits_part.len() != 6 || !digits(digits_part) {
+        return None;
+    }
+    Some(ReportTime {
+        day: digits_part[0..2].parse().ok()?,
+        hour: digits_part[2..4].parse().ok()?,
+        minute: digits_part[4..6].parse().ok()?,
+    })
+}
+
+// "171800" after FM
+fn parse_from_time(text: &str) -> Option<ReportTime> {
+    if text.len() != 6 || !digits(text) {
+        return None;
+    }
+    Some(ReportTime { day: text[0..2].parse().ok()?, hour: text[2..4].parse().ok()?, minute: text[4..6].parse().ok()? })
+}
+
+// "1718/1818"
+fn parse_valid_period(token: &str) -> Option<ValidPeriod> {
+    let (from, to) = token.split_once('/')?;
+    if from.len() != 4 || to.len() != 4 || !digits(from) || !digits(to) {
+        return None;
+    }
+    Some(ValidPeriod {
+        from_day: from[0..2].parse().ok()?,
+        from_hour: from[2..4].parse().ok()?,
+        to_day: to[0..2].parse().ok()?,
+        to_hour: to[2..4].parse().ok()?,
+    })
+}
+
+// "30015G25KT", "VRB03KT", "00000KT", "27010MPS"
+fn parse_wind(token: &str) -> Option<Wind> {
+    let (body, factor) = if let Some(body) = token.strip_suffix("KT") {
+        (body, 1.0)
+    } else if let Some(body) = token.strip_suffix("MPS") {
+        (body, 1.943844)
+    } else if let Some(body) = token.strip_suffix("KMH") {
+        (body, 0.539957)
+    } else {
+        return None;
+    };
+    if body.len() < 5 || !body.is_ascii() {
+        return None;
+    }
+    let (direction, rest) = body.split_at(3);
+    let direction_deg = match direction {
+        "VRB" => None,
+        d if digits(d) => Some(d.parse::<u16>().ok()?),
+        _ => return None,
+    };
+    let (speed, gust) = match rest.split_once('G') {
+        Some((speed, gust)) => (speed, Some(gust)),
+        None => (rest, None),
+    };
+    if !(2..=3).contains(&speed.len()) || !digits(speed) {
+        return None;
+    }
+    let convert = |value: &str| value.parse::<f64>().ok().map(|v| (v * factor).round() as u16);
+    let gust_kt = match gust {
+        Some(g) if (2..=3).contains(&g.len()) && digits(g) => Some(convert(g)?),
+        Some(_) => return None,
+        None => None,
+    };
+    Some(Wind { direction_deg, speed_kt: convert(speed)?, gust_kt, variable_from_deg: None, variable_to_deg: None })
+}
+
+// "250V310"
+fn parse_variable_direction(token: &str) -> Option<(u16, u16)> {
+    let (from, to) = token.split_once('V')?;
+    if from.len() != 3 || to.len() != 3 || !digits(from) || !digits(to) {
+        return None;
+    }
+    Some((from.parse().ok()?, to.parse().ok()?))
+}
+
+// "10SM", "P6SM", "M1/4SM", "1/2SM", "1 1/2SM" (two tokens) or metres ("9999", "0800")
+fn parse_visibility<'a>(token: &str, tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>) -> Option<Visibility> {
+    if token.len() == 4 && digits(token) {
+        let metres: f64 = token.parse().ok()?;
+        return Some(if token == "9999" {
+            Visibility { statute_miles: 10.0 * KM_TO_SM, bound: Some(Bound::MoreThan) }
+        } else {
+            Visibility { statute_miles: metres / 1000.0 * KM_TO_SM, bound: None }
+        });
+    }
+    // Whole miles followed by a fraction, e.g. "1 1/2SM"
+    if (1..=2).contains(&token.len()) && digits(token) {
+        let whole: f64 = token.parse().ok()?;
+        let fraction = tokens.peek().and_then(|next| next.strip_suffix("SM")).and_then(parse_fraction)?;
+        tokens.next();
+        return Some(Visibility { statute_miles: whole + fraction, bound: None });
+    }
+    let miles = token.strip_suffix("SM")?;
+    let (miles, bound) = if let Some(m) = miles.strip_prefix('P') {
+        (m, Some(Bound::MoreThan))
+    } else if let Some(m) = miles.strip_prefix('M') {
+        (m, Some(Bound::LessThan))
+    } else {
+        (miles, None)
+    };
+    let statute_miles = if digits(miles) { miles.parse().ok()? } else { parse_fraction(miles)? };
+    Some(Visibility { statute_miles, bound })
+}
+
+fn parse_fraction(text: &str) -> Option<f64> {
+    let (numerator, denominator) = text.split_once('/')?;
+    if !digits(numerator) || !digits(denominator) {
+        return None;
+    }
+    let denominator: f64 = denominator.parse().ok()?;
+    (denominator > 0.0).then_some(numerator.parse::<f64>().ok()? / denominator)
+}
+
+// "FEW039", "BKN079CB", "VV002", "CLR", "OVC///"
+fn parse_cloud(token: &str) -> Option<CloudGroup> {
+    if let Some(cover) = CloudCover::parse(token).filter(|c| matches!(c, CloudCover::SKC | CloudCover::CLR | CloudCover::NSC | CloudCover::NCD)) {
+        return Some(CloudGroup { cover, base_ft: None, cloud_type: None });
+    }
+    let split = if token.starts_with("VV") { 2 } else { 3 };
+    if token.len() < split + 3 || !token.is_ascii() {
+        return None;
+    }
+    let cover = CloudCover::parse(&token[..split])?;
+    let height = &token[split..split + 3];
+    let base_ft = match height {
+        "///" => None,
+        h if digits(h) => Some(h.parse::<u32>().ok()? * 100),
+        _ => return None,
+    };
+    let cloud_type = match &token[split + 3..] {
+        "" | "///" => None,
+        "CB" | "TCU" => Some(token[split + 3..].to_string()),
+        _ => return None,
+    };
+    Some(CloudGroup { cover, base_ft, cloud_type })
+}
+
+// "21/05", "M02/M08", "12/" (dew point missing)
+fn parse_temperature(token: &str) -> Option<(i32, Option<i32>)> {
+    let (temperature, dewpoint) = token.split_once('/')?;
+    let parse = |value: &str| -> Option<i32> {
+        let (negative, number) = match value.strip_prefix('M') {
+            Some(number) => (true, number),
+            None => (false, value),
+        };
+        if number.len() != 2 || !digits(number) {
+            return None;
+        }
+        let number: i32 = number.parse().ok()?;
+        Some(if negative { -number } else { number })
+    };
+    let dewpoint = match dewpoint {
+        "" | "//" => None,
+        d => Some(parse(d)?),
+    };
+    Some((parse(temperature)?, dewpoint))
+}
+
+// "A3000" (hundredths of inHg) or "Q1013" (hPa)
+fn parse_altimeter(token: &str) -> Option<Altimeter> {
+    if !token.is_ascii() || token.len() != 5 || !digits(&token[1..]) {
+        return None;
+    }
+    let value: f64 = token[1..].parse().ok()?;
+    match &token[..1] {
+        "A" => Some(Altimeter { inhg: value / 100.0, hpa: value / 100.0 * HPA_PER_INHG }),
+        "Q" => Some(Altimeter { inhg: value / HPA_PER_INHG, hpa: value }),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_a_us_metar() {
+        let metar = parse_metar("KBZN 171856Z 30015G25KT 10SM -SN FEW039 BKN079 OVC100 M02/M08 A3000 RMK AO2 SLP215").unwrap();
+        assert_eq!(metar.station, "KBZN");
+        assert_eq!(metar.issued, Some(ReportTime { day: 17, hour: 18, minute: 56 }));
+        assert_eq!(
+            metar.wind,
+            Some(Wind { direction_deg: Some(300), speed_kt: 15, gust_kt: Some(25), variable_from_deg: None, variable_to_deg: None })
+        );
+        assert_eq!(metar.visibility, Some(Visibility { statute_miles: 10.0, bound: None }));
+        assert_eq!(metar.weather.len(), 1);
+        assert_eq!(metar.weather[0].to_string(), "light snow");
+        assert_eq!(metar.clouds.len(), 3);
+        assert_eq!(metar.clouds[1], CloudGroup { cover: CloudCover::BKN, base_ft: Some(7900), cloud_type: None });
+        assert_eq!(metar.temperature_c, Some(-2));
+        assert_eq!(metar.dewpoint_c, Some(-8));
+        let altimeter = metar.altimeter.unwrap();
+        assert!((altimeter.inhg - 30.00).abs() < 1e-9);
+        assert!((altimeter.hpa - 1015.9).abs() < 0.1);
+        assert_eq!(metar.remarks.as_deref(), Some("AO2 SLP215"));
+        assert!(metar.unparsed.is_empty());
+    }
+
+    #[test]
+    fn ceiling_is_the_lowest_broken_or_overcast_layer() {
+        // FEW039 is below the ceiling but does not form it
+        let metar = parse_metar("KBZN 171856Z 30015G25KT 10SM -SN FEW039 BKN079 OVC100 M02/M08 A3000").unwrap();
+        assert_eq!(metar.ceiling_ft(), Some(7900));
+        assert_eq!(metar.flight_category(), Some(FlightCategory::VFR));
+
+        let metar = parse_metar("KPWM 171853Z 18005KT 4SM BR SCT008 OVC025 08/07 A2992").unwrap();
+        assert_eq!(metar.ceiling_ft(), Some(2500));
+        assert_eq!(metar.flight_category(), Some(FlightCategory::MVFR));
+
+        let metar = parse_metar("KRKD 171853Z 00000KT 10SM SCT002 12/05 A2990").unwrap();
+        assert_eq!(metar.ceiling_ft(), None);
+        assert_eq!(metar.flight_category(), Some(FlightCategory::VFR));
+    }
+
+    #[test]
+    fn decodes_an_icao_metar() {
+        let metar = parse_metar("METAR LIRF 171850Z 24008KT 210V280 9999 FEW030CB 18/12 Q1017 NOSIG").unwrap();
+        assert_eq!(metar.station, "LIRF");
+        let wind = metar.wind.unwrap();
+        assert_eq!((wind.variable_from_deg, wind.variable_to_deg), (Some(210), Some(280)));
+        let visibility = metar.visibility.unwrap();
+        assert_eq!(visibility.bound, Some(Bound::MoreThan));
+        assert!((visibility.statute_miles - 6.21).abs() < 0.01);
+        assert_eq!(metar.clouds[0].cloud_type.as_deref(), Some("CB"));
+        assert_eq!(metar.altimeter.unwrap().hpa, 1017.0);
+        assert_eq!(metar.trend.as_deref(), Some("NOSIG"));
+        assert!(metar.unparsed.is_empty());
+    }
+
+    #[test]
+    fn trend_groups_are_not_observed_conditions() {
+        let metar = parse_metar("EGLL 171850Z 25012KT 9999 SCT030 14/09 Q1015 BECMG 4000 BR TEMPO BKN008 RMK TEST").unwrap();
+        assert!((metar.visibility.unwrap().statute_miles - 6.21).abs() < 0.01);
+        assert!(metar.weather.is_empty());
+        assert_eq!(metar.clouds.len(), 1);
+        assert_eq!(metar.altimeter.unwrap().hpa, 1015.0);
+        assert_eq!(metar.trend.as_deref(), Some("BECMG 4000 BR TEMPO BKN008"));
+        assert_eq!(metar.remarks.as_deref(), Some("TEST"));
+        assert_eq!(metar.flight_category(), Some(FlightCategory::VFR));
+        assert!(metar.unparsed.is_empty());
+    }
+
+    #[test]
+    fn decodes_fractional_and_bounded_visibility() {
+        let metar = parse_metar("KPWM 171853Z AUTO 00000KT 1 1/2SM BR OVC004 08/08 A2992").unwrap();
+        assert!(metar.automated);
+        assert!(metar.wind.unwrap().is_calm());
+        assert_eq!(metar.visibility, Some(Visibility { statute_miles: 1.5, bound: None }));
+        assert_eq!(metar.visibility.unwrap().to_string(), "1 1/2 SM");
+
+        let metar = parse_metar("KRKD 171853Z VRB03KT M1/4SM +FG VV001 05/05 A2990").unwrap();
+        assert_eq!(metar.wind.unwrap().direction_deg, None);
+        assert_eq!(metar.visibility, Some(Visibility { statute_miles: 0.25, bound: Some(Bound::LessThan) }));
+        assert_eq!(metar.clouds[0], CloudGroup { cover: CloudCover::VV, base_ft: Some(100), cloud_type: None });
+        assert_eq!(metar.weather[0].intensity, Intensity::Heavy);
+    }
+
+    #[test]
+    fn describes_weather_groups() {
+        let describe = |group: &str| WeatherPhenomenon::parse(group).unwrap().to_string();
+        assert_eq!(describe("+TSRA"), "heavy thunderstorm with rain");
+        assert_eq!(describe("FZDZ"), "freezing drizzle");
+        assert_eq!(describe("VCSH"), "showers in the vicinity");
+        assert_eq!(describe("-RASN"), "light rain and snow");
+        assert_eq!(describe("BCFG"), "patches of fog");
+        assert!(WeatherPhenomenon::parse("XX").is_none());
+        assert!(WeatherPhenomenon::parse("FZ").is_none());
+    }
+
+    #[test]
+    fn keeps_unknown_groups_instead_of_failing() {
+        let metar = parse_metar("KBZN 171856Z 30015KT 10SM CLR 21/05 A3000 $").unwrap();
+        assert_eq!(metar.unparsed, vec!["$".to_string()]);
+        assert_eq!(metar.clouds[0].cover, CloudCover::CLR);
+    }
+
+    #[test]
+    fn multibyte_groups_are_left_undecoded() {
+        for group in ["é123", "Aé12", "Q101é", "BKNé30", "3é015KT", "1é2SM", "M0é/M08", "+é"] {
+            let metar = parse_metar(&format!("KBZN 171856Z {} A3000", group)).unwrap();
+            assert_eq!(metar.unparsed, vec![group.to_string()], "group {}", group);
+        }
+    }
+
+    #[test]
+    fn rejects_text_without_a_station() {
+        assert!(parse_metar("").is_err());
+        assert!(parse_metar("no data").is_err());
+    }
+
+    #[test]
+    fn decodes_a_taf_with_change_groups() {
+        let taf = parse_taf(
+            "TAF AMD KBZN 171720Z 1718/1818 30012KT P6SM SCT080 FM180000 32008KT P6SM BKN100 \
+             TEMPO 1806/1810 2SM -SN OVC015 PROB30 1812/1816 1/2SM FG",
+        )
+        .unwrap();
+        assert!(taf.amended);
+        assert_eq!(taf.station, "KBZN");
+        assert_eq!(taf.valid, Some(ValidPeriod { from_day: 17, from_hour: 18, to_day: 18, to_hour: 18 }));
+        assert_eq!(taf.periods.len(), 4);
+
+        let initial = &taf.periods[0];
+        assert_eq!(initial.change, TafChange::Initial);
+        assert_eq!(initial.wind.unwrap().speed_kt, 12);
+        assert_eq!(initial.visibility, Some(Visibility { statute_miles: 6.0, bound: Some(Bound::MoreThan) }));
+
+        assert_eq!(taf.periods[1].change, TafChange::From { time: ReportTime { day: 18, hour: 0, minute: 0 } });
+        assert_eq!(taf.periods[1].clouds[0].cover, CloudCover::BKN);
+
+        let tempo = &taf.periods[2];
+        assert_eq!(tempo.change, TafChange::Temporary);
+        assert_eq!(tempo.valid, Some(ValidPeriod { from_day: 18, from_hour: 6, to_day: 18, to_hour: 10 }));
+        assert_eq!(tempo.weather[0].to_string(), "light snow");
+
+        let prob = &taf.periods[3];
+        assert_eq!(prob.change, TafChange::Probability { percent: 30, temporary: false });
+        assert_eq!(prob.visibility.unwrap().statute_miles, 0.5);
+        assert!(taf.periods.iter().all(|p| p.unparsed.is_empty()));
+    }
+
+    #[test]
+    fn decodes_a_multiline_taf() {
+        let taf = parse_taf("TAF KBZN 171720Z 1718/1818 30012KT P6SM SCT080\n  FM180000 32008KT P6SM BKN100\n").unwrap();
+        assert_eq!(taf.periods.len(), 2);
+        assert_eq!(taf.periods[1].wind.unwrap().direction_deg, Some(320));
+    }
+
+    #[test]
+    fn converts_metric_wind_units() {
+        let wind = parse_wind("27010MPS").unwrap();
+        assert_eq!(wind.speed_kt, 19);
+        assert!(parse_wind("2701KT").is_none());
+    }
+}
@@ -0,0 +1,253 @@
+// Derived comfort metrics: dew point, wind chill, heat index, apparent temperature and humidex
+//
+// NWS leaves dew point, wind chill and heat index null on many observations. These
+// formulas fill the gaps from temperature, humidity and wind, and `ComfortMetrics`
+// records whether each value was reported by the station or computed here.
+// Inputs and outputs are SI: °C, m/s and percent relative humidity.
+
+use serde::Serialize;
+
+use crate::units::{celsius_to_fahrenheit, fahrenheit_to_celsius};
+
+/// Where a value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Source {
+    /// Reported by the observing station
+    Reported,
+    /// Computed locally from the other readings
+    Computed,
+}
+
+/// A temperature-like value and where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Derived {
+    pub value: f64,
+    pub source: Source,
+}
+
+impl Derived {
+    fn pick(reported: Option<f64>, computed: impl FnOnce() -> Option<f64>) -> Option<Derived> {
+        match reported {
+            Some(value) => Some(Derived { value, source: Source::Reported }),
+            None => computed().map(|value| Derived { value, source: Source::Computed }),
+        }
+    }
+}
+
+/// The readings comfort metrics are derived from.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Readings {
+    pub temperature_c: Option<f64>,
+    pub relative_humidity: Option<f64>,
+    pub wind_speed_mps: Option<f64>,
+    /// Values the station reported itself, preferred over computed ones
+    pub dew_point_c: Option<f64>,
+    pub wind_chill_c: Option<f64>,
+    pub heat_index_c: Option<f64>,
+}
+
+/// Comfort metrics for one observation, in °C except `humidex` (a unitless index).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct ComfortMetrics {
+    pub dew_point_c: Option<Derived>,
+    /// Only defined at or below 10 °C with wind above 4.8 km/h
+    pub wind_chill_c: Option<Derived>,
+    /// Only defined at or above 26.7 °C (80 °F)
+    pub heat_index_c: Option<Derived>,
+    /// What the temperature feels like: heat index when hot, wind chill when cold
+    /// and windy, otherwise the air temperature
+    pub feels_like_c: Option<Derived>,
+    /// Steadman's apparent temperature, accounting for humidity and wind
+    pub apparent_temperature_c: Option<Derived>,
+    pub humidex: Option<Derived>,
+}
+
+impl ComfortMetrics {
+    pub fn from_readings(r: &Readings) -> ComfortMetrics {
+        let dew_point_c = Derived::pick(r.dew_point_c, || Some(dew_point(r.temperature_c?, r.relative_humidity?)));
+        let wind_chill_c = Derived::pick(r.wind_chill_c, || wind_chill(r.temperature_c?, r.wind_speed_mps?));
+        let heat_index_c = Derived::pick(r.heat_index_c, || heat_index(r.temperature_c?, r.relative_humidity?));
+        let feels_like_c = heat_index_c.or(wind_chill_c).or_else(|| {
+            r.temperature_c.map(|value| Derived { value, source: Source::Reported })
+        });
+        let apparent_temperature_c = Derived::pick(None, || {
+            Some(apparent_temperature(r.temperature_c?, r.relative_humidity?, r.wind_speed_mps?))
+        });
+        let humidex = Derived::pick(None, || Some(humidex(r.temperature_c?, dew_point_c?.value)));
+        ComfortMetrics { dew_point_c, wind_chill_c, heat_index_c, feels_like_c, apparent_temperature_c, humidex }
+    }
+}
+
+/// Dew point from temperature and relative humidity (Magnus formula, accurate to
+/// about 0.1 °C between -40 and 50 °C).
+pub fn dew_point(temperature_c: f64, relative_humidity: f64) -> f64 {
+    const A: f64 = 17.625;
+    const B: f64 = 243.04;
+    let gamma = (relative_humidity.clamp(1.0, 100.0) / 100.0).ln() + A * temperature_c / (B + temperature_c);
+    B * gamma / (A - gamma)
+}
+
+/// NWS wind chill; `None` above 10 °C (50 °F) or with wind at or below 4.8 km/h (3 mph).
+pub fn wind_chill(temperature_c: f64, wind_speed_mps: f64) -> Option<f64> {
+    let wind_kmh = wind_speed_mps * 3.6;
+    if temperature_c > 10.0 || wind_kmh <= 4.8 {
+        return None;
+    }
+    let v = wind_kmh.powf(0.16);
+    Some(13.12 + 0.6215 * temperature_c - 11.37 * v + 0.3965 * temperature_c * v)
+}
+
+/// NWS heat index (Rothfusz regression with its low- and high-humidity adjustments);
+/// `None` below 26.7 °C (80 °F).
+pub fn heat_index(temperature_c: f64, relative_humidity: f64) -> Option<f64> {
+    let t = celsius_to_fahrenheit(temperature_c);
+    let rh = relative_humidity;
+    if t < 80.0 {
+        return None;
+    }
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    if (simple + t) / 2.0 < 80.0 {
+        return Some(fahrenheit_to_celsius(simple));
+    }
+    let mut hi = -42.379 + 2.04901523 * t + 10.14333127 * rh
+        - 0.22475541 * t * rh
+        - 0.00683783 * t * t
+        - 0.05481717 * rh * rh
+        + 0.00122874 * t * t * rh
+        + 0.00085282 * t * rh * rh
+        - 0.00000199 * t * t * rh * rh;
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+        hi -= ((13.0 - rh) / 4.0) * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    } else if rh > 85.0 && (80.0..=87.0).contains(&t) {
+        hi += ((rh - 85.0) / 10.0) * ((87.0 - t) / 5.0);
+    }
+    Some(fahrenheit_to_celsius(hi))
+}
+
+/// Steadman's apparent temperature as used by the Australian Bureau of Meteorology
+/// (shade, no solar radiation term).
+pub fn apparent_temperature(temperature_c: f64, relative_humidity: f64, wind_speed_mps: f64) -> f64 {
+    let vapour_pressure_hpa = relative_humidity / 100.0 * 6.105 * (17.27 * temperature_c / (237.7 + temperature_c)).exp();
+    temperature_c + 0.33 * vapour_pressure_hpa - 0.70 * wind_speed_mps - 4.00
+}
+
+/// Environment Canada humidex from temperature and dew point.
+pub fn humidex(temperature_c: f64, dew_point_c: f64) -> f64 {
+    let vapour_pressure_hpa = 6.11 * (5417.7530 * (1.0 / 273.16 - 1.0 / (273.15 + dew_point_c))).exp();
+    temperature_c + 0.5555 * (vapour_pressure_hpa - 10.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() <= tolerance, "expected {} ± {}, got {}", expected, tolerance, actual);
+    }
+
+    #[test]
+    fn dew_point_matches_reference_values() {
+        assert_close(dew_point(20.0, 50.0), 9.3, 0.1);
+        assert_close(dew_point(30.0, 70.0), 23.9, 0.1);
+        assert_close(dew_point(-10.0, 80.0), -12.8, 0.1);
+    }
+
+    #[test]
+    fn dew_point_equals_temperature_when_saturated() {
+        assert_close(dew_point(15.0, 100.0), 15.0, 1e-9);
+    }
+
+    #[test]
+    fn wind_chill_matches_nws_table() {
+        // 0 °F with 15 mph wind is -19 °F in the NWS wind chill chart
+        let chill = wind_chill(fahrenheit_to_celsius(0.0), 15.0 / 2.23694).unwrap();
+        assert_close(celsius_to_fahrenheit(chill), -19.0, 0.5);
+        // 30 °F with 10 mph wind is 21 °F
+        let chill = wind_chill(fahrenheit_to_celsius(30.0), 10.0 / 2.23694).unwrap();
+        assert_close(celsius_to_fahrenheit(chill), 21.0, 0.5);
+    }
+
+    #[test]
+    fn wind_chill_undefined_when_warm_or_calm() {
+        assert_eq!(wind_chill(15.0, 10.0), None);
+        assert_eq!(wind_chill(0.0, 1.0), None);
+    }
+
+    #[test]
+    fn heat_index_matches_nws_table() {
+        // 90 °F at 60 % humidity is 100 °F in the NWS heat index chart
+        let hi = heat_index(fahrenheit_to_celsius(90.0), 60.0).unwrap();
+        assert_close(celsius_to_fahrenheit(hi), 100.0, 1.0);
+        // 100 °F at 40 % is 109 °F
+        let hi = heat_index(fahrenheit_to_celsius(100.0), 40.0).unwrap();
+        assert_close(celsius_to_fahrenheit(hi), 109.0, 1.0);
+    }
+
+    #[test]
+    fn heat_index_undefined_below_80_f() {
+        assert_eq!(heat_index(20.0, 90.0), None);
+    }
+
+    #[test]
+    fn apparent_temperature_matches_bom_formula() {
+        assert_close(apparent_temperature(25.0, 50.0, 2.0), 24.8, 0.1);
+        // Wind makes it feel colder
+        assert!(apparent_temperature(10.0, 50.0, 10.0) < apparent_temperature(10.0, 50.0, 0.0));
+    }
+
+    #[test]
+    fn humidex_matches_environment_canada_example() {
+        // 30 °C with a 15 °C dew point gives a humidex of 34
+        assert_close(humidex(30.0, 15.0), 34.0, 0.5);
+    }
+
+    #[test]
+    fn reported_values_win_over_computed_ones() {
+        let metrics = ComfortMetrics::from_readings(&Readings {
+            temperature_c: Some(20.0),
+            relative_humidity: Some(50.0),
+            wind_speed_mps: Some(3.0),
+            dew_point_c: Some(5.0),
+            ..Readings::default()
+        });
+        assert_eq!(metrics.dew_point_c, Some(Derived { value: 5.0, source: Source::Reported }));
+        // Humidex uses the reported dew point
+        assert_close(metrics.humidex.unwrap().value, humidex(20.0, 5.0), 1e-9);
+        assert_eq!(metrics.humidex.unwrap().source, Source::Computed);
+    }
+
+    #[test]
+    fn missing_values_are_computed() {
+        let metrics = ComfortMetrics::from_readings(&Readings {
+            temperature_c: Some(-5.0),
+            relative_humidity: Some(70.0),
+            wind_speed_mps: Some(8.0),
+            ..Readings::default()
+        });
+        let dew = metrics.dew_point_c.unwrap();
+        assert_eq!(dew.source, Source::Computed);
+        assert_close(dew.value, dew_point(-5.0, 70.0), 1e-9);
+        let chill = metrics.wind_chill_c.unwrap();
+        assert_eq!(chill.source, Source::Computed);
+        assert_eq!(metrics.heat_index_c, None);
+        // Cold and windy: feels like the wind chill
+        assert_eq!(metrics.feels_like_c, Some(chill));
+    }
+
+    #[test]
+    fn feels_like_is_air_temperature_in_mild_weather() {
+        let metrics = ComfortMetrics::from_readings(&Readings {
+            temperature_c: Some(18.0),
+            relative_humidity: Some(40.0),
+            wind_speed_mps: Some(2.0),
+            ..Readings::default()
+        });
+        assert_eq!(metrics.feels_like_c, Some(Derived { value: 18.0, source: Source::Reported }));
+    }
+
+    #[test]
+    fn nothing_is_computed_without_inputs() {
+        assert_eq!(ComfortMetrics::from_readings(&Readings::default()), ComfortMetrics::default());
+    }
+}
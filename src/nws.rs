@@ -0,0 +1,924 @@
+// National Weather Service (api.weather.gov): stations, observations and forecasts
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use tokio::task::JoinSet;
+
+use crate::flight_rules::{is_ceiling_cover, FlightCategory};
+use crate::meteo::{ComfortMetrics, Readings};
+use crate::units::{METRES_TO_FEET, METRES_TO_MILES};
+use crate::{api_error, config, haversine_distance, AppError, HTTP_CLIENT};
+
+/// The observation station used for a point, plus the point's forecast URL.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearestStation {
+    pub station_id: String,
+    pub station_name: String,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    /// Distance from the point
+    pub distance_km: Option<f64>,
+    /// IANA time zone of the station, e.g. "America/Denver"
+    pub time_zone: Option<String>,
+    pub forecast_url: String,
+    /// `/forecast/hourly` URL for the same grid point
+    pub forecast_hourly_url: Option<String>,
+}
+
+/// An observation station NWS lists for a point.
+#[derive(Debug, Clone, Serialize)]
+pub struct Station {
+    pub station_id: String,
+    pub station_name: String,
+    pub lat: Option<f64>,
+    pub lon: Option<f64>,
+    pub distance_km: Option<f64>,
+    pub time_zone: Option<String>,
+}
+
+/// The stations NWS lists for a point, nearest first, and the point's forecast URLs.
+#[derive(Debug, Clone, Serialize)]
+pub struct PointStations {
+    pub stations: Vec<Station>,
+    pub forecast_url: String,
+    pub forecast_hourly_url: Option<String>,
+}
+
+impl PointStations {
+    /// `station` together with this point's forecast URLs.
+    pub fn with_forecast(&self, station: &Station) -> NearestStation {
+        NearestStation {
+            station_id: station.station_id.clone(),
+            station_name: station.station_name.clone(),
+            lat: station.lat,
+            lon: station.lon,
+            distance_km: station.distance_km,
+            time_zone: station.time_zone.clone(),
+            forecast_url: self.forecast_url.clone(),
+            forecast_hourly_url: self.forecast_hourly_url.clone(),
+        }
+    }
+}
+
+/// A nearby station and when it last reported.
+#[derive(Debug, Clone, Serialize)]
+pub struct StationStatus {
+    #[serde(flatten)]
+    pub station: Station,
+    /// Time of the latest observation; `None` if the station returned none
+    pub observed_at: Option<DateTime<FixedOffset>>,
+}
+
+impl StationStatus {
+    /// Age of the latest observation.
+    pub fn age(&self) -> Option<Duration> {
+        self.observed_at.map(observation_age)
+    }
+}
+
+/// The station chosen for a point by `select_station`.
+#[derive(Debug, Clone)]
+pub struct StationChoice {
+    pub station: NearestStation,
+    /// Its latest observation, if one could be fetched
+    pub observation: Option<Observation>,
+    /// Stations passed over, in order, because their latest observation was stale or
+    /// missing. When `all_stale`, the other candidates that were tried instead.
+    pub skipped: Vec<StationStatus>,
+    /// No candidate had a recent observation, so the first choice was kept anyway
+    pub all_stale: bool,
+}
+
+impl StationChoice {
+    /// Whether the chosen station is the preferred or nearest one rather than a fallback.
+    pub fn is_first_choice(&self) -> bool {
+        self.all_stale || self.skipped.is_empty()
+    }
+}
+
+/// Latest observation from a station. Values are in the SI units NWS reports:
+/// °C, m/s, metres, pascals, percent and degrees true.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Observation {
+    /// When the station made the observation
+    pub observed_at: Option<DateTime<FixedOffset>>,
+    /// Quality-control flag of each reading that has one, keyed by field name (e.g. "temperature_c")
+    pub quality_control: BTreeMap<&'static str, QualityControl>,
+    pub text_description: Option<String>,
+    pub temperature_c: Option<f64>,
+    pub dewpoint_c: Option<f64>,
+    pub heat_index_c: Option<f64>,
+    pub wind_chill_c: Option<f64>,
+    pub wind_direction_deg: Option<f64>,
+    pub wind_speed_mps: Option<f64>,
+    pub wind_gust_mps: Option<f64>,
+    pub relative_humidity: Option<f64>,
+    pub cloud_layers: Vec<CloudLayer>,
+    pub visibility_m: Option<f64>,
+    pub barometric_pressure_pa: Option<f64>,
+}
+
+impl Observation {
+    /// Time since the observation was made.
+    pub fn age(&self) -> Option<Duration> {
+        self.observed_at.map(observation_age)
+    }
+
+    /// Whether the observation is missing a time or older than `[stations] max_observation_age_minutes`.
+    pub fn is_stale(&self) -> bool {
+        self.age().is_none_or(|age| age > max_observation_age())
+    }
+
+    /// Dew point, wind chill, heat index and feels-like values, computed where NWS left them out.
+    pub fn comfort(&self) -> ComfortMetrics {
+        ComfortMetrics::from_readings(&Readings {
+            temperature_c: self.temperature_c,
+            relative_humidity: self.relative_humidity,
+            wind_speed_mps: self.wind_speed_mps,
+            dew_point_c: self.dewpoint_c,
+            wind_chill_c: self.wind_chill_c,
+            heat_index_c: self.heat_index_c,
+        })
+    }
+
+    /// Base of the lowest broken, overcast or obscured layer, in metres; `None` when
+    /// no layer forms a ceiling.
+    pub fn ceiling_m(&self) -> Option<f64> {
+        self.cloud_layers
+            .iter()
+            .filter(|layer| layer.amount.as_deref().is_some_and(is_ceiling_cover))
+            .filter_map(|layer| layer.base_m)
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    /// FAA flight category from the ceiling and visibility; `None` without a visibility
+    /// or cloud report to go on.
+    pub fn flight_category(&self) -> Option<FlightCategory> {
+        if self.visibility_m.is_none() && self.cloud_layers.is_empty() {
+            return None;
+        }
+        FlightCategory::from_conditions(
+            self.ceiling_m().map(|m| m * METRES_TO_FEET),
+            self.visibility_m.map(|m| m * METRES_TO_MILES),
+        )
+    }
+
+    /// The quality-control flag for a field, e.g. `quality("temperature_c")`.
+    pub fn quality(&self, field: &str) -> Option<QualityControl> {
+        self.quality_control.get(field).copied()
+    }
+}
+
+/// MADIS quality-control flag NWS attaches to each reading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityControl {
+    /// Preliminary; no checks applied yet
+    #[serde(rename = "Z")]
+    Unchecked,
+    /// Passed the validity check
+    #[serde(rename = "C")]
+    Coarse,
+    /// Passed the validity and internal consistency checks
+    #[serde(rename = "S")]
+    Screened,
+    /// Passed every check, including comparison with neighbouring stations
+    #[serde(rename = "V")]
+    Verified,
+    /// Failed the validity check
+    #[serde(rename = "X")]
+    Rejected,
+    /// Passed the validity check but failed a later one
+    #[serde(rename = "Q")]
+    Questioned,
+    /// Judged good by a forecaster
+    #[serde(rename = "G")]
+    Good,
+    /// Judged bad by a forecaster
+    #[serde(rename = "B")]
+    Bad,
+    #[serde(other, rename = "?")]
+    Unknown,
+}
+
+impl QualityControl {
+    /// Whether the reading failed a check or was judged bad.
+    pub fn is_suspect(&self) -> bool {
+        matches!(self, QualityControl::Rejected | QualityControl::Questioned | QualityControl::Bad)
+    }
+}
+
+impl fmt::Display for QualityControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            QualityControl::Unchecked => "not yet checked",
+            QualityControl::Coarse => "coarse check",
+            QualityControl::Screened => "screened",
+            QualityControl::Verified => "verified",
+            QualityControl::Rejected => "rejected",
+            QualityControl::Questioned => "questioned",
+            QualityControl::Good => "judged good",
+            QualityControl::Bad => "judged bad",
+            QualityControl::Unknown => "unknown flag",
+        })
+    }
+}
+
+fn observation_age(observed_at: DateTime<FixedOffset>) -> Duration {
+    Utc::now() - observed_at.with_timezone(&Utc)
+}
+
+/// Observations older than this count as stale.
+pub fn max_observation_age() -> Duration {
+    Duration::minutes(config::get().stations.max_observation_age_minutes as i64)
+}
+
+/// Lowest and highest value of one quantity over an observation history.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Extremes {
+    pub min: f64,
+    pub min_at: DateTime<FixedOffset>,
+    pub max: f64,
+    pub max_at: DateTime<FixedOffset>,
+}
+
+impl Extremes {
+    /// Extremes of `field` over the observations that report it.
+    pub fn of(observations: &[Observation], field: impl Fn(&Observation) -> Option<f64>) -> Option<Extremes> {
+        let mut readings = observations.iter().filter_map(|o| Some((field(o)?, o.observed_at?)));
+        let (first, first_at) = readings.next()?;
+        let mut extremes = Extremes { min: first, min_at: first_at, max: first, max_at: first_at };
+        for (value, at) in readings {
+            if value < extremes.min {
+                (extremes.min, extremes.min_at) = (value, at);
+            }
+            if value > extremes.max {
+                (extremes.max, extremes.max_at) = (value, at);
+            }
+        }
+        Some(extremes)
+    }
+}
+
+/// Direction of the barometric pressure over the last three hours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PressureTrend {
+    Rising,
+    Steady,
+    Falling,
+}
+
+/// The 3-hour pressure tendency: change between the latest reading and the one
+/// closest to three hours earlier.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct PressureTendency {
+    pub trend: PressureTrend,
+    pub change_pa: f64,
+    /// Time between the two readings, close to 3
+    pub over_hours: f64,
+}
+
+impl PressureTendency {
+    /// Changes smaller than 1 hPa in three hours count as steady
+    const STEADY_PA: f64 = 100.0;
+
+    /// `None` unless there are readings about three hours apart.
+    pub fn from_observations(observations: &[Observation]) -> Option<PressureTendency> {
+        let readings: Vec<(DateTime<FixedOffset>, f64)> = observations.iter()
+            .filter_map(|o| Some((o.observed_at?, o.barometric_pressure_pa?)))
+            .collect();
+        let &(latest_at, latest) = readings.iter().max_by_key(|(at, _)| *at)?;
+        let target = latest_at - Duration::hours(3);
+        let &(earlier_at, earlier) = readings.iter()
+            .filter(|(at, _)| (*at - target).num_minutes().abs() <= 45)
+            .min_by_key(|(at, _)| (*at - target).num_minutes().abs())?;
+        let change_pa = latest - earlier;
+        let trend = if change_pa.abs() < Self::STEADY_PA {
+            PressureTrend::Steady
+        } else if change_pa > 0.0 {
+            PressureTrend::Rising
+        } else {
+            PressureTrend::Falling
+        };
+        Some(PressureTendency { trend, change_pa, over_hours: (latest_at - earlier_at).num_minutes() as f64 / 60.0 })
+    }
+}
+
+/// Min/max of each quantity and the pressure tendency over an observation history.
+#[derive(Debug, Clone, Serialize)]
+pub struct HistorySummary {
+    pub temperature_c: Option<Extremes>,
+    pub wind_speed_mps: Option<Extremes>,
+    pub wind_gust_mps: Option<Extremes>,
+    pub relative_humidity: Option<Extremes>,
+    pub barometric_pressure_pa: Option<Extremes>,
+    pub pressure_tendency: Option<PressureTendency>,
+}
+
+impl HistorySummary {
+    pub fn from_observations(observations: &[Observation]) -> HistorySummary {
+        HistorySummary {
+            temperature_c: Extremes::of(observations, |o| o.temperature_c),
+            wind_speed_mps: Extremes::of(observations, |o| o.wind_speed_mps),
+            wind_gust_mps: Extremes::of(observations, |o| o.wind_gust_mps),
+            relative_humidity: Extremes::of(observations, |o| o.relative_humidity),
+            barometric_pressure_pa: Extremes::of(observations, |o| o.barometric_pressure_pa),
+            pressure_tendency: PressureTendency::from_observations(observations),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CloudLayer {
+    pub amount: Option<String>, // e.g., SKC, FEW, SCT, BKN, OVC
+    pub base_m: Option<f64>,
+}
+
+/// One period ("Tonight", "Friday", ...) of an NWS forecast, or one hour of
+/// an hourly forecast (hourly periods have an empty `name`).
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ForecastPeriod {
+    pub name: String,
+    #[serde(rename = "startTime")]
+    pub start_time: Option<DateTime<FixedOffset>>,
+    #[serde(rename = "isDaytime", default)]
+    pub is_daytime: bool,
+    pub temperature: f64,
+    #[serde(rename = "temperatureUnit")]
+    pub temperature_unit: String,
+    /// Chance of precipitation in percent
+    #[serde(rename = "probabilityOfPrecipitation", default, deserialize_with = "quantity_value")]
+    pub probability_of_precipitation: Option<f64>,
+    /// As NWS words it, e.g. "5 to 10 mph"
+    #[serde(rename = "windSpeed", default)]
+    pub wind_speed: Option<String>,
+    /// Compass point the wind blows from, e.g. "SW"
+    #[serde(rename = "windDirection", default)]
+    pub wind_direction: Option<String>,
+    #[serde(rename = "shortForecast", default)]
+    pub short_forecast: String,
+    #[serde(rename = "detailedForecast")]
+    pub detailed_forecast: String,
+}
+
+// Structs for deserializing NOAA API /points response
+#[derive(Deserialize, Debug)]
+struct NWSPointResponse {
+    properties: NWSPointProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct NWSPointProperties {
+    #[serde(rename = "observationStations")]
+    observation_stations_url: String, // URL to fetch list of observation stations
+    forecast: String, // URL for the zone forecast
+    #[serde(rename = "forecastHourly")]
+    forecast_hourly: Option<String>,
+}
+
+// Structs for deserializing NOAA API /stations response (list of stations)
+#[derive(Deserialize, Debug)]
+struct NWSStationsResponse {
+    features: Vec<NWSStationFeature>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NWSStationFeature {
+    properties: NWSStationProperties,
+    geometry: Option<NWSGeometry>, // Added to capture station coordinates
+}
+
+#[derive(Deserialize, Debug)]
+struct NWSStationProperties {
+    #[serde(rename = "stationIdentifier")]
+    station_identifier: String,
+    name: String,
+    #[serde(rename = "timeZone")]
+    time_zone: Option<String>,
+}
+
+// GeoJSON geometry for station coordinates
+#[derive(Deserialize, Debug)]
+struct NWSGeometry {
+    coordinates: Option<Vec<f64>>, // [longitude, latitude]
+}
+
+// Structs for deserializing NOAA API /stations/{stationId}/observations/latest response
+#[derive(Deserialize, Debug)]
+struct WeatherObservationResponse {
+    properties: Option<WeatherProperties>, // Make properties itself optional for robustness
+}
+
+// /stations/{stationId}/observations response
+#[derive(Deserialize, Debug)]
+struct ObservationHistoryResponse {
+    features: Vec<WeatherObservationResponse>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WeatherProperties {
+    timestamp: Option<DateTime<FixedOffset>>,
+    temperature: Option<WeatherValueUnit>,
+    dewpoint: Option<WeatherValueUnit>,
+    #[serde(rename = "heatIndex")]
+    heat_index: Option<WeatherValueUnit>,
+    #[serde(rename = "windChill")]
+    wind_chill: Option<WeatherValueUnit>,
+    #[serde(rename = "textDescription")]
+    text_description: Option<String>,
+    #[serde(rename = "windDirection")]
+    wind_direction: Option<WeatherValueUnit>,
+    #[serde(rename = "windSpeed")]
+    wind_speed: Option<WeatherValueUnit>,
+    #[serde(rename = "windGust")]
+    wind_gust: Option<WeatherValueUnit>,
+    #[serde(rename = "relativeHumidity")]
+    relative_humidity: Option<WeatherValueUnit>,
+    #[serde(rename = "cloudLayers")]
+    cloud_layers: Option<Vec<NWSCloudLayer>>, // Make cloud_layers optional
+    visibility: Option<WeatherValueUnit>,
+    #[serde(rename = "barometricPressure")]
+    barometric_pressure: Option<WeatherValueUnit>,
+}
+
+#[derive(Deserialize, Debug)]
+struct WeatherValueUnit {
+    value: Option<f64>, // Value can be null for some fields like heatIndex or windGust
+    #[serde(rename = "unitCode")]
+    unit_code: Option<String>, // e.g. "wmoUnit:km_h-1"
+    #[serde(rename = "qualityControl")]
+    quality_control: Option<QualityControl>,
+}
+
+#[derive(Deserialize, Debug)]
+struct NWSCloudLayer {
+    base: Option<WeatherValueUnit>, // meters
+    amount: Option<String>,
+}
+
+// Structs for deserializing NOAA API Forecast response
+#[derive(Deserialize, Debug)]
+struct ForecastResponse {
+    properties: ForecastProperties,
+}
+
+#[derive(Deserialize, Debug)]
+struct ForecastProperties {
+    periods: Vec<ForecastPeriod>,
+}
+
+// Forecast quantities arrive as `{"unitCode": ..., "value": ...}` objects; keep just the value
+fn quantity_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+    Ok(Option::<WeatherValueUnit>::deserialize(deserializer)?.and_then(|v| v.value))
+}
+
+fn value(v: Option<WeatherValueUnit>) -> Option<f64> {
+    v.and_then(|v| v.value)
+}
+
+// NWS reports wind in km/h on most stations but m/s on some; normalize to m/s
+fn speed_mps(v: Option<WeatherValueUnit>) -> Option<f64> {
+    let v = v?;
+    let speed = v.value?;
+    match v.unit_code.as_deref() {
+        Some("wmoUnit:km_h-1") => Some(speed / 3.6),
+        Some("wmoUnit:kn") => Some(speed * 0.514444),
+        _ => Some(speed),
+    }
+}
+
+/// Fetches the observation stations NWS lists for a point, sorted by distance.
+/// Returns `Ok(None)` when NWS lists no stations for the point.
+pub async fn fetch_point_stations(lat: f64, lon: f64) -> Result<Option<PointStations>, AppError> {
+    let points_url = format!("{}/points/{},{}", config::endpoints().nws, lat, lon);
+
+    let points_response = HTTP_CLIENT.get(&points_url).send().await.map_err(AppError::Network)?;
+
+    if !points_response.status().is_success() {
+        return Err(api_error("NWS Points API", &points_url, points_response).await);
+    }
+
+    let points_data: NWSPointResponse = points_response.json().await.map_err(|e| {
+        AppError::Api(format!("Failed to parse JSON response from NWS Points API (URL: {}): {}", points_url, e))
+    })?;
+
+    let stations_url = config::rebase_nws_url(&points_data.properties.observation_stations_url);
+    let forecast_url = config::rebase_nws_url(&points_data.properties.forecast);
+    let forecast_hourly_url = points_data.properties.forecast_hourly.as_deref().map(config::rebase_nws_url);
+
+    let stations_response = HTTP_CLIENT.get(&stations_url).send().await.map_err(AppError::Network)?;
+
+    if !stations_response.status().is_success() {
+        return Err(api_error("NWS Stations API", &stations_url, stations_response).await);
+    }
+
+    let stations_data: NWSStationsResponse = stations_response.json().await.map_err(|e| {
+         AppError::Api(format!("Failed to parse JSON response from NWS Stations API (URL: {}): {}", stations_url, e))
+    })?;
+
+    let mut stations: Vec<Station> = stations_data.features.into_iter().map(|feature| {
+        let (station_lat, station_lon) = feature.geometry
+            .and_then(|geometry| geometry.coordinates)
+            .filter(|coords| coords.len() == 2)
+            .map(|coords| (Some(coords[1]), Some(coords[0]))) // lat, lon
+            .unwrap_or((None, None));
+        Station {
+            station_id: feature.properties.station_identifier,
+            station_name: feature.properties.name,
+            lat: station_lat,
+            lon: station_lon,
+            distance_km: station_lat.zip(station_lon).map(|(s_lat, s_lon)| haversine_distance(lat, lon, s_lat, s_lon)),
+            time_zone: feature.properties.time_zone,
+        }
+    }).collect();
+    if stations.is_empty() {
+        return Ok(None);
+    }
+    // NWS already lists nearby stations roughly in order; stations without coordinates go last
+    stations.sort_by(|a, b| a.distance_km.unwrap_or(f64::MAX).total_cmp(&b.distance_km.unwrap_or(f64::MAX)));
+    Ok(Some(PointStations { stations, forecast_url, forecast_hourly_url }))
+}
+
+/// Finds the NWS observation station closest to a point, ignoring how recently it reported.
+/// Returns `Ok(None)` when NWS lists no stations for the point.
+pub async fn find_nearest_station(lat: f64, lon: f64) -> Result<Option<NearestStation>, AppError> {
+    Ok(fetch_point_stations(lat, lon).await?.map(|point| point.with_forecast(&point.stations[0])))
+}
+
+/// Latest observation times of the `limit` stations closest to a point, nearest first.
+pub async fn station_statuses(point: &PointStations, limit: usize) -> Vec<StationStatus> {
+    let mut requests = JoinSet::new();
+    for (index, station) in point.stations.iter().take(limit).enumerate() {
+        let station_id = station.station_id.clone();
+        requests.spawn(async move { (index, fetch_latest_observation(&station_id).await.ok()) });
+    }
+    let mut observed_at = vec![None; limit.min(point.stations.len())];
+    while let Some(Ok((index, observation))) = requests.join_next().await {
+        observed_at[index] = observation.and_then(|o| o.observed_at);
+    }
+    point.stations.iter().zip(observed_at)
+        .map(|(station, observed_at)| StationStatus { station: station.clone(), observed_at })
+        .collect()
+}
+
+/// Chooses the observation station for a point: `preferred` if NWS lists it, else the
+/// nearest. When that station's latest observation is missing or older than
+/// `[stations] max_observation_age_minutes`, the next nearest station with a recent
+/// observation is used instead; if none has one, the first choice is kept and
+/// `all_stale` is set. Returns `Ok(None)` when NWS lists no stations for the point.
+pub async fn select_station(lat: f64, lon: f64, preferred: Option<&str>) -> Result<Option<StationChoice>, AppError> {
+    let Some(point) = fetch_point_stations(lat, lon).await? else {
+        return Ok(None);
+    };
+    let candidates = candidate_stations(&point.stations, preferred, config::get().stations.candidates);
+    let mut requests = JoinSet::new();
+    for (index, station) in candidates.iter().enumerate() {
+        let station_id = station.station_id.clone();
+        requests.spawn(async move { (index, fetch_latest_observation(&station_id).await.ok()) });
+    }
+    let mut observations = vec![None; candidates.len()];
+    while let Some(Ok((index, observation))) = requests.join_next().await {
+        observations[index] = observation;
+    }
+    let mut fetched: Vec<(&Station, Option<Observation>)> = candidates.into_iter().zip(observations).collect();
+    let (index, skipped, all_stale) = choose_candidate(&fetched, max_observation_age());
+    let (station, observation) = fetched.swap_remove(index);
+    Ok(Some(StationChoice { station: point.with_forecast(station), observation, skipped, all_stale }))
+}
+
+// The `limit` nearest stations, with `preferred` moved (or added) to the front when NWS lists it
+fn candidate_stations<'a>(stations: &'a [Station], preferred: Option<&str>, limit: usize) -> Vec<&'a Station> {
+    let mut candidates: Vec<&Station> = stations.iter().take(limit.max(1)).collect();
+    if let Some(preferred) = preferred.and_then(|id| stations.iter().find(|s| s.station_id.eq_ignore_ascii_case(id))) {
+        candidates.retain(|s| s.station_id != preferred.station_id);
+        candidates.insert(0, preferred);
+    }
+    candidates
+}
+
+// Picks among candidates in preference order, given their latest observations: the first
+// one no older than `max_age`, else the first one. Returns its index, the candidates
+// passed over before it (or, when none was recent, all the others) and whether none was
+// recent. `candidates` must not be empty.
+fn choose_candidate(candidates: &[(&Station, Option<Observation>)], max_age: Duration) -> (usize, Vec<StationStatus>, bool) {
+    let status = |(station, observation): &(&Station, Option<Observation>)| StationStatus {
+        station: (*station).clone(),
+        observed_at: observation.as_ref().and_then(|o| o.observed_at),
+    };
+    let is_recent = |observation: &Option<Observation>| observation.as_ref().and_then(Observation::age).is_some_and(|age| age <= max_age);
+    match candidates.iter().position(|(_, observation)| is_recent(observation)) {
+        Some(index) => (index, candidates[..index].iter().map(status).collect(), false),
+        None => (0, candidates[1..].iter().map(status).collect(), true),
+    }
+}
+
+/// Fetches the latest observation for a station (e.g. "KBZN").
+pub async fn fetch_latest_observation(station_id: &str) -> Result<Observation, AppError> {
+    let url = format!(
+        "{}/stations/{}/observations/latest",
+        config::endpoints().nws,
+        station_id
+    );
+
+    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+
+    if !response.status().is_success() {
+        return Err(api_error("NWS Observations API", &url, response).await);
+    }
+
+    // Now that we know the request was successful, we can parse the JSON.
+    let weather_data_response: WeatherObservationResponse = response.json().await.map_err(|e| {
+        AppError::Api(format!("Failed to parse JSON response from NWS Observations API (URL: {}): {}", url, e))
+    })?;
+
+    let properties = weather_data_response.properties.ok_or_else(|| {
+        AppError::Api(format!("Weather data properties are missing in the API response for station {}.", station_id))
+    })?;
+
+    Ok(observation_from(properties))
+}
+
+/// Longest history `fetch_observation_history` will ask for.
+pub const MAX_HISTORY_HOURS: u32 = 72;
+
+/// Fetches a station's observations from the last `hours` hours (at most
+/// `MAX_HISTORY_HOURS`), oldest first.
+pub async fn fetch_observation_history(station_id: &str, hours: u32) -> Result<Vec<Observation>, AppError> {
+    let start = Utc::now() - Duration::hours(hours.min(MAX_HISTORY_HOURS) as i64);
+    let url = format!(
+        "{}/stations/{}/observations?start={}",
+        config::endpoints().nws,
+        station_id,
+        start.format("%Y-%m-%dT%H:%M:%SZ")
+    );
+
+    let response = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+
+    if !response.status().is_success() {
+        return Err(api_error("NWS Observations API", &url, response).await);
+    }
+
+    let history: ObservationHistoryResponse = response.json().await.map_err(|e| {
+        AppError::Api(format!("Failed to parse JSON response from NWS Observations API (URL: {}): {}", url, e))
+    })?;
+
+    // NWS lists the newest observation first
+    let mut observations: Vec<Observation> = history.features.into_iter()
+        .filter_map(|feature| feature.properties)
+        .map(observation_from)
+        .filter(|o| o.observed_at.is_some())
+        .collect();
+    observations.sort_by_key(|o| o.observed_at);
+    Ok(observations)
+}
+
+fn observation_from(properties: WeatherProperties) -> Observation {
+    let quality_control = [
+        ("temperature_c", &properties.temperature),
+        ("dewpoint_c", &properties.dewpoint),
+        ("heat_index_c", &properties.heat_index),
+        ("wind_chill_c", &properties.wind_chill),
+        ("wind_direction_deg", &properties.wind_direction),
+        ("wind_speed_mps", &properties.wind_speed),
+        ("wind_gust_mps", &properties.wind_gust),
+        ("relative_humidity", &properties.relative_humidity),
+        ("visibility_m", &properties.visibility),
+        ("barometric_pressure_pa", &properties.barometric_pressure),
+    ]
+    .into_iter()
+    .filter_map(|(field, v)| {
+        // A flag on a missing reading says nothing useful
+        let v = v.as_ref().filter(|v| v.value.is_some())?;
+        Some((field, v.quality_control?))
+    })
+    .collect();
+
+    Observation {
+        observed_at: properties.timestamp,
+        quality_control,
+        text_description: properties.text_description,
+        temperature_c: value(properties.temperature),
+        dewpoint_c: value(properties.dewpoint),
+        heat_index_c: value(properties.heat_index),
+        wind_chill_c: value(properties.wind_chill),
+        wind_direction_deg: value(properties.wind_direction),
+        wind_speed_mps: speed_mps(properties.wind_speed),
+        wind_gust_mps: speed_mps(properties.wind_gust),
+        relative_humidity: value(properties.relative_humidity),
+        cloud_layers: properties.cloud_layers.unwrap_or_default().into_iter()
+            .map(|layer| CloudLayer { amount: layer.amount, base_m: value(layer.base) })
+            .collect(),
+        visibility_m: value(properties.visibility),
+        barometric_pressure_pa: value(properties.barometric_pressure),
+    }
+}
+
+/// Fetches the forecast periods from a `/gridpoints/.../forecast` URL.
+pub async fn fetch_forecast(forecast_url: &str) -> Result<Vec<ForecastPeriod>, AppError> {
+    fetch_periods(forecast_url, "NWS Forecast API").await
+}
+
+/// Fetches the hourly periods (about 156 hours) from a `/gridpoints/.../forecast/hourly` URL.
+pub async fn fetch_hourly_forecast(forecast_hourly_url: &str) -> Result<Vec<ForecastPeriod>, AppError> {
+    fetch_periods(forecast_hourly_url, "NWS Hourly Forecast API").await
+}
+
+async fn fetch_periods(url: &str, service: &str) -> Result<Vec<ForecastPeriod>, AppError> {
+    let url = &config::rebase_nws_url(url);
+    let response = HTTP_CLIENT.get(url).send().await.map_err(AppError::Network)?;
+
+    if !response.status().is_success() {
+        return Err(api_error(service, url, response).await);
+    }
+
+    let forecast_data: ForecastResponse = response.json().await.map_err(|e| {
+        AppError::Api(format!("Failed to parse JSON response from {} (URL: {}): {}", service, url, e))
+    })?;
+    Ok(forecast_data.properties.periods)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn station(id: &str) -> Station {
+        Station { station_id: id.to_string(), station_name: format!("{} Airport", id), lat: None, lon: None, distance_km: None, time_zone: None }
+    }
+
+    fn observed(minutes_ago: i64) -> Option<Observation> {
+        let observed_at = (Utc::now() - Duration::minutes(minutes_ago)).fixed_offset();
+        Some(Observation { observed_at: Some(observed_at), ..Observation::default() })
+    }
+
+    fn ids(statuses: &[StationStatus]) -> Vec<&str> {
+        statuses.iter().map(|s| s.station.station_id.as_str()).collect()
+    }
+
+    #[test]
+    fn preferred_station_goes_first() {
+        let stations = [station("KBZN"), station("KBTM"), station("KHLN"), station("KGPI")];
+        let order = |preferred, limit| candidate_stations(&stations, preferred, limit).iter().map(|s| s.station_id.clone()).collect::<Vec<_>>();
+        assert_eq!(order(None, 3), ["KBZN", "KBTM", "KHLN"]);
+        assert_eq!(order(Some("khln"), 3), ["KHLN", "KBZN", "KBTM"]);
+        // A preferred station beyond the candidate limit is still tried first
+        assert_eq!(order(Some("KGPI"), 2), ["KGPI", "KBZN", "KBTM"]);
+        assert_eq!(order(Some("KXYZ"), 2), ["KBZN", "KBTM"]);
+        assert_eq!(order(None, 0), ["KBZN"]);
+    }
+
+    #[test]
+    fn falls_back_past_stale_and_missing_observations() {
+        let (a, b, c) = (station("KBZN"), station("KBTM"), station("KHLN"));
+        let candidates = [(&a, observed(180)), (&b, None), (&c, observed(20))];
+        let (index, skipped, all_stale) = choose_candidate(&candidates, Duration::minutes(90));
+        assert_eq!(index, 2);
+        assert_eq!(ids(&skipped), ["KBZN", "KBTM"]);
+        assert!(skipped[0].observed_at.is_some() && skipped[1].observed_at.is_none());
+        assert!(!all_stale);
+
+        let (index, skipped, all_stale) = choose_candidate(&candidates[..1], Duration::minutes(240));
+        assert_eq!((index, skipped.len(), all_stale), (0, 0, false));
+    }
+
+    #[test]
+    fn keeps_the_first_choice_when_every_observation_is_stale() {
+        let (a, b, c) = (station("KBZN"), station("KBTM"), station("KHLN"));
+        let candidates = [(&a, None), (&b, observed(300)), (&c, observed(200))];
+        let (index, skipped, all_stale) = choose_candidate(&candidates, Duration::minutes(90));
+        assert_eq!(index, 0);
+        assert_eq!(ids(&skipped), ["KBTM", "KHLN"]);
+        assert!(all_stale);
+    }
+
+    // Trimmed from a /stations/KBZN/observations/latest response
+    const LATEST_OBSERVATION: &str = r#"{
+        "properties": {
+            "timestamp": "2024-01-15T14:53:00+00:00",
+            "textDescription": "Light Snow",
+            "temperature": { "unitCode": "wmoUnit:degC", "value": -7.2, "qualityControl": "V" },
+            "dewpoint": { "unitCode": "wmoUnit:degC", "value": -9.4, "qualityControl": "Q" },
+            "windDirection": { "unitCode": "wmoUnit:degree_(angle)", "value": 230, "qualityControl": "V" },
+            "windSpeed": { "unitCode": "wmoUnit:km_h-1", "value": 18.0, "qualityControl": "V" },
+            "windGust": { "unitCode": "wmoUnit:km_h-1", "value": null, "qualityControl": "Z" },
+            "barometricPressure": { "unitCode": "wmoUnit:Pa", "value": 101590, "qualityControl": "N" },
+            "visibility": { "unitCode": "wmoUnit:m", "value": 4020, "qualityControl": "X" },
+            "relativeHumidity": { "unitCode": "wmoUnit:percent", "value": 84.3 },
+            "heatIndex": { "unitCode": "wmoUnit:degC", "value": null, "qualityControl": "V" },
+            "cloudLayers": [
+                { "base": { "unitCode": "wmoUnit:m", "value": 760 }, "amount": "BKN" },
+                { "base": { "unitCode": "wmoUnit:m", "value": 1520 }, "amount": "OVC" }
+            ]
+        }
+    }"#;
+
+    fn latest_observation() -> Observation {
+        let response: WeatherObservationResponse = serde_json::from_str(LATEST_OBSERVATION).unwrap();
+        observation_from(response.properties.unwrap())
+    }
+
+    #[test]
+    fn reads_readings_and_quality_flags() {
+        let observation = latest_observation();
+        assert_eq!(observation.temperature_c, Some(-7.2));
+        assert_eq!(observation.wind_speed_mps, Some(5.0));
+        assert_eq!(observation.ceiling_m(), Some(760.0));
+        assert_eq!(observation.quality("temperature_c"), Some(QualityControl::Verified));
+        assert_eq!(observation.quality("dewpoint_c"), Some(QualityControl::Questioned));
+        assert_eq!(observation.quality("visibility_m"), Some(QualityControl::Rejected));
+        // A flag NWS adds later still parses, as an unknown one
+        assert_eq!(observation.quality("barometric_pressure_pa"), Some(QualityControl::Unknown));
+        // No flag given
+        assert_eq!(observation.quality("relative_humidity"), None);
+    }
+
+    #[test]
+    fn drops_flags_on_missing_readings() {
+        let observation = latest_observation();
+        assert_eq!(observation.wind_gust_mps, None);
+        assert_eq!(observation.quality("wind_gust_mps"), None);
+        assert_eq!(observation.quality("heat_index_c"), None);
+        assert_eq!(observation.quality("wind_chill_c"), None);
+        assert_eq!(observation.quality_control.len(), 6);
+    }
+
+    #[test]
+    fn suspect_flags() {
+        let suspect: Vec<QualityControl> = [
+            QualityControl::Unchecked,
+            QualityControl::Coarse,
+            QualityControl::Screened,
+            QualityControl::Verified,
+            QualityControl::Rejected,
+            QualityControl::Questioned,
+            QualityControl::Good,
+            QualityControl::Bad,
+            QualityControl::Unknown,
+        ]
+        .into_iter()
+        .filter(QualityControl::is_suspect)
+        .collect();
+        assert_eq!(suspect, [QualityControl::Rejected, QualityControl::Questioned, QualityControl::Bad]);
+    }
+
+    #[test]
+    fn old_or_undated_observations_are_stale() {
+        let observation = latest_observation();
+        assert!(observation.is_stale());
+        assert!(!observed(10).unwrap().is_stale());
+        assert!(Observation::default().is_stale());
+    }
+
+    fn at(minutes: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-01-15T12:00:00+00:00").unwrap() + Duration::minutes(minutes)
+    }
+
+    fn pressure(minutes: i64, pa: f64) -> Observation {
+        Observation { observed_at: Some(at(minutes)), barometric_pressure_pa: Some(pa), ..Observation::default() }
+    }
+
+    #[test]
+    fn pressure_tendency_compares_with_three_hours_earlier() {
+        let observations = [pressure(0, 101_000.0), pressure(-170, 101_250.0), pressure(-185, 101_400.0), pressure(-60, 100_000.0)];
+        let tendency = PressureTendency::from_observations(&observations).unwrap();
+        // The reading closest to three hours before the latest one is used
+        assert_eq!(tendency.change_pa, -400.0);
+        assert_eq!(tendency.over_hours, 185.0 / 60.0);
+        assert_eq!(tendency.trend, PressureTrend::Falling);
+
+        let rising = PressureTendency::from_observations(&[pressure(0, 101_200.0), pressure(-180, 101_000.0)]).unwrap();
+        assert_eq!(rising.trend, PressureTrend::Rising);
+    }
+
+    #[test]
+    fn pressure_tendency_steady_below_one_hectopascal() {
+        let trend = |change| PressureTendency::from_observations(&[pressure(0, 101_000.0 + change), pressure(-180, 101_000.0)]).unwrap().trend;
+        assert_eq!(trend(99.0), PressureTrend::Steady);
+        assert_eq!(trend(-99.0), PressureTrend::Steady);
+        assert_eq!(trend(100.0), PressureTrend::Rising);
+        assert_eq!(trend(-100.0), PressureTrend::Falling);
+    }
+
+    #[test]
+    fn pressure_tendency_needs_a_reading_near_three_hours_earlier() {
+        // Within 45 minutes either side of three hours earlier
+        assert!(PressureTendency::from_observations(&[pressure(0, 101_000.0), pressure(-135, 101_000.0)]).is_some());
+        assert!(PressureTendency::from_observations(&[pressure(0, 101_000.0), pressure(-225, 101_000.0)]).is_some());
+        assert!(PressureTendency::from_observations(&[pressure(0, 101_000.0), pressure(-134, 101_000.0)]).is_none());
+        assert!(PressureTendency::from_observations(&[pressure(0, 101_000.0), pressure(-226, 101_000.0)]).is_none());
+        assert!(PressureTendency::from_observations(&[pressure(0, 101_000.0)]).is_none());
+        assert!(PressureTendency::from_observations(&[]).is_none());
+    }
+
+    #[test]
+    fn extremes_skip_missing_readings() {
+        let temperature = |minutes, c| Observation { observed_at: Some(at(minutes)), temperature_c: c, ..Observation::default() };
+        let observations = [temperature(0, Some(3.0)), temperature(60, None), temperature(120, Some(-2.5)), temperature(180, Some(8.0)), temperature(240, Some(-2.5))];
+        let extremes = Extremes::of(&observations, |o| o.temperature_c).unwrap();
+        assert_eq!((extremes.min, extremes.min_at), (-2.5, at(120)));
+        assert_eq!((extremes.max, extremes.max_at), (8.0, at(180)));
+        assert!(Extremes::of(&observations, |o| o.wind_speed_mps).is_none());
+    }
+}
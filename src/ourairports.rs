@@ -0,0 +1,253 @@
+// OurAirports companion datasets: frequencies, navaids, countries and regions
+//
+// These sit next to airports.csv and go through the same on-disk cache. Each one is
+// optional: when a file cannot be loaded, lookups simply come back empty and airport
+// details fall back to the raw codes.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::dataset_cache::{self, DatasetStatus, RefreshPolicy};
+use crate::{config, haversine_distance, initial_bearing, AppError};
+
+/// A radio frequency published for an airport (airport-frequencies.csv).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Frequency {
+    pub airport_ident: String,
+    /// e.g. "TWR", "ATIS", "CTAF", "GND", "UNIC"
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub description: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub frequency_mhz: Option<f64>,
+}
+
+/// A radio navigation aid such as a VOR or NDB (navaids.csv).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Navaid {
+    pub ident: String,
+    pub name: String,
+    /// e.g. "VOR-DME", "VORTAC", "NDB"
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub frequency_khz: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub latitude_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub longitude_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub elevation_ft: Option<f64>,
+    pub iso_country: String,
+    /// Ident of the airport the navaid serves, if any
+    pub associated_airport: String,
+}
+
+impl Navaid {
+    /// Tuning frequency as pilots write it: kHz for NDBs, MHz for everything else.
+    pub fn frequency_label(&self) -> Option<String> {
+        let khz = self.frequency_khz?;
+        Some(if self.type_.contains("NDB") {
+            format!("{:.0} kHz", khz)
+        } else {
+            format!("{:.2} MHz", khz / 1000.0)
+        })
+    }
+}
+
+/// A country (countries.csv).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Country {
+    /// ISO 3166-1 alpha-2 code, e.g. "US"
+    pub code: String,
+    pub name: String,
+    pub continent: String,
+}
+
+/// A first-level subdivision such as a US state (regions.csv).
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Region {
+    /// ISO 3166-2 code, e.g. "US-ME"
+    pub code: String,
+    /// The code used locally, e.g. "ME"
+    pub local_code: String,
+    pub name: String,
+    pub iso_country: String,
+}
+
+/// A navaid found by `navaids_near`, with distance and bearing from the query point.
+#[derive(Debug, Clone, Serialize)]
+pub struct NearbyNavaid {
+    pub navaid: &'static Navaid,
+    pub distance_km: f64,
+    /// Initial bearing from the query point to the navaid, degrees true
+    pub bearing_deg: f64,
+}
+
+static FREQUENCIES: OnceLock<HashMap<String, Vec<Frequency>>> = OnceLock::new();
+static NAVAIDS: OnceLock<Vec<Navaid>> = OnceLock::new();
+static COUNTRIES: OnceLock<HashMap<String, Country>> = OnceLock::new();
+static REGIONS: OnceLock<HashMap<String, Region>> = OnceLock::new();
+
+/// File names of the companion datasets, in load order.
+pub const DATASETS: [&str; 4] = ["airport-frequencies.csv", "navaids.csv", "countries.csv", "regions.csv"];
+
+// Downloads (or reads from cache) one CSV and deserializes its rows, skipping malformed ones
+fn load<T: DeserializeOwned>(name: &str, policy: RefreshPolicy) -> Result<(Vec<T>, DatasetStatus), AppError> {
+    let url = format!("{}/{}", config::endpoints().ourairports, name);
+    let (bytes, status) = dataset_cache::fetch_dataset(name, &url, policy)?;
+    let mut rdr = csv::Reader::from_reader(bytes.as_slice());
+    Ok((rdr.deserialize::<T>().flatten().collect(), status))
+}
+
+fn key(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Loads every companion dataset, from the on-disk cache when fresh enough, and
+/// returns one result per file in `DATASETS` order. A file that fails to load leaves
+/// its lookups empty. Blocking; call before starting the async runtime.
+pub fn init_datasets() -> Vec<Result<DatasetStatus, AppError>> {
+    let policy = RefreshPolicy::IfStale;
+    vec![
+        load::<Frequency>(DATASETS[0], policy).map(|(rows, status)| {
+            let mut map: HashMap<String, Vec<Frequency>> = HashMap::new();
+            for frequency in rows {
+                map.entry(key(&frequency.airport_ident)).or_default().push(frequency);
+            }
+            let _ = FREQUENCIES.set(map);
+            status
+        }),
+        load::<Navaid>(DATASETS[1], policy).map(|(rows, status)| {
+            let _ = NAVAIDS.set(rows);
+            status
+        }),
+        load::<Country>(DATASETS[2], policy).map(|(rows, status)| {
+            let _ = COUNTRIES.set(rows.into_iter().map(|c| (key(&c.code), c)).collect());
+            status
+        }),
+        load::<Region>(DATASETS[3], policy).map(|(rows, status)| {
+            let _ = REGIONS.set(rows.into_iter().map(|r| (key(&r.code), r)).collect());
+            status
+        }),
+    ]
+}
+
+/// Revalidates every cached companion dataset with the server regardless of its age.
+pub fn refresh_datasets() -> Vec<Result<DatasetStatus, AppError>> {
+    DATASETS
+        .iter()
+        .map(|name| {
+            let url = format!("{}/{}", config::endpoints().ourairports, name);
+            let (_, status) = dataset_cache::fetch_dataset(name, &url, RefreshPolicy::Force)?;
+            if let dataset_cache::DatasetSource::StaleCache { error } = status.source {
+                return Err(AppError::Api(format!("Could not refresh {}: {}", name, error)));
+            }
+            Ok(status)
+        })
+        .collect()
+}
+
+/// Frequencies published for an airport ident (e.g. "KBZN").
+pub fn frequencies_for(ident: &str) -> &'static [Frequency] {
+    FREQUENCIES.get().and_then(|map| map.get(&key(ident))).map(Vec::as_slice).unwrap_or_default()
+}
+
+/// Country by ISO code (e.g. "IT").
+pub fn country(code: &str) -> Option<&'static Country> {
+    COUNTRIES.get()?.get(&key(code))
+}
+
+/// Region by ISO code (e.g. "US-ME").
+pub fn region(code: &str) -> Option<&'static Region> {
+    REGIONS.get()?.get(&key(code))
+}
+
+/// Navaids within `radius_km` of a point, nearest first.
+pub fn navaids_near(lat: f64, lon: f64, radius_km: f64) -> Vec<NearbyNavaid> {
+    navaids_within(NAVAIDS.get().map(Vec::as_slice).unwrap_or_default(), lat, lon, radius_km)
+}
+
+fn navaids_within(navaids: &'static [Navaid], lat: f64, lon: f64, radius_km: f64) -> Vec<NearbyNavaid> {
+    let mut results: Vec<NearbyNavaid> = navaids
+        .iter()
+        .filter_map(|navaid| {
+            let (navaid_lat, navaid_lon) = (navaid.latitude_deg?, navaid.longitude_deg?);
+            let distance_km = haversine_distance(lat, lon, navaid_lat, navaid_lon);
+            (distance_km <= radius_km).then(|| NearbyNavaid {
+                navaid,
+                distance_km,
+                bearing_deg: initial_bearing(lat, lon, navaid_lat, navaid_lon),
+            })
+        })
+        .collect();
+    results.sort_by(|a, b| a.distance_km.total_cmp(&b.distance_km));
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn navaid(ident: &str, type_: &str, frequency_khz: Option<f64>, position: Option<(f64, f64)>) -> Navaid {
+        Navaid {
+            ident: ident.to_string(),
+            name: ident.to_string(),
+            type_: type_.to_string(),
+            frequency_khz,
+            latitude_deg: position.map(|p| p.0),
+            longitude_deg: position.map(|p| p.1),
+            elevation_ft: None,
+            iso_country: "US".to_string(),
+            associated_airport: String::new(),
+        }
+    }
+
+    #[test]
+    fn labels_ndb_frequencies_in_khz() {
+        let label = |type_, khz| navaid("X", type_, khz, None).frequency_label();
+        assert_eq!(label("NDB", Some(353.0)).as_deref(), Some("353 kHz"));
+        assert_eq!(label("NDB-DME", Some(410.0)).as_deref(), Some("410 kHz"));
+        assert_eq!(label("VORTAC", Some(112_200.0)).as_deref(), Some("112.20 MHz"));
+        assert_eq!(label("VOR-DME", Some(115_750.0)).as_deref(), Some("115.75 MHz"));
+        assert_eq!(label("VOR", None), None);
+    }
+
+    #[test]
+    fn lookups_ignore_case_and_whitespace() {
+        assert_eq!(key(" us-me "), "US-ME");
+        let _ = REGIONS.set(HashMap::from([(
+            key("US-ME"),
+            Region { code: "US-ME".to_string(), local_code: "ME".to_string(), name: "Maine".to_string(), iso_country: "US".to_string() },
+        )]));
+        let _ = COUNTRIES.set(HashMap::from([(
+            key("IT"),
+            Country { code: "IT".to_string(), name: "Italy".to_string(), continent: "EU".to_string() },
+        )]));
+        assert_eq!(region("us-me").map(|r| r.name.as_str()), Some("Maine"));
+        assert_eq!(region("US-ME ").map(|r| r.name.as_str()), Some("Maine"));
+        assert_eq!(country("it").map(|c| c.name.as_str()), Some("Italy"));
+        assert!(country("FR").is_none());
+    }
+
+    #[test]
+    fn nearby_navaids_are_nearest_first() {
+        let navaids: &'static [Navaid] = Vec::leak(vec![
+            navaid("FAR", "VOR", Some(112_000.0), Some((45.9, -111.0))),
+            navaid("NEAR", "VORTAC", Some(113_000.0), Some((45.75, -111.1))),
+            navaid("MID", "NDB", Some(350.0), Some((45.8, -110.9))),
+            navaid("GONE", "VOR", Some(114_000.0), Some((47.0, -111.0))),
+            navaid("NOPOS", "VOR", Some(115_000.0), None),
+        ]);
+        let nearby = navaids_within(navaids, 45.7, -111.1, 50.0);
+        let idents: Vec<&str> = nearby.iter().map(|n| n.navaid.ident.as_str()).collect();
+        assert_eq!(idents, ["NEAR", "MID", "FAR"]);
+        assert!(nearby.windows(2).all(|w| w[0].distance_km <= w[1].distance_km));
+        // NEAR is due north of the query point
+        assert!(nearby[0].bearing_deg.abs() < 0.5);
+        assert!(navaids_within(navaids, 45.7, -111.1, 1.0).is_empty());
+    }
+}
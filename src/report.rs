@@ -0,0 +1,292 @@
+// Machine-readable report documents (`--format json`)
+//
+// Every report is wrapped in a `Report` envelope carrying `schema_version`.
+// Within a schema version fields may be added but are never renamed or removed.
+// Measurements keep the units named in their field names (e.g. `temperature_c`).
+
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+
+use crate::addresses::{ExportFormat, ImportOutcome, ImportStatus};
+use crate::airports::{Airport, NearbyAirport};
+use crate::altitude::FieldAltitudes;
+use crate::flight_rules::FlightCategory;
+use crate::geocode::{GeocodedAddress, GeocoderKind, MatchStatus, PlaceDescription};
+use crate::geocode_cache::GeocodeCache;
+use crate::metar::{Metar, Taf};
+use crate::meteo::ComfortMetrics;
+use crate::nws::{HistorySummary, StationStatus};
+use crate::ourairports::{Country, Frequency, NearbyNavaid, Region};
+use crate::runways::{Runway, RunwayWindAnalysis};
+use crate::{Alert, Earthquake, StoredAddress, ForecastPeriod, NearestStation, Observation, TidePrediction, TideStation};
+
+/// Version of the report schemas below.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Envelope around every JSON report.
+#[derive(Debug, Serialize)]
+pub struct Report<T: Serialize> {
+    pub schema_version: u32,
+    /// "conditions", "stations", "observation_history", "forecast", "hourly_forecast", "alerts", "earthquakes", "tides", "airport", "airport_search", "nearby_airports", "addresses", "address",
+    /// "address_import", "address_normalize", "address_export", "geocode_cache", "geocode_cache_clear" or "place"
+    pub report: &'static str,
+    pub generated_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub body: T,
+}
+
+impl<T: Serialize> Report<T> {
+    pub fn new(report: &'static str, body: T) -> Self {
+        Report { schema_version: SCHEMA_VERSION, report, generated_at: Utc::now(), body }
+    }
+}
+
+/// The place a report was requested for.
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    /// Matched street address or "Airport Name (IDENT)"
+    pub label: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// Current conditions at the observation station nearest to a location.
+#[derive(Debug, Serialize)]
+pub struct ConditionsReport {
+    pub location: Location,
+    pub station: NearestStation,
+    pub observation: Observation,
+    /// The observation is older than `[stations] max_observation_age_minutes`
+    pub stale: bool,
+    /// Dew point, wind chill, heat index and feels-like values, each marked reported or computed
+    pub comfort: ComfortMetrics,
+    /// FAA flight category from the observed ceiling and visibility
+    pub flight_category: Option<FlightCategory>,
+    /// Closer or preferred stations passed over because their latest observation was stale;
+    /// when every candidate was stale, the others that were tried
+    pub skipped_stations: Vec<StationStatus>,
+}
+
+/// The observation stations closest to a location, nearest first.
+#[derive(Debug, Serialize)]
+pub struct StationsReport {
+    pub location: Location,
+    pub stations: Vec<StationStatus>,
+    /// Station chosen for a saved address
+    pub preferred_station_id: Option<String>,
+    /// Observations older than this count as stale
+    pub max_observation_age_minutes: u64,
+}
+
+/// A station's recent observations, oldest first, with their summary.
+#[derive(Debug, Serialize)]
+pub struct ObservationHistoryReport {
+    pub location: Location,
+    pub station: NearestStation,
+    pub hours: u32,
+    pub summary: HistorySummary,
+    pub observations: Vec<Observation>,
+}
+
+/// NWS forecast periods for a location (12-hour periods, or hours for "hourly_forecast").
+#[derive(Debug, Serialize)]
+pub struct ForecastReport {
+    pub location: Location,
+    pub station: NearestStation,
+    pub periods: Vec<ForecastPeriod>,
+}
+
+/// NWS alerts in effect at a location, most severe first.
+#[derive(Debug, Serialize)]
+pub struct AlertsReport {
+    pub location: Location,
+    pub alerts: Vec<Alert>,
+}
+
+/// Earthquakes matching a magnitude/time filter, newest first.
+#[derive(Debug, Serialize)]
+pub struct EarthquakeReport {
+    pub min_magnitude: f64,
+    pub hours: u64,
+    pub earthquakes: Vec<Earthquake>,
+}
+
+/// High/low tide predictions at the tide station nearest to a location.
+#[derive(Debug, Serialize)]
+pub struct TideReport {
+    pub location: Location,
+    pub station: TideStation,
+    pub days: Vec<TideDay>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TideDay {
+    pub date: NaiveDate,
+    /// Times are local station time
+    pub predictions: Vec<TidePrediction>,
+}
+
+/// Airports matching a search pattern.
+#[derive(Debug, Serialize)]
+pub struct AirportSearchReport {
+    pub pattern: String,
+    /// Only airports whose latest METAR is in this flight category
+    pub flight_category: Option<FlightCategory>,
+    pub airports: Vec<Airport>,
+}
+
+/// Airport record with weather from the nearest NWS station, when available.
+#[derive(Debug, Serialize)]
+pub struct AirportReport {
+    pub airport: Airport,
+    pub station: Option<NearestStation>,
+    pub observation: Option<Observation>,
+    pub forecast: Vec<ForecastPeriod>,
+    pub alerts: Vec<Alert>,
+    /// FAA flight category from the METAR, else from the NWS observation
+    pub flight_category: Option<FlightCategory>,
+    /// Runways from runways.csv
+    pub runways: Vec<Runway>,
+    /// Headwind and crosswind on each open runway end, best first; `None` when calm or unknown
+    pub runway_winds: Option<RunwayWindAnalysis>,
+    /// Pressure and density altitude from the field elevation and current temperature and altimeter
+    pub altitudes: Option<FieldAltitudes>,
+    pub country: Option<Country>,
+    pub region: Option<Region>,
+    /// Every frequency published for the airport
+    pub frequencies: Vec<Frequency>,
+    /// Navaids within 50 km, nearest first
+    pub navaids: Vec<NearbyNavaid>,
+    /// Latest decoded METAR from aviationweather.gov
+    pub metar: Option<Metar>,
+    /// Current decoded TAF
+    pub taf: Option<Taf>,
+}
+
+/// Airports within a radius of a location, nearest first.
+#[derive(Debug, Serialize)]
+pub struct NearbyAirportsReport {
+    pub location: Location,
+    pub radius_km: f64,
+    pub airports: Vec<NearbyAirport>,
+}
+
+/// The saved addresses, in list order.
+#[derive(Debug, Serialize)]
+pub struct AddressListReport {
+    pub addresses: Vec<StoredAddress>,
+}
+
+/// An address book entry after an add, edit or move, or the entry just deleted.
+#[derive(Debug, Serialize)]
+pub struct AddressReport {
+    pub address: StoredAddress,
+    pub deleted: bool,
+}
+
+/// Per-row results of `addresses import`.
+#[derive(Debug, Serialize)]
+pub struct AddressImportReport {
+    pub path: String,
+    pub added: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub rows: Vec<ImportOutcome>,
+}
+
+impl AddressImportReport {
+    pub fn new(path: &Path, rows: Vec<ImportOutcome>) -> AddressImportReport {
+        let count = |status| rows.iter().filter(|r| r.status == status).count();
+        AddressImportReport {
+            path: path.display().to_string(),
+            added: count(ImportStatus::Added),
+            duplicates: count(ImportStatus::Duplicate),
+            failed: count(ImportStatus::Failed),
+            rows,
+        }
+    }
+}
+
+/// One entry after `addresses normalize`.
+#[derive(Debug, Serialize)]
+pub struct NormalizedAddress {
+    pub id: u32,
+    pub previous_address: String,
+    pub match_status: MatchStatus,
+    /// The match now stored, from Census or the geocoder chain; `None` leaves the entry unchanged
+    pub matched: Option<GeocodedAddress>,
+}
+
+/// Census match status for every entry after `addresses normalize`.
+#[derive(Debug, Serialize)]
+pub struct AddressNormalizeReport {
+    pub addresses: Vec<NormalizedAddress>,
+}
+
+/// What `lookup` found at a coordinate, and the address book entry if it was saved.
+#[derive(Debug, Serialize)]
+pub struct PlaceReport {
+    #[serde(flatten)]
+    pub place: PlaceDescription,
+    pub saved: Option<StoredAddress>,
+}
+
+/// Where `addresses export` wrote the book.
+#[derive(Debug, Serialize)]
+pub struct AddressExportReport {
+    pub path: String,
+    pub format: ExportFormat,
+    pub count: usize,
+}
+
+/// One cached geocoder match.
+#[derive(Debug, Serialize)]
+pub struct GeocodeCacheEntry {
+    /// Normalized query the match is stored under
+    pub query: String,
+    pub matched_address: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub provider: GeocoderKind,
+    pub cached_at: DateTime<Utc>,
+    /// Older than the TTL; only used when the geocoder is unreachable
+    pub expired: bool,
+}
+
+/// Contents of the geocoding cache.
+#[derive(Debug, Serialize)]
+pub struct GeocodeCacheReport {
+    pub path: String,
+    pub ttl_days: u64,
+    pub entries: Vec<GeocodeCacheEntry>,
+}
+
+impl GeocodeCacheReport {
+    pub fn new(cache: &GeocodeCache) -> GeocodeCacheReport {
+        GeocodeCacheReport {
+            path: cache.path().display().to_string(),
+            ttl_days: crate::config::get().geocoding.cache_ttl_days,
+            entries: cache
+                .entries()
+                .map(|(query, entry)| GeocodeCacheEntry {
+                    query: query.clone(),
+                    matched_address: entry.matched_address.clone(),
+                    lat: entry.lat,
+                    lon: entry.lon,
+                    provider: entry.provider,
+                    cached_at: entry.cached_at,
+                    expired: !entry.is_fresh(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Result of `cache clear`.
+#[derive(Debug, Serialize)]
+pub struct GeocodeCacheClearReport {
+    pub removed: usize,
+    pub remaining: usize,
+}
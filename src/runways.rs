@@ -0,0 +1,290 @@
+// OurAirports runways (runways.csv) and headwind/crosswind components
+//
+// Each CSV row is one runway with a low-numbered ("le") and high-numbered ("he") end.
+// Wind components are worked out per runway end from the wind direction (degrees
+// true, as METARs and NWS report it) and the end's true heading.
+
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::dataset_cache::{self, DatasetStatus, RefreshPolicy};
+use crate::{config, AppError};
+
+/// One runway from runways.csv.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Runway {
+    pub airport_ident: String,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub length_ft: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub width_ft: Option<f64>,
+    /// Surface code as published, e.g. "ASP", "CON", "TURF"
+    pub surface: String,
+    #[serde(deserialize_with = "flag")]
+    pub lighted: bool,
+    #[serde(deserialize_with = "flag")]
+    pub closed: bool,
+    /// Low-numbered end, e.g. "12"
+    pub le_ident: String,
+    /// True heading of the low-numbered end
+    #[serde(rename = "le_heading_degT", deserialize_with = "csv::invalid_option")]
+    pub le_heading_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub le_elevation_ft: Option<f64>,
+    /// High-numbered end, e.g. "30"
+    pub he_ident: String,
+    #[serde(rename = "he_heading_degT", deserialize_with = "csv::invalid_option")]
+    pub he_heading_deg: Option<f64>,
+    #[serde(deserialize_with = "csv::invalid_option")]
+    pub he_elevation_ft: Option<f64>,
+}
+
+// "1"/"0" columns
+fn flag<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    let value = String::deserialize(deserializer)?;
+    Ok(matches!(value.trim(), "1" | "true" | "yes"))
+}
+
+/// One usable direction of a runway.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunwayEnd {
+    /// e.g. "30" or "09L"
+    pub ident: String,
+    /// True heading when landing or taking off on this end
+    pub heading_deg: f64,
+}
+
+impl Runway {
+    /// Human-readable name, e.g. "12/30".
+    pub fn name(&self) -> String {
+        match (self.le_ident.is_empty(), self.he_ident.is_empty()) {
+            (false, false) => format!("{}/{}", self.le_ident, self.he_ident),
+            (false, true) => self.le_ident.clone(),
+            _ => self.he_ident.clone(),
+        }
+    }
+
+    /// Both ends with a known heading. Without a published heading, one is estimated
+    /// from the runway number (magnetic, so only accurate to the local variation).
+    pub fn ends(&self) -> Vec<RunwayEnd> {
+        let le = self.le_heading_deg.or_else(|| heading_from_ident(&self.le_ident));
+        let he = self.he_heading_deg.or_else(|| heading_from_ident(&self.he_ident));
+        let (le, he) = match (le, he) {
+            (Some(le), None) => (Some(le), Some((le + 180.0).rem_euclid(360.0))),
+            (None, Some(he)) => (Some((he + 180.0).rem_euclid(360.0)), Some(he)),
+            other => other,
+        };
+        [(&self.le_ident, le), (&self.he_ident, he)]
+            .into_iter()
+            .filter(|(ident, _)| !ident.is_empty())
+            .filter_map(|(ident, heading)| Some(RunwayEnd { ident: ident.clone(), heading_deg: heading? }))
+            .collect()
+    }
+}
+
+// "09L" -> 90°; helipads ("H1") and unnumbered ends have none
+fn heading_from_ident(ident: &str) -> Option<f64> {
+    let number: String = ident.chars().take_while(|c| c.is_ascii_digit()).collect();
+    let number: u32 = number.parse().ok()?;
+    (1..=36).contains(&number).then(|| f64::from(number * 10))
+}
+
+static RUNWAYS: OnceLock<HashMap<String, Vec<Runway>>> = OnceLock::new();
+
+/// Loads runways.csv, from the on-disk cache when it is fresh enough.
+/// Blocking; call before starting the async runtime.
+pub fn init_runways() -> Result<DatasetStatus, AppError> {
+    let (map, status) = load_runways(RefreshPolicy::IfStale)?;
+    RUNWAYS.set(map).map_err(|_| AppError::Config("runways already initialized".to_string()))?;
+    Ok(status)
+}
+
+/// Revalidates the cached runways.csv with the server regardless of its age.
+pub fn refresh_runways() -> Result<DatasetStatus, AppError> {
+    let (_, status) = load_runways(RefreshPolicy::Force)?;
+    if let dataset_cache::DatasetSource::StaleCache { error } = status.source {
+        return Err(AppError::Api(format!("Could not refresh runways.csv: {}", error)));
+    }
+    Ok(status)
+}
+
+fn load_runways(policy: RefreshPolicy) -> Result<(HashMap<String, Vec<Runway>>, DatasetStatus), AppError> {
+    let url = format!("{}/runways.csv", config::endpoints().ourairports);
+    let (bytes, status) = dataset_cache::fetch_dataset("runways.csv", &url, policy)?;
+    let mut map: HashMap<String, Vec<Runway>> = HashMap::new();
+    let mut rdr = csv::Reader::from_reader(bytes.as_slice());
+    for runway in rdr.deserialize::<Runway>().flatten() {
+        map.entry(runway.airport_ident.trim().to_uppercase()).or_default().push(runway);
+    }
+    Ok((map, status))
+}
+
+/// Runways of the airport with this ident (e.g. "KBZN"); empty when unknown or not loaded.
+pub fn runways_for(ident: &str) -> &'static [Runway] {
+    RUNWAYS
+        .get()
+        .and_then(|map| map.get(&ident.trim().to_uppercase()))
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+/// Headwind and crosswind on one runway end. Negative headwind is a tailwind.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RunwayWind {
+    pub runway: String,
+    #[serde(flatten)]
+    pub end: RunwayEnd,
+    pub headwind_kt: f64,
+    /// Always positive; `from_right` says which side it comes from
+    pub crosswind_kt: f64,
+    pub from_right: bool,
+    /// Crosswind component of the gusts, when gusts are reported
+    pub gust_crosswind_kt: Option<f64>,
+    /// Crosswind (or gust crosswind) above `[runways] crosswind_limit_kt`
+    pub exceeds_limit: bool,
+}
+
+/// Wind components on every open runway end for one wind, best runway first.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunwayWindAnalysis {
+    /// Wind direction, degrees true
+    pub wind_direction_deg: f64,
+    pub wind_speed_kt: f64,
+    pub gust_kt: Option<f64>,
+    pub crosswind_limit_kt: f64,
+    /// Runway end ident with the most headwind and least crosswind within the limit
+    pub recommended: Option<String>,
+    pub ends: Vec<RunwayWind>,
+}
+
+/// Headwind and crosswind (positive from the right) for a wind on a heading.
+pub fn wind_components(wind_direction_deg: f64, wind_speed_kt: f64, heading_deg: f64) -> (f64, f64) {
+    let angle = (wind_direction_deg - heading_deg).to_radians();
+    (wind_speed_kt * angle.cos(), wind_speed_kt * angle.sin())
+}
+
+/// The configured crosswind limit in knots.
+pub fn crosswind_limit_kt() -> f64 {
+    config::get().runways.crosswind_limit_kt
+}
+
+impl RunwayWindAnalysis {
+    /// Works out the components on every open runway end. Ends are sorted with
+    /// those within the crosswind limit first, then by most headwind.
+    pub fn new(runways: &[Runway], wind_direction_deg: f64, wind_speed_kt: f64, gust_kt: Option<f64>, crosswind_limit_kt: f64) -> RunwayWindAnalysis {
+        let mut ends: Vec<RunwayWind> = runways
+            .iter()
+            .filter(|runway| !runway.closed)
+            .flat_map(|runway| runway.ends().into_iter().map(move |end| (runway.name(), end)))
+            .map(|(runway, end)| {
+                let (headwind_kt, crosswind_kt) = wind_components(wind_direction_deg, wind_speed_kt, end.heading_deg);
+                let gust_crosswind_kt = gust_kt.map(|gust| wind_components(wind_direction_deg, gust, end.heading_deg).1.abs());
+                let exceeds_limit = crosswind_kt.abs().max(gust_crosswind_kt.unwrap_or(0.0)) > crosswind_limit_kt;
+                RunwayWind {
+                    runway,
+                    end,
+                    headwind_kt,
+                    crosswind_kt: crosswind_kt.abs(),
+                    from_right: crosswind_kt > 0.0,
+                    gust_crosswind_kt,
+                    exceeds_limit,
+                }
+            })
+            .collect();
+        ends.sort_by(|a, b| {
+            a.exceeds_limit
+                .cmp(&b.exceeds_limit)
+                .then_with(|| b.headwind_kt.total_cmp(&a.headwind_kt))
+                .then_with(|| a.crosswind_kt.total_cmp(&b.crosswind_kt))
+        });
+        // Never recommend a tailwind or an end over the crosswind limit
+        let recommended = ends
+            .first()
+            .filter(|end| end.headwind_kt >= 0.0 && !end.exceeds_limit)
+            .map(|end| end.end.ident.clone());
+        RunwayWindAnalysis { wind_direction_deg, wind_speed_kt, gust_kt, crosswind_limit_kt, recommended, ends }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runway(le: &str, le_heading: f64, he: &str, he_heading: f64) -> Runway {
+        Runway {
+            airport_ident: "KTST".to_string(),
+            length_ft: Some(8000.0),
+            width_ft: Some(150.0),
+            surface: "ASP".to_string(),
+            lighted: true,
+            closed: false,
+            le_ident: le.to_string(),
+            le_heading_deg: Some(le_heading),
+            le_elevation_ft: None,
+            he_ident: he.to_string(),
+            he_heading_deg: Some(he_heading),
+            he_elevation_ft: None,
+        }
+    }
+
+    #[test]
+    fn components_of_a_direct_and_a_crossing_wind() {
+        let (head, cross) = wind_components(300.0, 20.0, 300.0);
+        assert!((head - 20.0).abs() < 1e-9 && cross.abs() < 1e-9);
+        // 90° from the right is all crosswind
+        let (head, cross) = wind_components(30.0, 10.0, 300.0);
+        assert!(head.abs() < 1e-9 && (cross - 10.0).abs() < 1e-9);
+        // 30° off the nose: crosswind is half the wind speed
+        let (head, cross) = wind_components(330.0, 20.0, 300.0);
+        assert!((head - 17.32).abs() < 0.01 && (cross - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn tailwind_is_negative_headwind() {
+        let (head, _) = wind_components(120.0, 10.0, 300.0);
+        assert!((head + 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn recommends_the_runway_most_into_the_wind() {
+        let runways = [runway("12", 130.0, "30", 310.0), runway("03", 30.0, "21", 210.0)];
+        let analysis = RunwayWindAnalysis::new(&runways, 300.0, 15.0, Some(25.0), 15.0);
+        assert_eq!(analysis.recommended.as_deref(), Some("30"));
+        assert_eq!(analysis.ends.len(), 4);
+        let best = &analysis.ends[0];
+        assert!(!best.from_right);
+        assert!(!best.exceeds_limit);
+        // Runway 21 takes the wind 90° off its nose; the gusts exceed the limit
+        let r21 = analysis.ends.iter().find(|e| e.end.ident == "21").unwrap();
+        assert!(r21.from_right && r21.exceeds_limit);
+    }
+
+    #[test]
+    fn nothing_is_recommended_over_the_crosswind_limit() {
+        let analysis = RunwayWindAnalysis::new(&[runway("12", 130.0, "30", 310.0)], 40.0, 30.0, None, 15.0);
+        assert!(analysis.ends.iter().all(|end| end.exceeds_limit));
+        assert_eq!(analysis.recommended, None);
+    }
+
+    #[test]
+    fn closed_runways_are_ignored() {
+        let mut closed = runway("12", 130.0, "30", 310.0);
+        closed.closed = true;
+        let analysis = RunwayWindAnalysis::new(&[closed], 300.0, 15.0, None, 15.0);
+        assert!(analysis.ends.is_empty());
+        assert_eq!(analysis.recommended, None);
+    }
+
+    #[test]
+    fn missing_headings_fall_back_to_the_runway_number() {
+        let mut r = runway("09L", 0.0, "27R", 0.0);
+        r.le_heading_deg = None;
+        r.he_heading_deg = None;
+        let ends = r.ends();
+        assert_eq!(ends[0].heading_deg, 90.0);
+        assert_eq!(ends[1].heading_deg, 270.0);
+        assert_eq!(r.name(), "09L/27R");
+    }
+}
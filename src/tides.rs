@@ -0,0 +1,85 @@
+// NOAA CO-OPS tide stations and predictions
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::Serialize;
+
+use crate::{config, haversine_distance, AppError, HTTP_CLIENT};
+
+/// A NOAA station that publishes tide predictions.
+#[derive(Debug, Clone, Serialize)]
+pub struct TideStation {
+    pub id: String,
+    pub name: String,
+    pub state: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+/// A predicted high or low tide, in local station time.
+#[derive(Debug, Clone, Serialize)]
+pub struct TidePrediction {
+    pub time: NaiveDateTime,
+    pub height_ft: f64,
+    pub kind: String, // "H" or "L"
+}
+
+/// Fetches every NOAA station that has tide predictions.
+pub async fn fetch_tide_stations() -> Result<Vec<TideStation>, AppError> {
+    let stations_url = format!("{}/mdapi/prod/webapi/stations.json?type=tidepredictions&format=json", config::endpoints().noaa_tides);
+    let resp = HTTP_CLIENT.get(&stations_url).send().await.map_err(AppError::Network)?;
+    let json: serde_json::Value = resp.json().await.map_err(AppError::Network)?;
+    let mut stations = Vec::new();
+    if let Some(arr) = json["stations"].as_array() {
+        for s in arr {
+            let id = s["id"].as_str().unwrap_or("").to_string();
+            let name = s["name"].as_str().unwrap_or("").to_string();
+            let state = s["state"].as_str().unwrap_or("").to_string();
+            // NOAA returns lat/lon as floats, not strings, if available
+            let lat = s["lat"].as_f64().unwrap_or_else(|| s["lat"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0));
+            let lon = s["lng"].as_f64().unwrap_or_else(|| s["lng"].as_str().and_then(|v| v.parse::<f64>().ok()).unwrap_or(0.0));
+            stations.push(TideStation { id, name, state, lat, lon });
+        }
+    }
+    Ok(stations)
+}
+
+/// Returns the station nearest to `lat`/`lon`, preferring stations in `state`
+/// (two-letter code) and falling back to all stations when none are there.
+pub fn nearest_tide_station<'a>(stations: &'a [TideStation], lat: f64, lon: f64, state: Option<&str>) -> Option<&'a TideStation> {
+    let in_state = |s: &&TideStation| state.is_some_and(|st| !st.is_empty() && s.state.eq_ignore_ascii_case(st));
+    let candidates: Vec<&TideStation> = if stations.iter().any(|s| in_state(&s)) {
+        stations.iter().filter(in_state).collect()
+    } else {
+        stations.iter().collect()
+    };
+    candidates.into_iter().min_by(|a, b| {
+        let da = haversine_distance(lat, lon, a.lat, a.lon);
+        let db = haversine_distance(lat, lon, b.lat, b.lon);
+        da.total_cmp(&db)
+    })
+}
+
+/// Fetches the high/low tide predictions for one day at a station.
+pub async fn fetch_tide_predictions(station_id: &str, day: NaiveDate) -> Result<Vec<TidePrediction>, AppError> {
+    let url = format!("{}/api/prod/datagetter?product=predictions&application=NOS.COOPS.TAC.WL&begin_date={}&end_date={}&datum=MLLW&station={}&time_zone=lst_ldt&units=english&interval=hilo&format=json", config::endpoints().noaa_tides, day.format("%Y%m%d"), day.format("%Y%m%d"), station_id);
+    let resp = HTTP_CLIENT.get(&url).send().await.map_err(AppError::Network)?;
+    let json: serde_json::Value = resp.json().await.map_err(AppError::Network)?;
+    let mut predictions = Vec::new();
+    if let Some(preds) = json["predictions"].as_array() {
+        for p in preds {
+            let t = p["t"].as_str().unwrap_or("");
+            let (Ok(time), Some(height_ft)) = (
+                NaiveDateTime::parse_from_str(t, "%Y-%m-%d %H:%M"),
+                p["v"].as_str().and_then(|v| v.parse::<f64>().ok()),
+            ) else {
+                continue;
+            };
+            predictions.push(TidePrediction {
+                time,
+                height_ft,
+                kind: p["type"].as_str().unwrap_or("").to_string(),
+            });
+        }
+    }
+    Ok(predictions)
+}
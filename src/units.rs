@@ -0,0 +1,216 @@
+// Unit systems for displaying measurements
+//
+// Fetch functions always return SI values (°C, m/s, metres, pascals); these
+// helpers format them for the unit system the user picked.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::AppError;
+
+/// How measurements are shown to the user.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnitSystem {
+    /// °F, mph, inHg, feet and miles
+    #[default]
+    Imperial,
+    /// °C, km/h, hPa, metres and kilometres
+    Metric,
+    /// °C, knots, inHg and hPa, feet, statute and nautical miles
+    Aviation,
+}
+
+impl FromStr for UnitSystem {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "imperial" => Ok(UnitSystem::Imperial),
+            "metric" => Ok(UnitSystem::Metric),
+            "aviation" => Ok(UnitSystem::Aviation),
+            other => Err(AppError::UserInput(format!("Unknown unit system '{}'. Use imperial, metric or aviation.", other))),
+        }
+    }
+}
+
+impl fmt::Display for UnitSystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UnitSystem::Imperial => "imperial",
+            UnitSystem::Metric => "metric",
+            UnitSystem::Aviation => "aviation",
+        })
+    }
+}
+
+pub fn celsius_to_fahrenheit(c: f64) -> f64 {
+    c * 9.0 / 5.0 + 32.0
+}
+
+pub fn fahrenheit_to_celsius(f: f64) -> f64 {
+    (f - 32.0) * 5.0 / 9.0
+}
+
+const MPS_TO_MPH: f64 = 2.23694;
+const MPS_TO_KMH: f64 = 3.6;
+pub const MPS_TO_KNOTS: f64 = 1.943844;
+pub const PA_TO_INHG: f64 = 0.0002953;
+pub const METRES_TO_FEET: f64 = 3.28084;
+pub const METRES_TO_MILES: f64 = 0.000621371;
+const KM_TO_NAUTICAL_MILES: f64 = 0.539957;
+
+impl UnitSystem {
+    /// Temperature from °C, e.g. "21.0 °C"
+    pub fn temperature(&self, celsius: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.1} °F", celsius_to_fahrenheit(celsius)),
+            UnitSystem::Metric | UnitSystem::Aviation => format!("{:.1} °C", celsius),
+        }
+    }
+
+    /// A signed temperature difference from °C, e.g. "+12 °C" above ISA
+    pub fn temperature_difference(&self, celsius: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:+.0} °F", celsius * 9.0 / 5.0),
+            UnitSystem::Metric | UnitSystem::Aviation => format!("{:+.0} °C", celsius),
+        }
+    }
+
+    /// A temperature NWS already expressed in `unit` ("F" or "C"), e.g. forecast periods
+    pub fn forecast_temperature(&self, value: f64, unit: &str) -> String {
+        let celsius = if unit.eq_ignore_ascii_case("F") { fahrenheit_to_celsius(value) } else { value };
+        match self {
+            UnitSystem::Imperial => format!("{:.0}°F", celsius_to_fahrenheit(celsius)),
+            UnitSystem::Metric | UnitSystem::Aviation => format!("{:.0}°C", celsius),
+        }
+    }
+
+    /// Speed from m/s, e.g. "17.3 mph"
+    pub fn speed(&self, mps: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.1} mph", mps * MPS_TO_MPH),
+            UnitSystem::Metric => format!("{:.1} km/h", mps * MPS_TO_KMH),
+            UnitSystem::Aviation => format!("{:.0} kt", mps * MPS_TO_KNOTS),
+        }
+    }
+
+    /// Rewrites an NWS forecast wind phrase such as "5 to 10 mph" into this unit system
+    pub fn forecast_wind_speed(&self, phrase: &str) -> String {
+        if *self == UnitSystem::Imperial {
+            return phrase.to_string();
+        }
+        let Some(numbers) = phrase.strip_suffix(" mph") else {
+            return phrase.to_string();
+        };
+        let (unit, factor) = match self {
+            UnitSystem::Metric => ("km/h", MPS_TO_KMH / MPS_TO_MPH),
+            _ => ("kt", MPS_TO_KNOTS / MPS_TO_MPH),
+        };
+        let converted: Vec<String> = numbers
+            .split_whitespace()
+            .map(|word| match word.parse::<f64>() {
+                Ok(mph) => format!("{:.0}", mph * factor),
+                Err(_) => word.to_string(),
+            })
+            .collect();
+        format!("{} {}", converted.join(" "), unit)
+    }
+
+    /// Pressure from pascals, e.g. "29.88 inHg" or "1012 hPa"
+    pub fn pressure(&self, pa: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.2} inHg", pa * PA_TO_INHG),
+            UnitSystem::Metric => format!("{:.0} hPa", pa / 100.0),
+            UnitSystem::Aviation => format!("{:.2} inHg ({:.0} hPa)", pa * PA_TO_INHG, pa / 100.0),
+        }
+    }
+
+    /// Heights such as cloud bases and elevations, from metres
+    pub fn height(&self, metres: f64) -> String {
+        match self {
+            UnitSystem::Imperial | UnitSystem::Aviation => format!("{:.0} ft", metres * METRES_TO_FEET),
+            UnitSystem::Metric => format!("{:.0} m", metres),
+        }
+    }
+
+    /// Visibility from metres, e.g. "10.0 mi" or "10.0 SM"
+    pub fn visibility(&self, metres: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.1} mi", metres * METRES_TO_MILES),
+            UnitSystem::Metric => format!("{:.1} km", metres / 1000.0),
+            UnitSystem::Aviation => format!("{:.1} SM", metres * METRES_TO_MILES),
+        }
+    }
+
+    /// Distances between places, from kilometres
+    pub fn distance(&self, km: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.1} mi", km * 1000.0 * METRES_TO_MILES),
+            UnitSystem::Metric => format!("{:.1} km", km),
+            UnitSystem::Aviation => format!("{:.1} NM", km * KM_TO_NAUTICAL_MILES),
+        }
+    }
+
+    /// Earthquake depths, from kilometres; seismology uses km outside imperial units
+    pub fn depth(&self, km: f64) -> String {
+        match self {
+            UnitSystem::Imperial => format!("{:.1} mi", km * 1000.0 * METRES_TO_MILES),
+            UnitSystem::Metric | UnitSystem::Aviation => format!("{:.1} km", km),
+        }
+    }
+
+    /// Tide heights, which NOAA reports in feet for `units=english`
+    pub fn tide_height(&self, feet: f64) -> String {
+        match self {
+            UnitSystem::Imperial | UnitSystem::Aviation => format!("{:.3} ft", feet),
+            UnitSystem::Metric => format!("{:.3} m", feet / METRES_TO_FEET),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [UnitSystem; 3] = [UnitSystem::Imperial, UnitSystem::Metric, UnitSystem::Aviation];
+
+    fn each(format: impl Fn(UnitSystem) -> String) -> [String; 3] {
+        ALL.map(format)
+    }
+
+    #[test]
+    fn parses_unit_systems() {
+        for units in ALL {
+            assert_eq!(units.to_string().parse::<UnitSystem>().unwrap(), units);
+        }
+        assert_eq!(" Metric ".parse::<UnitSystem>().unwrap(), UnitSystem::Metric);
+        assert!("si".parse::<UnitSystem>().is_err());
+    }
+
+    #[test]
+    fn converts_forecast_wind_phrases() {
+        assert_eq!(each(|u| u.forecast_wind_speed("5 to 10 mph")), ["5 to 10 mph", "8 to 16 km/h", "4 to 9 kt"]);
+        assert_eq!(each(|u| u.forecast_wind_speed("15 mph")), ["15 mph", "24 km/h", "13 kt"]);
+        // Phrases without a trailing " mph" are passed through unchanged
+        for phrase in ["", "Calm", "10 to 15 km/h", "5 mph gusts"] {
+            assert_eq!(each(|u| u.forecast_wind_speed(phrase)), [phrase; 3]);
+        }
+    }
+
+    #[test]
+    fn formats_each_quantity() {
+        assert_eq!(each(|u| u.temperature(21.0)), ["69.8 °F", "21.0 °C", "21.0 °C"]);
+        assert_eq!(each(|u| u.temperature_difference(12.0)), ["+22 °F", "+12 °C", "+12 °C"]);
+        assert_eq!(each(|u| u.forecast_temperature(70.0, "F")), ["70°F", "21°C", "21°C"]);
+        assert_eq!(each(|u| u.speed(10.0)), ["22.4 mph", "36.0 km/h", "19 kt"]);
+        assert_eq!(each(|u| u.pressure(101_325.0)), ["29.92 inHg", "1013 hPa", "29.92 inHg (1013 hPa)"]);
+        assert_eq!(each(|u| u.height(1000.0)), ["3281 ft", "1000 m", "3281 ft"]);
+        assert_eq!(each(|u| u.visibility(16_093.44)), ["10.0 mi", "16.1 km", "10.0 SM"]);
+        assert_eq!(each(|u| u.distance(100.0)), ["62.1 mi", "100.0 km", "54.0 NM"]);
+        assert_eq!(each(|u| u.depth(10.0)), ["6.2 mi", "10.0 km", "10.0 km"]);
+        assert_eq!(each(|u| u.tide_height(3.28084)), ["3.281 ft", "1.000 m", "3.281 ft"]);
+    }
+}